@@ -0,0 +1,206 @@
+//! Contains the callback messages that pool implementations send to the
+//! calling contract, for checks that must run after other messages of the
+//! response have been executed.
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_binary, CosmosMsg, Deps, Env, Event, StdResult, WasmMsg};
+use cw_asset::{Asset, AssetList};
+
+use crate::error::CwDexError;
+use crate::response::CwDexResponse;
+
+/// Callback messages sent by the pool implementations to the calling
+/// contract itself.
+///
+/// Callbacks are only sent where the DEX has no native way to enforce a
+/// minimum output:
+/// - Astroport: withdrawing liquidity, and providing liquidity to stable and
+///   concentrated pairs.
+/// - Osmosis concentrated liquidity: withdrawing liquidity.
+///
+/// Contracts using these operations with a non-zero `min_out` must add a
+/// `CwDexCallback(CwDexCallbackMsg)` variant to their `ExecuteMsg` and pass
+/// the message to [`CwDexCallbackMsg::execute`], otherwise the callback
+/// fails to parse and the transaction reverts. The callbacks only query
+/// state, so they are safe for anyone to execute.
+///
+/// # Ordering
+///
+/// [`CwDexCallbackMsg::AssertMinOut`] compares the balances of the contract
+/// when the callback is executed to the balances queried when the
+/// [`CwDexResponse`] was built, not when the action is executed. Any tokens
+/// received by the contract between the two, such as from messages added to
+/// the response before the ones returned by cw-dex, count towards `min_out`.
+/// Contracts should therefore add the messages of the [`CwDexResponse`]
+/// before any other message that sends tokens of the `min_out` assets to the
+/// contract.
+#[cw_serde]
+pub enum CwDexCallbackMsg {
+    /// Asserts that the balance of the contract of each asset in `min_out`
+    /// grew by at least the amount of the asset since `balances_before` was
+    /// queried.
+    AssertMinOut {
+        /// The balances of the contract before the action
+        balances_before: AssetList,
+        /// The minimum amount of each asset to receive from the action
+        min_out: AssetList,
+    },
+}
+
+/// The variant of the calling contract's `ExecuteMsg` that callbacks are sent
+/// as.
+#[cw_serde]
+enum CallbackExecuteMsg {
+    CwDexCallback(CwDexCallbackMsg),
+}
+
+impl CwDexCallbackMsg {
+    /// Returns a message asserting that the balances of the contract grow by
+    /// at least `min_out` between now and when the message is executed, or
+    /// `None` if `min_out` has no non-zero amounts.
+    ///
+    /// The balances are queried when the message is built, so any message
+    /// executed before the returned message counts towards `min_out`. See
+    /// the [ordering](CwDexCallbackMsg#ordering) constraint.
+    pub fn assert_min_out(
+        deps: Deps,
+        env: &Env,
+        min_out: &AssetList,
+    ) -> StdResult<Option<CosmosMsg>> {
+        let min_out: Vec<Asset> = min_out
+            .into_iter()
+            .filter(|min| !min.amount.is_zero())
+            .cloned()
+            .collect();
+        if min_out.is_empty() {
+            return Ok(None);
+        }
+
+        let balances_before = min_out
+            .iter()
+            .map(|min| {
+                let balance = min
+                    .info
+                    .query_balance(&deps.querier, &env.contract.address)?;
+                Ok(Asset::new(min.info.clone(), balance))
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        CwDexCallbackMsg::AssertMinOut {
+            balances_before: balances_before.into(),
+            min_out: min_out.into(),
+        }
+        .into_cosmos_msg(env)
+        .map(Some)
+    }
+
+    /// Converts the callback into a message executing it on the contract
+    /// itself.
+    pub fn into_cosmos_msg(self, env: &Env) -> StdResult<CosmosMsg> {
+        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            msg: to_binary(&CallbackExecuteMsg::CwDexCallback(self))?,
+            funds: vec![],
+        }))
+    }
+
+    /// Executes the callback. Returns an error if the assertion of the
+    /// callback fails.
+    pub fn execute(self, deps: Deps, env: &Env) -> Result<CwDexResponse, CwDexError> {
+        match self {
+            CwDexCallbackMsg::AssertMinOut {
+                balances_before,
+                min_out,
+            } => {
+                let mut event = Event::new("apollo/cw-dex/assert_min_out");
+                for min in min_out.into_iter() {
+                    let before = balances_before
+                        .find(&min.info)
+                        .map(|asset| asset.amount)
+                        .unwrap_or_default();
+                    let balance = min
+                        .info
+                        .query_balance(&deps.querier, &env.contract.address)?;
+                    let received = balance.saturating_sub(before);
+                    if received < min.amount {
+                        return Err(CwDexError::MinOutNotReceived {
+                            min_out: min.amount,
+                            received,
+                        });
+                    }
+                    event = event.add_attribute(min.info.to_string(), received);
+                }
+                Ok(CwDexResponse::new().add_event(event))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies_with_balance, mock_env};
+    use cosmwasm_std::{coin, from_binary, Uint128};
+
+    use super::*;
+
+    #[test]
+    fn test_assert_min_out() {
+        let env = mock_env();
+        let mut deps = mock_dependencies_with_balance(&[coin(100, "uatom")]);
+        let min_out: AssetList = vec![
+            Asset::native("uatom", 50u128),
+            Asset::native("uosmo", 20u128),
+        ]
+        .into();
+        let no_min_out: AssetList = vec![Asset::native("uatom", 0u128)].into();
+
+        assert_eq!(
+            CwDexCallbackMsg::assert_min_out(deps.as_ref(), &env, &no_min_out).unwrap(),
+            None
+        );
+
+        let msg = CwDexCallbackMsg::assert_min_out(deps.as_ref(), &env, &min_out)
+            .unwrap()
+            .unwrap();
+        let callback = match msg {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr, msg, ..
+            }) => {
+                assert_eq!(contract_addr, env.contract.address.to_string());
+                match from_binary(&msg).unwrap() {
+                    CallbackExecuteMsg::CwDexCallback(callback) => callback,
+                }
+            }
+            _ => panic!("unexpected msg"),
+        };
+        assert_eq!(
+            callback,
+            CwDexCallbackMsg::AssertMinOut {
+                balances_before: vec![
+                    Asset::native("uatom", 100u128),
+                    Asset::native("uosmo", 0u128),
+                ]
+                .into(),
+                min_out,
+            }
+        );
+
+        deps.querier.update_balance(
+            env.contract.address.clone(),
+            vec![coin(150, "uatom"), coin(19, "uosmo")],
+        );
+        assert_eq!(
+            callback.clone().execute(deps.as_ref(), &env).unwrap_err(),
+            CwDexError::MinOutNotReceived {
+                min_out: Uint128::new(20),
+                received: Uint128::new(19),
+            }
+        );
+
+        deps.querier.update_balance(
+            env.contract.address.clone(),
+            vec![coin(150, "uatom"), coin(20, "uosmo")],
+        );
+        assert!(callback.execute(deps.as_ref(), &env).is_ok());
+    }
+}
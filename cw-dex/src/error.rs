@@ -4,7 +4,7 @@
 use std::num::TryFromIntError;
 
 use cosmwasm_std::{DivideByZeroError, OverflowError, StdError, Uint128};
-use cw_asset::Asset;
+use cw_asset::{Asset, AssetInfo};
 use thiserror::Error;
 
 /// ## Description
@@ -77,6 +77,105 @@ pub enum CwDexError {
         /// The actual amount of tokens received
         received: Uint128,
     },
+
+    /// More tokens than the maximum allowed are needed for the action
+    #[error("Maximum amount of tokens in exceeded. Max in: {max_in}, required: {required}")]
+    MaxInExceeded {
+        /// The maximum amount of tokens the user allowed to spend
+        max_in: Uint128,
+        /// The amount of tokens required by the action
+        required: Uint128,
+    },
+
+    /// The asset is not one of the assets of the pool
+    #[error("Asset {asset} is not in the pool")]
+    AssetNotInPool {
+        /// The asset in question
+        asset: AssetInfo,
+    },
+
+    /// The pool does not have the number of assets the operation supports
+    #[error("Expected a pool with {expected} assets, found {found}")]
+    InvalidPoolAssetCount {
+        /// The number of assets the operation supports
+        expected: usize,
+        /// The number of assets of the pool
+        found: usize,
+    },
+
+    /// An iterative computation did not converge
+    #[error("Failed to converge when computing {what}")]
+    FailedToConverge {
+        /// The value that was being computed
+        what: String,
+    },
+
+    /// The pool implementation does not support the operation
+    #[error("Operation {operation} is not supported by this pool")]
+    UnsupportedPoolOperation {
+        /// The operation that was attempted
+        operation: String,
+    },
+
+    /// The staking implementation does not support the operation
+    #[error("Operation {operation} is not supported by {implementation} staking")]
+    UnsupportedStakingOperation {
+        /// The operation that was attempted
+        operation: String,
+        /// The staking implementation
+        implementation: String,
+    },
+
+    /// A submessage whose reply is being parsed failed
+    #[error("Submessage failed: {reason}")]
+    SubMsgFailed {
+        /// The error returned by the submessage
+        reason: String,
+    },
+
+    /// A submessage reply is expected to contain response data
+    #[error("No data in submessage reply")]
+    NoReplyData {},
+
+    /// The response of a submessage reply could not be decoded
+    #[error("Failed to decode {msg_type} from submessage reply: {reason}")]
+    ReplyDecode {
+        /// The type that was being decoded
+        msg_type: String,
+        /// The decoding error
+        reason: String,
+    },
+
+    /// An event attribute expected in a submessage reply is missing
+    #[error("Attribute {attribute} of event {event} not found in submessage reply")]
+    MissingReplyAttribute {
+        /// The type of the event
+        event: String,
+        /// The key of the attribute
+        attribute: String,
+    },
+
+    /// The swap route is empty or its operations are not connected
+    #[error("Invalid swap route: {reason}")]
+    InvalidSwapRoute {
+        /// The reason the route is invalid
+        reason: String,
+    },
+
+    /// No reference price was given for an asset of the pool
+    #[error("No reference price given for asset {asset}")]
+    MissingPrice {
+        /// The asset without a price
+        asset: AssetInfo,
+    },
+
+    /// A time weighted average price could not be computed for the
+    /// requested window
+    #[error("Time weighted average price unavailable: {reason}")]
+    TwapUnavailable {
+        /// The reason the price could not be computed
+        reason: String,
+    },
 }
 
 impl From<CwDexError> for StdError {
@@ -1,13 +1,21 @@
 #![allow(missing_docs)]
 
-use super::msg::{Config, FactoryQueryMsg, FeeInfo, FeeInfoResponse, PairType};
+use super::msg::{
+    ConcentratedPoolParams, Config, FactoryQueryMsg, FeeInfo, FeeInfoResponse, PairFactoryConfig,
+    PairInfo, PairType, PairsResponse,
+};
+use crate::CwDexError;
+use apollo_utils::iterators::IntoElementwise;
 use cosmwasm_std::{
-    to_binary, Addr, Decimal, Env, QuerierWrapper, QueryRequest, StdResult, Uint128, WasmQuery,
+    to_binary, Addr, Decimal, Decimal256, Env, QuerierWrapper, QueryRequest, StdError, StdResult,
+    Uint128, WasmQuery,
 };
 use cw20::{Cw20QueryMsg, TokenInfoResponse};
 use cw_asset::astroport::AstroAssetInfo;
+use cw_asset::AssetInfo;
 use cw_storage_plus::Item;
 use std::cmp::Ordering;
+use std::str::FromStr;
 
 pub const NATIVE_TOKEN_PRECISION: u8 = 6;
 pub const MAX_ALLOWED_SLIPPAGE: &str = "0.5";
@@ -37,9 +45,10 @@ pub fn checked_u8_mul(a: &U256, b: u8) -> Option<U256> {
 // ============================================================
 //
 
-pub const N_COINS: u8 = 2;
 pub const AMP_PRECISION: u64 = 100;
 pub const ITERATIONS: u8 = 32;
+/// The fixed point precision of [`Decimal`]
+pub const DECIMAL_FRACTIONAL: u128 = 1_000_000_000_000_000_000;
 
 /// Compute actual amplification coefficient (A)
 pub fn compute_current_amp(config: &Config, env: &Env) -> StdResult<u64> {
@@ -81,41 +90,111 @@ pub fn adjust_precision(
         ))?,
     })
 }
-/// Compute stable swap invariant (D)
+/// Compute stable swap invariant (D) of a pool with `amounts.len()` assets
 /// Equation:
 /// A * sum(x_i) * n**n + D = A * D * n**n + D**(n+1) / (n**n * prod(x_i))
-pub fn compute_d(leverage: u64, amount_a: u128, amount_b: u128) -> Option<u128> {
-    let amount_a_times_coins =
-        checked_u8_mul(&U256::from(amount_a), N_COINS)?.checked_add(U256::one())?;
-    let amount_b_times_coins =
-        checked_u8_mul(&U256::from(amount_b), N_COINS)?.checked_add(U256::one())?;
-    let sum_x = amount_a.checked_add(amount_b)?; // sum(x_i), a.k.a S
+pub fn compute_d(leverage: u64, amounts: &[u128]) -> Result<u128, CwDexError> {
+    compute_d_with_max_iterations(leverage, amounts, ITERATIONS)
+}
+
+/// Computes the stable swap invariant like [`compute_d`], returning
+/// [`CwDexError::FailedToConverge`] if D does not converge within
+/// `max_iterations` steps of Newton's method.
+fn compute_d_with_max_iterations(
+    leverage: u64,
+    amounts: &[u128],
+    max_iterations: u8,
+) -> Result<u128, CwDexError> {
+    if leverage < AMP_PRECISION {
+        return Err(StdError::generic_err(format!(
+            "Leverage {} is below the amplification precision {}",
+            leverage, AMP_PRECISION
+        ))
+        .into());
+    }
+    let overflow = || StdError::generic_err("Overflow when computing stable swap invariant");
+
+    let n_coins = u8::try_from(amounts.len())?;
+    let amounts_times_coins = amounts
+        .iter()
+        .map(|amount| checked_u8_mul(&U256::from(*amount), n_coins)?.checked_add(U256::one()))
+        .collect::<Option<Vec<U256>>>()
+        .ok_or_else(overflow)?;
+    // sum(x_i), a.k.a S
+    let sum_x = amounts
+        .iter()
+        .try_fold(0u128, |sum, amount| sum.checked_add(*amount))
+        .ok_or_else(overflow)?;
     if sum_x == 0 {
-        Some(0)
-    } else {
-        let mut d_previous: U256;
-        let mut d: U256 = sum_x.into();
+        return Ok(0);
+    }
 
-        // Newton's method to approximate D
-        for _ in 0..ITERATIONS {
-            let mut d_product = d;
-            d_product = d_product
-                .checked_mul(d)?
-                .checked_div(amount_a_times_coins)?;
+    let mut d_previous: U256;
+    let mut d: U256 = sum_x.into();
+
+    // Newton's method to approximate D
+    for _ in 0..max_iterations {
+        let mut d_product = d;
+        for amount_times_coins in &amounts_times_coins {
             d_product = d_product
-                .checked_mul(d)?
-                .checked_div(amount_b_times_coins)?;
-            d_previous = d;
-            // d = (leverage * sum_x + d_p * n_coins) * d / ((leverage - 1) * d + (n_coins +
-            // 1) * d_p);
-            d = calculate_step(&d, leverage, sum_x, &d_product)?;
-            // Equality with the precision of 1
-            if d == d_previous {
-                break;
-            }
+                .checked_mul(d)
+                .and_then(|p| p.checked_div(*amount_times_coins))
+                .ok_or_else(overflow)?;
+        }
+        d_previous = d;
+        // d = (leverage * sum_x + d_p * n_coins) * d / ((leverage - 1) * d + (n_coins +
+        // 1) * d_p);
+        d = calculate_step(&d, leverage, sum_x, &d_product, n_coins).ok_or_else(overflow)?;
+        // Equality with the precision of 1
+        if d == d_previous {
+            return u128::try_from(d).map_err(|_| overflow().into());
         }
-        u128::try_from(d).ok()
     }
+
+    Err(CwDexError::FailedToConverge {
+        what: "stable swap invariant D".to_string(),
+    })
+}
+
+/// Compute the marginal price of the asset at index `base` denominated in the
+/// asset at index `quote` of a stable swap pool with balances `amounts`, i.e.
+/// the ratio of the partial derivatives of the invariant with respect to the
+/// base and quote amounts:
+/// (A * n**n + D_P / x_base) / (A * n**n + D_P / x_quote),
+/// where D_P = D**(n+1) / (n**n * prod(x_i)).
+pub fn compute_stable_spot_price(
+    leverage: u64,
+    amounts: &[u128],
+    base: usize,
+    quote: usize,
+) -> Option<Decimal> {
+    let n_coins = u8::try_from(amounts.len()).ok()?;
+    let d = U256::from(compute_d(leverage, amounts).ok()?);
+    let x_base = U256::from(*amounts.get(base)?);
+    let x_quote = U256::from(*amounts.get(quote)?);
+
+    let mut d_product = d;
+    for amount in amounts {
+        d_product = d_product
+            .checked_mul(d)?
+            .checked_div(checked_u8_mul(&U256::from(*amount), n_coins)?)?;
+    }
+    let d_product_mul = d_product.checked_mul(AMP_PRECISION.into())?;
+
+    let numerator = U256::from(leverage)
+        .checked_mul(x_base)?
+        .checked_add(d_product_mul)?;
+    let denominator = U256::from(leverage)
+        .checked_mul(x_quote)?
+        .checked_add(d_product_mul)?;
+
+    let price = numerator
+        .checked_mul(U256::from(DECIMAL_FRACTIONAL))?
+        .checked_div(denominator)?
+        .checked_mul(x_quote)?
+        .checked_div(x_base)?;
+
+    u128::try_from(price).ok().map(Decimal::raw)
 }
 
 /// d = (leverage * sum_x + d_product * n_coins) * initial_d / ((leverage - 1) *
@@ -125,27 +204,330 @@ pub fn calculate_step(
     leverage: u64,
     sum_x: u128,
     d_product: &U256,
+    n_coins: u8,
 ) -> Option<U256> {
     let leverage_mul = U256::from(leverage).checked_mul(sum_x.into())? / AMP_PRECISION;
-    let d_p_mul = checked_u8_mul(d_product, N_COINS)?;
+    let d_p_mul = checked_u8_mul(d_product, n_coins)?;
 
     let l_val = leverage_mul.checked_add(d_p_mul)?.checked_mul(*initial_d)?;
 
     let leverage_sub =
         initial_d.checked_mul((leverage.checked_sub(AMP_PRECISION)?).into())? / AMP_PRECISION;
-    let n_coins_sum = checked_u8_mul(d_product, N_COINS.checked_add(1)?)?;
+    let n_coins_sum = checked_u8_mul(d_product, n_coins.checked_add(1)?)?;
 
     let r_val = leverage_sub.checked_add(n_coins_sum)?;
 
     l_val.checked_div(r_val)
 }
 
+/// Returns the amount of an asset to swap in an XYK pair with `reserve_in` of
+/// the asset before providing liquidity with `amount` of it, such that the
+/// remaining amount and the return of the swap are in the same ratio as the
+/// reserves after the swap. Astroport charges `fee` on the return of the swap,
+/// which gives the equation:
+/// s^2 + ((2 - fee) * reserve_in - fee * amount) * s - amount * reserve_in = 0
+pub fn xyk_zap_in_swap_amount(
+    amount: Uint128,
+    reserve_in: Uint128,
+    fee: Decimal256,
+) -> StdResult<Uint128> {
+    let amount = Decimal256::from_ratio(amount, 1u128);
+    let reserve_in = Decimal256::from_ratio(reserve_in, 1u128);
+    let two = Decimal256::from_ratio(2u128, 1u128);
+
+    // The linear coefficient b = b_pos - b_neg may be negative
+    let b_pos = two.checked_sub(fee)?.checked_mul(reserve_in)?;
+    let b_neg = fee.checked_mul(amount)?;
+    let b_abs = if b_pos > b_neg {
+        b_pos - b_neg
+    } else {
+        b_neg - b_pos
+    };
+    let sqrt_discriminant = b_abs
+        .checked_mul(b_abs)?
+        .checked_add(
+            Decimal256::from_ratio(4u128, 1u128)
+                .checked_mul(amount)?
+                .checked_mul(reserve_in)?,
+        )?
+        .sqrt();
+
+    let numerator = if b_pos > b_neg {
+        sqrt_discriminant.checked_sub(b_abs)?
+    } else {
+        sqrt_discriminant.checked_add(b_abs)?
+    };
+
+    decimal256_to_uint(numerator * Decimal256::percent(50), 0)
+}
+
+/// Returns the slippage tolerance to pass to an XYK pair when providing
+/// liquidity, such that the pair rejects the deposit if it would mint less
+/// than `min_out` LP tokens.
+///
+/// The pair asserts that the ratio of the deposits deviates at most by the
+/// slippage tolerance from the ratio of the reserves, and mints LP tokens for
+/// the smaller of the deposits relative to the reserves. With
+/// `max_lp_amount` being the amount of LP tokens the larger of the deposits
+/// would mint, the assertion passes exactly when at least `min_out` LP tokens
+/// are minted. The tolerance is capped at the maximum the pair accepts.
+pub fn xyk_slippage_tolerance(min_out: Uint128, max_lp_amount: Uint128) -> StdResult<Decimal> {
+    let max_allowed = Decimal::from_str(MAX_ALLOWED_SLIPPAGE)?;
+    if max_lp_amount.is_zero() {
+        return Ok(max_allowed);
+    }
+    if min_out >= max_lp_amount {
+        return Ok(Decimal::zero());
+    }
+    Ok((Decimal::one() - Decimal::from_ratio(min_out, max_lp_amount)).min(max_allowed))
+}
+
+/// The number of decimal places of the prices accumulated by Astroport pairs
+pub const TWAP_PRECISION: u8 = 6;
+
+/// Returns the average price between two cumulative prices of an Astroport
+/// pair queried `elapsed` seconds apart.
+///
+/// The pair adds the price multiplied by the seconds elapsed to the cumulative
+/// price on every action, wrapping around on overflow.
+pub fn twap_from_cumulative_prices(
+    start: Uint128,
+    end: Uint128,
+    elapsed: u64,
+) -> StdResult<Decimal> {
+    let denominator =
+        Uint128::from(elapsed).checked_mul(Uint128::from(10u128.pow(TWAP_PRECISION.into())))?;
+    Decimal::checked_from_ratio(end.wrapping_sub(start), denominator)
+        .map_err(|e| StdError::generic_err(e.to_string()))
+}
+
+//
+// ============================================================
+// ====== Helper functions for concentrated implementation ====
+// ============================================================
+//
+// Ported from the Astroport passive concentrated liquidity pair:
+// <https://github.com/astroport-fi/astroport-core/tree/v2.8.0/contracts/pair_concentrated/src>
+
+/// The pair type of Astroport passive concentrated liquidity pairs
+pub const CONCENTRATED_PAIR_TYPE: &str = "concentrated";
+/// The amount of LP tokens locked in the pair on the first provide
+pub const MINIMUM_LIQUIDITY_AMOUNT: u128 = 1_000;
+/// The maximum number of iterations of the Newton's method for D
+const CONCENTRATED_MAX_ITER: u8 = 64;
+/// The tolerance of the Newton's method for D, 10^-14
+const CONCENTRATED_TOL: Decimal256 = Decimal256::raw(10_000);
+
+/// Converts `value` in units of a token to an amount of the token with
+/// `precision` decimals, rounding down.
+pub fn decimal256_to_uint(value: Decimal256, precision: u8) -> StdResult<Uint128> {
+    let amount = value.checked_mul(Decimal256::from_ratio(10u128.pow(precision.into()), 1u128))?;
+    Ok(Uint128::try_from(
+        amount.atomics() / Decimal256::one().atomics(),
+    )?)
+}
+
+fn dec_div(a: Decimal256, b: Decimal256) -> StdResult<Decimal256> {
+    a.checked_div(b)
+        .map_err(|e| StdError::generic_err(e.to_string()))
+}
+
+/// Returns the fee of a concentrated pair with balances `xp`, which moves
+/// from `mid_fee` towards `out_fee` as the pool gets more imbalanced.
+pub fn concentrated_fee(
+    params: &ConcentratedPoolParams,
+    xp: &[Decimal256; 2],
+) -> StdResult<Decimal256> {
+    let fee_gamma = Decimal256::from(params.fee_gamma);
+    let sum = xp[0].checked_add(xp[1])?;
+    let k = dec_div(
+        xp[0]
+            .checked_mul(xp[1])?
+            .checked_mul(Decimal256::percent(400))?,
+        sum.checked_pow(2)?,
+    )?;
+    let k = dec_div(
+        fee_gamma,
+        fee_gamma.checked_add(Decimal256::one())?.checked_sub(k)?,
+    )?;
+
+    Ok(k.checked_mul(params.mid_fee.into())?
+        .checked_add((Decimal256::one() - k).checked_mul(params.out_fee.into())?)?)
+}
+
+/// Returns the fee charged on an imbalanced provide of `deposits` into a
+/// concentrated pair with balances `xp` after the provide:
+/// fee(xp) * N / (4 * (N - 1)) * sum(|deposit_i - avg|) / sum(deposit_i)
+pub fn concentrated_provide_fee(
+    params: &ConcentratedPoolParams,
+    deposits: &[Decimal256; 2],
+    xp: &[Decimal256; 2],
+) -> StdResult<Decimal256> {
+    let sum = deposits[0].checked_add(deposits[1])?;
+    if sum.is_zero() {
+        return Ok(Decimal256::zero());
+    }
+    let avg = dec_div(sum, Decimal256::percent(200))?;
+    let abs_diff = |a: Decimal256, b: Decimal256| if a > b { a - b } else { b - a };
+    let deviation = abs_diff(deposits[0], avg).checked_add(abs_diff(deposits[1], avg))?;
+
+    // N / (4 * (N - 1)) = 1 / 2 for N = 2
+    dec_div(
+        concentrated_fee(params, xp)?.checked_mul(deviation)?,
+        sum.checked_mul(Decimal256::percent(200))?,
+    )
+}
+
+/// A signed value used in the Newton's method for D, as the magnitude and
+/// whether it is negative.
+type Signed = (Decimal256, bool);
+
+/// Returns `pos - neg` as a signed value.
+fn signed_sub(pos: Decimal256, neg: Decimal256) -> Signed {
+    if pos >= neg {
+        (pos - neg, false)
+    } else {
+        (neg - pos, true)
+    }
+}
+
+/// Returns `K0` and `K` of the cryptoswap invariant:
+/// K0 = x_0 * x_1 * N^N / D^N, K = ANN * gamma^2 * K0 / (gamma + 1 - K0)^2
+fn concentrated_k(
+    ann: Decimal256,
+    gamma: Decimal256,
+    d: Decimal256,
+    x: &[Decimal256; 2],
+) -> StdResult<(Decimal256, Decimal256, Decimal256)> {
+    let k0 = dec_div(
+        x[0].checked_mul(x[1])?
+            .checked_mul(Decimal256::percent(400))?,
+        d.checked_pow(2)?,
+    )?;
+    let gamma_one_k0 = gamma.checked_add(Decimal256::one())?.checked_sub(k0)?;
+    let k = dec_div(
+        ann.checked_mul(gamma.checked_pow(2)?)?.checked_mul(k0)?,
+        gamma_one_k0.checked_pow(2)?,
+    )?;
+    Ok((k0, k, gamma_one_k0))
+}
+
+/// The cryptoswap invariant for two assets:
+/// f(D) = K * D * (x_0 + x_1) + x_0 * x_1 - K * D^2 - D^2 / N^N
+fn concentrated_f(
+    ann: Decimal256,
+    gamma: Decimal256,
+    d: Decimal256,
+    x: &[Decimal256; 2],
+) -> StdResult<Signed> {
+    let (_, k, _) = concentrated_k(ann, gamma, d, x)?;
+    let sum = x[0].checked_add(x[1])?;
+    let d2 = d.checked_pow(2)?;
+
+    let pos = k
+        .checked_mul(d)?
+        .checked_mul(sum)?
+        .checked_add(x[0].checked_mul(x[1])?)?;
+    let neg = k
+        .checked_mul(d2)?
+        .checked_add(dec_div(d2, Decimal256::percent(400))?)?;
+    Ok(signed_sub(pos, neg))
+}
+
+/// The derivative of [`concentrated_f`] with respect to D:
+/// f'(D) = (K_D * D + K) * (x_0 + x_1) - (K_D * D + 2K) * D - D / 2,
+/// where K_D = -x_0 * x_1 * N^3 * ANN * gamma^2 * (gamma + 1 + K0) /
+/// (D^3 * (gamma + 1 - K0)^3) is the derivative of K.
+fn concentrated_df_dd(
+    ann: Decimal256,
+    gamma: Decimal256,
+    d: Decimal256,
+    x: &[Decimal256; 2],
+) -> StdResult<Signed> {
+    let (k0, k, gamma_one_k0) = concentrated_k(ann, gamma, d, x)?;
+    let sum = x[0].checked_add(x[1])?;
+
+    // m = -K_D * D
+    let m = dec_div(
+        x[0].checked_mul(x[1])?
+            .checked_mul(Decimal256::percent(800))?
+            .checked_mul(ann)?
+            .checked_mul(gamma.checked_pow(2)?)?
+            .checked_mul(gamma.checked_add(Decimal256::one())?.checked_add(k0)?)?,
+        d.checked_pow(2)?
+            .checked_mul(gamma_one_k0.checked_pow(3)?)?,
+    )?;
+
+    // (K - m) * sum - (2K - m) * D - D / 2
+    let pos = k.checked_mul(sum)?.checked_add(m.checked_mul(d)?)?;
+    let neg = m
+        .checked_mul(sum)?
+        .checked_add(k.checked_mul(d)?.checked_mul(Decimal256::percent(200))?)?
+        .checked_add(dec_div(d, Decimal256::percent(200))?)?;
+    Ok(signed_sub(pos, neg))
+}
+
+/// Computes the cryptoswap invariant D of a concentrated pair with balances
+/// `x` using Newton's method.
+pub fn concentrated_compute_d(
+    ann: Decimal256,
+    gamma: Decimal256,
+    x: &[Decimal256; 2],
+) -> Result<Decimal256, CwDexError> {
+    concentrated_compute_d_with_max_iterations(ann, gamma, x, CONCENTRATED_MAX_ITER)
+}
+
+/// Computes the cryptoswap invariant like [`concentrated_compute_d`],
+/// returning [`CwDexError::FailedToConverge`] if D does not converge within
+/// `max_iterations` steps of Newton's method.
+fn concentrated_compute_d_with_max_iterations(
+    ann: Decimal256,
+    gamma: Decimal256,
+    x: &[Decimal256; 2],
+    max_iterations: u8,
+) -> Result<Decimal256, CwDexError> {
+    // Start at the geometric mean scaled by N
+    let mut d_prev = x[0]
+        .checked_mul(x[1])?
+        .sqrt()
+        .checked_mul(Decimal256::percent(200))?;
+
+    for _ in 0..max_iterations {
+        let (f, f_neg) = concentrated_f(ann, gamma, d_prev, x)?;
+        let (df, df_neg) = concentrated_df_dd(ann, gamma, d_prev, x)?;
+        let step = dec_div(f, df)?;
+
+        let d = if f_neg == df_neg {
+            d_prev.checked_sub(step)?
+        } else {
+            d_prev.checked_add(step)?
+        };
+
+        let diff = if d > d_prev { d - d_prev } else { d_prev - d };
+        if diff <= CONCENTRATED_TOL {
+            return Ok(d);
+        }
+        d_prev = d;
+    }
+
+    Err(CwDexError::FailedToConverge {
+        what: "concentrated invariant D".to_string(),
+    })
+}
+
 // Astroport StableSwap pair does not return needed Config elements with smart
 // query Raw query gets all the necessary elements
 pub fn query_pair_config(querier: &QuerierWrapper, pair: Addr) -> StdResult<Config> {
     Item::<Config>::new("config").query(querier, pair)
 }
 
+/// Queries the address of the factory that created `pair`
+pub fn query_pair_factory(querier: &QuerierWrapper, pair: Addr) -> StdResult<Addr> {
+    Ok(Item::<PairFactoryConfig>::new("config")
+        .query(querier, pair)?
+        .factory_addr)
+}
+
 pub fn query_token_precision(
     querier: &QuerierWrapper,
     asset_info: AstroAssetInfo,
@@ -186,3 +568,286 @@ pub fn query_fee_info(
         maker_fee_rate: Decimal::from_ratio(Uint128::from(res.maker_fee_bps), Uint128::new(10000)),
     })
 }
+
+/// Queries a page of the pairs created by the Astroport factory at
+/// `factory_addr`, ordered by their asset infos.
+///
+/// Arguments:
+/// - `start_after`: The asset infos of the last pair of the previous page
+/// - `limit`: The maximum number of pairs to return. The factory applies its
+///   own default and maximum page size.
+pub fn list_pairs(
+    querier: &QuerierWrapper,
+    factory_addr: Addr,
+    start_after: Option<Vec<AssetInfo>>,
+    limit: Option<u32>,
+) -> StdResult<Vec<PairInfo>> {
+    let res: PairsResponse = querier.query_wasm_smart(
+        factory_addr,
+        &FactoryQueryMsg::Pairs {
+            start_after: start_after.map(|asset_infos| asset_infos.into_elementwise()),
+            limit,
+        },
+    )?;
+    Ok(res.pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> ConcentratedPoolParams {
+        ConcentratedPoolParams {
+            amp: Decimal::from_ratio(40u128, 1u128),
+            gamma: Decimal::from_ratio(145u128, 1_000_000u128),
+            mid_fee: Decimal::from_ratio(26u128, 10_000u128),
+            out_fee: Decimal::from_ratio(45u128, 10_000u128),
+            fee_gamma: Decimal::from_ratio(23u128, 100_000u128),
+            repeg_profit_threshold: Decimal::from_ratio(2u128, 1_000_000u128),
+            min_price_scale_delta: Decimal::from_ratio(146u128, 1_000_000u128),
+            price_scale: Decimal::one(),
+            ma_half_time: 600,
+        }
+    }
+
+    #[test]
+    fn test_concentrated_compute_d_balanced_pool() {
+        let ann = Decimal256::from_ratio(160u128, 1u128);
+        let gamma = Decimal256::from(params().gamma);
+        let x = Decimal256::from_ratio(1_000_000u128, 1u128);
+
+        // For a balanced pool D is the sum of the balances
+        let d = concentrated_compute_d(ann, gamma, &[x, x]).unwrap();
+        let expected = Decimal256::from_ratio(2_000_000u128, 1u128);
+        let diff = if d > expected {
+            d - expected
+        } else {
+            expected - d
+        };
+        assert!(diff < Decimal256::from_ratio(1u128, 1_000_000u128));
+    }
+
+    #[test]
+    fn test_concentrated_compute_d_failed_to_converge() {
+        let ann = Decimal256::from_ratio(160u128, 1u128);
+        let gamma = Decimal256::from(params().gamma);
+        let x = [
+            Decimal256::from_ratio(1_000_000u128, 1u128),
+            Decimal256::from_ratio(100_000u128, 1u128),
+        ];
+
+        assert!(concentrated_compute_d(ann, gamma, &x).is_ok());
+        let err = concentrated_compute_d_with_max_iterations(ann, gamma, &x, 1).unwrap_err();
+        assert_eq!(
+            err,
+            CwDexError::FailedToConverge {
+                what: "concentrated invariant D".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_concentrated_provide_fee() {
+        let xp = [
+            Decimal256::from_ratio(1_000_000u128, 1u128),
+            Decimal256::from_ratio(1_000_000u128, 1u128),
+        ];
+
+        // Balanced provides are not charged a fee
+        let deposits = [Decimal256::one(), Decimal256::one()];
+        assert_eq!(
+            concentrated_provide_fee(&params(), &deposits, &xp).unwrap(),
+            Decimal256::zero()
+        );
+
+        // Single sided provides into a balanced pool are charged half the mid
+        // fee
+        let deposits = [Decimal256::one(), Decimal256::zero()];
+        assert_eq!(
+            concentrated_provide_fee(&params(), &deposits, &xp).unwrap(),
+            Decimal256::from(params().mid_fee) * Decimal256::percent(50)
+        );
+    }
+
+    #[test]
+    fn test_compute_d_balanced_pool() {
+        // For a balanced pool D is the sum of the balances
+        let leverage = 100 * AMP_PRECISION * 3;
+        let d = compute_d(leverage, &[1_000_000, 1_000_000, 1_000_000]).unwrap();
+        assert!(d.abs_diff(3_000_000) <= 1);
+
+        assert_eq!(compute_d(leverage, &[0, 0, 0]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_compute_d_failed_to_converge() {
+        let leverage = 100 * AMP_PRECISION * 2;
+
+        assert!(compute_d(leverage, &[1_000_000, 1]).is_ok());
+        let err = compute_d_with_max_iterations(leverage, &[1_000_000, 1], 1).unwrap_err();
+        assert_eq!(
+            err,
+            CwDexError::FailedToConverge {
+                what: "stable swap invariant D".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_compute_d_leverage_below_amp_precision() {
+        let err = compute_d(AMP_PRECISION - 1, &[1_000_000, 1_000_000]).unwrap_err();
+        assert_eq!(
+            err,
+            CwDexError::Std(StdError::generic_err(
+                "Leverage 99 is below the amplification precision 100"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_compute_d_overflow() {
+        let err = compute_d(100 * AMP_PRECISION * 2, &[u128::MAX, 1]).unwrap_err();
+        assert_eq!(
+            err,
+            CwDexError::Std(StdError::generic_err(
+                "Overflow when computing stable swap invariant"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_xyk_slippage_tolerance() {
+        assert_eq!(
+            xyk_slippage_tolerance(Uint128::new(900), Uint128::new(1_000)).unwrap(),
+            Decimal::percent(10)
+        );
+        assert_eq!(
+            xyk_slippage_tolerance(Uint128::new(1_000), Uint128::new(1_000)).unwrap(),
+            Decimal::zero()
+        );
+        // Capped at the maximum allowed slippage of the pair
+        assert_eq!(
+            xyk_slippage_tolerance(Uint128::zero(), Uint128::new(1_000)).unwrap(),
+            Decimal::percent(50)
+        );
+        assert_eq!(
+            xyk_slippage_tolerance(Uint128::new(100), Uint128::zero()).unwrap(),
+            Decimal::percent(50)
+        );
+    }
+
+    #[test]
+    fn test_twap_from_cumulative_prices() {
+        // A price of 2.5 for 100 seconds
+        let start = Uint128::new(1_000_000);
+        let end = start + Uint128::new(250_000_000);
+        assert_eq!(
+            twap_from_cumulative_prices(start, end, 100).unwrap(),
+            Decimal::from_ratio(5u128, 2u128)
+        );
+
+        // The cumulative price wrapped around since the start
+        let start = Uint128::MAX - Uint128::new(49_999_999);
+        let end = Uint128::new(200_000_000);
+        assert_eq!(
+            twap_from_cumulative_prices(start, end, 100).unwrap(),
+            Decimal::from_ratio(5u128, 2u128)
+        );
+    }
+
+    #[test]
+    fn test_xyk_zap_in_swap_amount() {
+        let reserve = Uint128::new(1_000_000_000);
+
+        // Without a fee half of a small amount is swapped
+        let swap_amount =
+            xyk_zap_in_swap_amount(Uint128::new(1_000), reserve, Decimal256::zero()).unwrap();
+        assert_eq!(swap_amount, Uint128::new(499));
+
+        // The remainder and the return of the swap are in the ratio of the
+        // reserves after the swap
+        let amount = Uint128::new(500_000_000);
+        let fee = Decimal256::permille(3);
+        let swap_amount = xyk_zap_in_swap_amount(amount, reserve, fee).unwrap();
+        let return_amount = (Decimal256::one() - fee)
+            * Decimal256::from_ratio(reserve * swap_amount, reserve + swap_amount);
+        let ratio_deposits = Decimal256::from_ratio(amount - swap_amount, 1u128)
+            .checked_div(return_amount)
+            .unwrap();
+        let ratio_reserves = Decimal256::from_ratio(reserve + swap_amount, 1u128)
+            .checked_div(Decimal256::from_ratio(reserve, 1u128) - return_amount)
+            .unwrap();
+        let diff = if ratio_deposits > ratio_reserves {
+            ratio_deposits - ratio_reserves
+        } else {
+            ratio_reserves - ratio_deposits
+        };
+        assert!(diff < Decimal256::permille(1));
+    }
+
+    #[test]
+    fn test_list_pairs_pagination() {
+        use cosmwasm_std::testing::mock_dependencies;
+        use cosmwasm_std::{from_binary, ContractResult, SystemResult};
+
+        let pair = |denom: &str| PairInfo {
+            asset_infos: vec![
+                AstroAssetInfo::NativeToken {
+                    denom: "uatom".to_string(),
+                },
+                AstroAssetInfo::NativeToken {
+                    denom: denom.to_string(),
+                },
+            ],
+            contract_addr: Addr::unchecked(format!("pair_{}", denom)),
+            liquidity_token: Addr::unchecked(format!("lp_{}", denom)),
+            pair_type: PairType::Xyk {},
+        };
+        let pairs = vec![pair("ujuno"), pair("uosmo"), pair("ustars")];
+
+        let mut deps = mock_dependencies();
+        let all_pairs = pairs.clone();
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "factory" => {
+                let (start_after, limit) = match from_binary(msg).unwrap() {
+                    FactoryQueryMsg::Pairs { start_after, limit } => (start_after, limit),
+                    _ => panic!("unexpected query"),
+                };
+                let start = start_after
+                    .map(|asset_infos| {
+                        all_pairs
+                            .iter()
+                            .position(|pair| pair.asset_infos == asset_infos)
+                            .unwrap()
+                            + 1
+                    })
+                    .unwrap_or_default();
+                let pairs = all_pairs
+                    .iter()
+                    .skip(start)
+                    .take(limit.unwrap_or(10) as usize)
+                    .cloned()
+                    .collect();
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&PairsResponse { pairs }).unwrap(),
+                ))
+            }
+            _ => panic!("unexpected query"),
+        });
+        let querier = deps.as_ref().querier;
+
+        let first_page = list_pairs(&querier, Addr::unchecked("factory"), None, Some(2)).unwrap();
+        assert_eq!(first_page, pairs[..2]);
+
+        // The next page starts after the asset infos of the last pair
+        let start_after: Vec<AssetInfo> = first_page[1].asset_infos.clone().into_elementwise();
+        let second_page = list_pairs(
+            &querier,
+            Addr::unchecked("factory"),
+            Some(start_after),
+            Some(2),
+        )
+        .unwrap();
+        assert_eq!(second_page, pairs[2..]);
+    }
+}
@@ -3,7 +3,8 @@
 #![allow(missing_docs)]
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Decimal, QuerierWrapper, StdResult, Uint128};
+use cosmwasm_std::{Addr, Binary, Decimal, QuerierWrapper, StdResult, Uint128};
+use serde::{Deserialize, Serialize};
 
 use cw_asset::astroport::{AstroAsset, AstroAssetInfo};
 
@@ -20,7 +21,7 @@ pub enum PairType {
 
 #[cw_serde]
 pub struct PairInfo {
-    pub asset_infos: [AstroAssetInfo; 2],
+    pub asset_infos: Vec<AstroAssetInfo>,
     pub contract_addr: Addr,
     pub liquidity_token: Addr,
     pub pair_type: PairType,
@@ -30,17 +31,16 @@ impl PairInfo {
         &self,
         querier: &QuerierWrapper,
         contract_addr: Addr,
-    ) -> StdResult<[AstroAsset; 2]> {
-        Ok([
-            AstroAsset {
-                amount: self.asset_infos[0].query_pool(querier, contract_addr.clone())?,
-                info: self.asset_infos[0].clone(),
-            },
-            AstroAsset {
-                amount: self.asset_infos[1].query_pool(querier, contract_addr)?,
-                info: self.asset_infos[1].clone(),
-            },
-        ])
+    ) -> StdResult<Vec<AstroAsset>> {
+        self.asset_infos
+            .iter()
+            .map(|info| {
+                Ok(AstroAsset {
+                    amount: info.query_pool(querier, contract_addr.clone())?,
+                    info: info.clone(),
+                })
+            })
+            .collect()
     }
 }
 
@@ -59,7 +59,7 @@ pub enum PairCw20HookMsg {
 pub enum PairExecuteMsg {
     /// ProvideLiquidity a user provides pool liquidity
     ProvideLiquidity {
-        assets: [AstroAsset; 2],
+        assets: Vec<AstroAsset>,
         slippage_tolerance: Option<Decimal>,
         auto_stake: Option<bool>,
         receiver: Option<String>,
@@ -78,11 +78,14 @@ pub enum PairQueryMsg {
     Pair {},
     Pool {},
     Simulation { offer_asset: AstroAsset },
+    ReverseSimulation { ask_asset: AstroAsset },
+    Config {},
+    CumulativePrices {},
 }
 
 #[cw_serde]
 pub struct PoolResponse {
-    pub assets: [AstroAsset; 2],
+    pub assets: Vec<AstroAsset>,
     pub total_share: Uint128,
 }
 
@@ -93,6 +96,47 @@ pub struct SimulationResponse {
     pub commission_amount: Uint128,
 }
 
+#[cw_serde]
+pub struct ReverseSimulationResponse {
+    pub offer_amount: Uint128,
+    pub spread_amount: Uint128,
+    pub commission_amount: Uint128,
+}
+
+#[cw_serde]
+pub struct CumulativePricesResponse {
+    pub assets: Vec<AstroAsset>,
+    pub total_share: Uint128,
+    pub cumulative_prices: Vec<(AstroAssetInfo, AstroAssetInfo, Uint128)>,
+}
+
+/// Response to `PairQueryMsg::Config`. Not using `cw_serde` since the fields
+/// of the response differ between versions of the pair contracts.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ConfigResponse {
+    pub block_time_last: u64,
+    /// Pair type specific parameters. For concentrated pairs these are
+    /// [`ConcentratedPoolParams`].
+    pub params: Option<Binary>,
+}
+
+/// The parameters of an Astroport passive concentrated liquidity pair, as
+/// returned in [`ConfigResponse::params`]. Not using `cw_serde` since newer
+/// versions of the pair contract return additional fields.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ConcentratedPoolParams {
+    pub amp: Decimal,
+    pub gamma: Decimal,
+    pub mid_fee: Decimal,
+    pub out_fee: Decimal,
+    pub fee_gamma: Decimal,
+    pub repeg_profit_threshold: Decimal,
+    pub min_price_scale_delta: Decimal,
+    /// Price of the second asset of the pair denominated in the first asset
+    pub price_scale: Decimal,
+    pub ma_half_time: u64,
+}
+
 /// Astroport stable pair config
 #[cw_serde]
 pub struct Config {
@@ -107,6 +151,13 @@ pub struct Config {
     pub next_amp_time: u64,
 }
 
+/// The factory address in the config stored by all types of pair contracts.
+/// Not using `cw_serde` since the rest of the config differs between them.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PairFactoryConfig {
+    pub factory_addr: Addr,
+}
+
 ///////////////////////////////
 // Generator Msgs
 ///////////////////////////////
@@ -126,6 +177,7 @@ pub enum GeneratorExecuteMsg {
 #[cw_serde]
 pub enum GeneratorQueryMsg {
     PendingToken { lp_token: String, user: String },
+    Deposit { lp_token: String, user: String },
 }
 
 #[cw_serde]
@@ -142,10 +194,10 @@ pub struct PendingTokenResponse {
 pub enum FactoryQueryMsg {
     Config {},
     Pair {
-        asset_infos: [AstroAssetInfo; 2],
+        asset_infos: Vec<AstroAssetInfo>,
     },
     Pairs {
-        start_after: Option<[AstroAssetInfo; 2]>,
+        start_after: Option<Vec<AstroAssetInfo>>,
         limit: Option<u32>,
     },
     FeeInfo {
@@ -153,6 +205,11 @@ pub enum FactoryQueryMsg {
     },
 }
 
+#[cw_serde]
+pub struct PairsResponse {
+    pub pairs: Vec<PairInfo>,
+}
+
 #[cw_serde]
 pub struct FeeInfoResponse {
     pub fee_address: Option<Addr>,
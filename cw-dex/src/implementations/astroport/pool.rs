@@ -1,31 +1,57 @@
 //! Pool trait implementation for Astroport
 
-use std::str::FromStr;
-
 use apollo_utils::iterators::IntoElementwise;
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    to_binary, wasm_execute, Addr, CosmosMsg, Decimal, Deps, Env, Event, QuerierWrapper,
-    QueryRequest, Response, StdError, StdResult, Uint128, WasmMsg, WasmQuery,
+    from_binary, to_binary, wasm_execute, Addr, CosmosMsg, Decimal, Decimal256, Deps, Env, Event,
+    QuerierWrapper, QueryRequest, StdError, StdResult, Uint128, Uint256, WasmMsg, WasmQuery,
 };
 use cw20::Cw20ExecuteMsg;
 use cw_asset::{Asset, AssetInfo, AssetInfoBase, AssetList};
 use cw_utils::Expiration;
 
 use super::helpers::{
-    adjust_precision, compute_current_amp, compute_d, query_pair_config, query_supply,
-    query_token_precision, MAX_ALLOWED_SLIPPAGE, N_COINS, U256,
+    adjust_precision, compute_current_amp, compute_d, compute_stable_spot_price,
+    concentrated_compute_d, concentrated_fee, concentrated_provide_fee, decimal256_to_uint,
+    query_fee_info, query_pair_config, query_pair_factory, query_supply, query_token_precision,
+    twap_from_cumulative_prices, xyk_slippage_tolerance, xyk_zap_in_swap_amount, AMP_PRECISION,
+    CONCENTRATED_PAIR_TYPE, MAX_ALLOWED_SLIPPAGE, MINIMUM_LIQUIDITY_AMOUNT, U256,
 };
 use super::msg::{
+    ConcentratedPoolParams, ConfigResponse, CumulativePricesResponse, FactoryQueryMsg,
     PairCw20HookMsg, PairExecuteMsg, PairInfo, PairQueryMsg, PairType, PoolResponse,
-    SimulationResponse,
+    ReverseSimulationResponse, SimulationResponse,
+};
+use crate::callback::CwDexCallbackMsg;
+use crate::implementations::helpers::assert_min_out;
+use crate::implementations::valuation::{lp_price_from_value, reference_price, xyk_fair_value};
+use crate::implementations::zap::{simulate_zap_out, withdraw_and_swap};
+use crate::response::CwDexResponse;
+use crate::traits::{
+    CumulativePricesSnapshot, Pool, PoolParams, PoolType, PriceOracle, SwapSimulation,
 };
-use crate::traits::Pool;
 use crate::CwDexError;
 use apollo_utils::assets::separate_natives_and_cw20s;
-use cw_asset::astroport::AstroAssetInfo;
+use cw_asset::astroport::{AstroAsset, AstroAssetInfo};
+use std::str::FromStr;
+
+/// The estimated outcome of providing liquidity to an Astroport pair, as
+/// returned by [`AstroportPool::simulate_provide_liquidity_detailed`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProvideLiquiditySimulation {
+    /// The assets that are deposited into the pair
+    pub assets_to_use: AssetList,
+    /// The assets that are not deposited and stay in the contract's balance
+    pub leftover: AssetList,
+    /// The expected amount of LP tokens minted
+    pub lp_token: Asset,
+}
 
 /// Represents an AMM pool on Astroport
+///
+/// Withdrawing liquidity, or providing liquidity to a stable or concentrated
+/// pair, with a non-zero `min_out` sends a [`CwDexCallbackMsg`] to the calling
+/// contract, which must handle it.
 #[cw_serde]
 pub struct AstroportPool {
     /// The address of the associated pair contract
@@ -34,7 +60,8 @@ pub struct AstroportPool {
     pub lp_token_addr: Addr,
     /// The assets of the pool
     pub pool_assets: Vec<AssetInfo>,
-    /// The type of pool represented: Constant product (*Xyk*) or *Stableswap*
+    /// The type of pool represented: Constant product (*Xyk*), *Stableswap*
+    /// or passive concentrated liquidity (*Custom("concentrated")*)
     pub pair_type: PairType,
 }
 
@@ -48,14 +75,51 @@ impl AstroportPool {
             .querier
             .query_wasm_smart::<PairInfo>(pair_addr.clone(), &PairQueryMsg::Pair {})?;
 
-        // Validate pair type. We only support XYK and stable swap pools
-        match pair_info.pair_type {
-            PairType::Custom(_) => Err(StdError::generic_err("Custom pair type is not supported")),
+        // The pool must refer to the queried contract, not to whatever address
+        // it claims to be
+        if pair_info.contract_addr != pair_addr {
+            return Err(StdError::generic_err(format!(
+                "Pair {} returned pair info of {}",
+                pair_addr, pair_info.contract_addr
+            )));
+        }
+
+        Self::from_pair_info(pair_info)
+    }
+
+    /// Creates a new instance of `AstroportPool` for the pair with the given
+    /// assets, looked up in the Astroport factory
+    ///
+    /// Arguments:
+    /// - `factory_addr`: The address of the Astroport factory contract
+    /// - `asset_infos`: The assets of the pair, in any order
+    pub fn from_factory(
+        deps: Deps,
+        factory_addr: Addr,
+        asset_infos: Vec<AssetInfo>,
+    ) -> StdResult<Self> {
+        let pair_info = deps.querier.query_wasm_smart::<PairInfo>(
+            factory_addr,
+            &FactoryQueryMsg::Pair {
+                asset_infos: asset_infos.into_elementwise(),
+            },
+        )?;
+
+        Self::from_pair_info(pair_info)
+    }
+
+    fn from_pair_info(pair_info: PairInfo) -> StdResult<Self> {
+        // Validate pair type. We only support XYK, stable swap and concentrated
+        // pools
+        match &pair_info.pair_type {
+            PairType::Custom(t) if t != CONCENTRATED_PAIR_TYPE => Err(StdError::generic_err(
+                format!("Custom pair type {} is not supported", t),
+            )),
             _ => Ok(()),
         }?;
 
         Ok(Self {
-            pair_addr,
+            pair_addr: pair_info.contract_addr,
             lp_token_addr: pair_info.liquidity_token,
             pool_assets: pair_info.asset_infos.into_elementwise(),
             pair_type: pair_info.pair_type,
@@ -80,26 +144,31 @@ impl AstroportPool {
     ///
     /// Copied from the astroport XYK pool implementation here:
     /// https://github.com/astroport-fi/astroport-core/blob/7bedc6f27e59ef8b921a0980be9bc30c4aab7459/contracts/pair/src/contract.rs#L297-L434
+    ///
+    /// XYK pairs keep all deposited assets but mint LP tokens for the smaller
+    /// of the deposits relative to the reserves, so only the amount of the
+    /// larger deposit needed to mint the same amount of LP tokens is used.
     fn xyk_simulate_provide_liquidity(
         &self,
         deps: Deps,
         _env: &Env,
         assets: AssetList,
-    ) -> Result<Asset, CwDexError> {
+    ) -> Result<ProvideLiquiditySimulation, CwDexError> {
         let PoolResponse {
             assets: pools,
             total_share,
         } = self.query_pool_info(&deps.querier)?;
+        assert_two_pool_assets(&pools)?;
 
         let deposits = [
             assets
                 .find(&pools[0].info.clone().into())
                 .map(|a| a.amount)
-                .expect("Wrong asset info is given"),
+                .unwrap_or_default(),
             assets
                 .find(&pools[1].info.clone().into())
                 .map(|a| a.amount)
-                .expect("Wrong asset info is given"),
+                .unwrap_or_default(),
         ];
 
         if deposits[0].is_zero() || deposits[1].is_zero() {
@@ -125,11 +194,63 @@ impl AstroportPool {
             )
         };
 
-        let lp_token = Asset {
-            info: AssetInfo::Cw20(self.lp_token_addr.clone()),
-            amount: share,
+        let used = if total_share.is_zero() {
+            deposits
+        } else {
+            [
+                deposits[0].min(multiply_ratio_ceil(share, pools[0].amount, total_share)?),
+                deposits[1].min(multiply_ratio_ceil(share, pools[1].amount, total_share)?),
+            ]
         };
-        Ok(lp_token)
+
+        let mut assets_to_use = AssetList::new();
+        let mut leftover = AssetList::new();
+        for ((pool, deposit), used) in pools.iter().zip(deposits).zip(used) {
+            let info: AssetInfo = pool.info.clone().into();
+            assets_to_use.add(&Asset::new(info.clone(), used))?;
+            leftover.add(&Asset::new(info, deposit - used))?;
+        }
+        leftover.purge();
+
+        Ok(ProvideLiquiditySimulation {
+            assets_to_use,
+            leftover,
+            lp_token: Asset::cw20(self.lp_token_addr.clone(), share),
+        })
+    }
+
+    /// Returns the estimated outcome of providing liquidity with `assets`,
+    /// including the amounts of `assets` that are deposited into the pair and
+    /// the leftover amounts that stay in the contract's balance.
+    ///
+    /// Only XYK pairs may leave leftover assets. Stable swap and concentrated
+    /// pairs accept deposits in any ratio.
+    pub fn simulate_provide_liquidity_detailed(
+        &self,
+        deps: Deps,
+        env: &Env,
+        assets: AssetList,
+    ) -> Result<ProvideLiquiditySimulation, CwDexError> {
+        self.validate_assets(&assets)?;
+
+        let lp_token = match &self.pair_type {
+            PairType::Xyk {} => return self.xyk_simulate_provide_liquidity(deps, env, assets),
+            PairType::Stable {} => {
+                self.stable_simulate_provide_liquidity(deps, env, assets.clone())
+            }
+            PairType::Custom(t) if t == CONCENTRATED_PAIR_TYPE => {
+                self.concentrated_simulate_provide_liquidity(deps, env, assets.clone())
+            }
+            PairType::Custom(_) => Err(CwDexError::Std(StdError::generic_err(
+                "custom pair type not supported",
+            ))),
+        }?;
+
+        Ok(ProvideLiquiditySimulation {
+            assets_to_use: assets,
+            leftover: AssetList::new(),
+            lp_token,
+        })
     }
 
     /// Math for providing liquidity to an Astroport stable swap pool.
@@ -143,38 +264,46 @@ impl AstroportPool {
         assets: AssetList,
     ) -> Result<Asset, CwDexError> {
         let config = query_pair_config(&deps.querier, self.pair_addr.clone())?;
-        let mut pools = config
+        let pools = config
             .pair_info
             .query_pools(&deps.querier, self.pair_addr.to_owned())?;
-        let deposits: [Uint128; 2] = [
-            assets
-                .find(&pools[0].info.clone().into())
-                .map(|a| a.amount)
-                .expect("Wrong asset info is given"),
-            assets
-                .find(&pools[1].info.clone().into())
-                .map(|a| a.amount)
-                .expect("Wrong asset info is given"),
-        ];
+        let deposits: Vec<Uint128> = pools
+            .iter()
+            .map(|pool| {
+                assets
+                    .find(&pool.info.clone().into())
+                    .map(|a| a.amount)
+                    .unwrap_or_default()
+            })
+            .collect();
 
-        if deposits[0].is_zero() && deposits[1].is_zero() {
+        if deposits.iter().all(|d| d.is_zero()) {
             return Err(CwDexError::InvalidZeroAmount {});
         }
 
-        for (i, pool) in pools.iter_mut().enumerate() {
+        for (deposit, pool) in deposits.iter().zip(pools.iter()) {
             // we cannot put a zero amount into an empty pool.
-            if deposits[i].is_zero() && pool.amount.is_zero() {
+            if deposit.is_zero() && pool.amount.is_zero() {
                 return Err(CwDexError::InvalidProvideLPsWithSingleToken {});
             }
         }
 
-        let token_precision_0 = query_token_precision(&deps.querier, pools[0].info.clone())?;
-        let token_precision_1 = query_token_precision(&deps.querier, pools[1].info.clone())?;
+        let token_precisions = pools
+            .iter()
+            .map(|pool| query_token_precision(&deps.querier, pool.info.clone()))
+            .collect::<StdResult<Vec<u8>>>()?;
+
+        let greater_precision = token_precisions.iter().copied().max().unwrap_or_default();
 
-        let greater_precision = token_precision_0.max(token_precision_1);
+        let deposit_amounts = deposits
+            .iter()
+            .zip(token_precisions.iter())
+            .map(|(deposit, precision)| adjust_precision(*deposit, *precision, greater_precision))
+            .collect::<StdResult<Vec<Uint128>>>()?;
 
-        let deposit_amount_0 = adjust_precision(deposits[0], token_precision_0, greater_precision)?;
-        let deposit_amount_1 = adjust_precision(deposits[1], token_precision_1, greater_precision)?;
+        let leverage = compute_current_amp(&config, env)?
+            .checked_mul(pools.len() as u64)
+            .ok_or_else(|| StdError::generic_err("Amplification overflow"))?;
 
         let total_share = query_supply(&deps.querier, config.pair_info.liquidity_token.clone())?;
         let share = if total_share.is_zero() {
@@ -185,34 +314,50 @@ impl AstroportPool {
                 },
             )?;
 
-            // Initial share = collateral amount
-            adjust_precision(
-                Uint128::new(
-                    (U256::from(deposit_amount_0.u128()) * U256::from(deposit_amount_1.u128()))
-                        .integer_sqrt()
-                        .as_u128(),
-                ),
-                greater_precision,
-                liquidity_token_precision,
-            )?
+            if let [deposit_amount_0, deposit_amount_1] = deposit_amounts[..] {
+                // Initial share = collateral amount
+                adjust_precision(
+                    Uint128::new(
+                        (U256::from(deposit_amount_0.u128()) * U256::from(deposit_amount_1.u128()))
+                            .integer_sqrt()
+                            .as_u128(),
+                    ),
+                    greater_precision,
+                    liquidity_token_precision,
+                )?
+            } else {
+                // Astroport's N-asset stable pair mints D as the initial
+                // share, of which MINIMUM_LIQUIDITY_AMOUNT is locked in the pair.
+                let deposit_d = compute_d(
+                    leverage,
+                    &deposit_amounts.iter().map(|d| d.u128()).collect::<Vec<_>>(),
+                )?;
+                adjust_precision(
+                    Uint128::new(deposit_d),
+                    greater_precision,
+                    liquidity_token_precision,
+                )?
+                .checked_sub(Uint128::new(MINIMUM_LIQUIDITY_AMOUNT))
+                .map_err(|_| CwDexError::LiquidityAmountTooSmall {})?
+            }
         } else {
-            let leverage = compute_current_amp(&config, env)?
-                .checked_mul(u64::from(N_COINS))
-                .unwrap();
-
-            let mut pool_amount_0 =
-                adjust_precision(pools[0].amount, token_precision_0, greater_precision)?;
-            let mut pool_amount_1 =
-                adjust_precision(pools[1].amount, token_precision_1, greater_precision)?;
+            let pool_amounts = pools
+                .iter()
+                .zip(token_precisions.iter())
+                .map(|(pool, precision)| {
+                    Ok(adjust_precision(pool.amount, *precision, greater_precision)?.u128())
+                })
+                .collect::<StdResult<Vec<u128>>>()?;
 
-            let d_before_addition_liquidity =
-                compute_d(leverage, pool_amount_0.u128(), pool_amount_1.u128()).unwrap();
+            let d_before_addition_liquidity = compute_d(leverage, &pool_amounts)?;
 
-            pool_amount_0 = pool_amount_0.checked_add(deposit_amount_0)?;
-            pool_amount_1 = pool_amount_1.checked_add(deposit_amount_1)?;
+            let pool_amounts = pool_amounts
+                .iter()
+                .zip(deposit_amounts.iter())
+                .map(|(pool, deposit)| Ok(Uint128::new(*pool).checked_add(*deposit)?.u128()))
+                .collect::<StdResult<Vec<u128>>>()?;
 
-            let d_after_addition_liquidity =
-                compute_d(leverage, pool_amount_0.u128(), pool_amount_1.u128()).unwrap();
+            let d_after_addition_liquidity = compute_d(leverage, &pool_amounts)?;
 
             // d after adding liquidity may be less than or equal to d before adding
             // liquidity because of rounding
@@ -238,35 +383,26 @@ impl AstroportPool {
         };
         Ok(lp_token)
     }
-}
 
-impl Pool for AstroportPool {
-    fn provide_liquidity(
+    /// Returns the messages to provide `assets` to the pair, including the
+    /// messages increasing the allowance of any Cw20 tokens.
+    fn provide_liquidity_msgs(
         &self,
-        deps: Deps,
         env: &Env,
-        assets: AssetList,
-        min_out: Uint128,
-    ) -> Result<Response, CwDexError> {
-        let lp_out = self.simulate_provide_liquidity(deps, env, assets.clone())?;
-        if min_out > lp_out.amount {
-            return Err(CwDexError::MinOutNotReceived {
-                min_out,
-                received: lp_out.amount,
-            });
-        }
-
+        assets: &AssetList,
+        slippage_tolerance: Decimal,
+    ) -> Result<Vec<CosmosMsg>, CwDexError> {
         let msg = PairExecuteMsg::ProvideLiquidity {
-            assets: assets.to_owned().try_into()?,
-            slippage_tolerance: Some(Decimal::from_str(MAX_ALLOWED_SLIPPAGE)?),
+            assets: assets.iter().cloned().map(Into::into).collect(),
+            slippage_tolerance: Some(slippage_tolerance),
             auto_stake: Some(false),
             receiver: None,
         };
 
-        let (funds, cw20s) = separate_natives_and_cw20s(&assets);
+        let (funds, cw20s) = separate_natives_and_cw20s(assets);
 
         // Increase allowance on all Cw20s
-        let allowance_msgs: Vec<CosmosMsg> = cw20s
+        let mut msgs: Vec<CosmosMsg> = cw20s
             .into_iter()
             .map(|asset| {
                 Ok(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -281,29 +417,599 @@ impl Pool for AstroportPool {
             })
             .collect::<StdResult<Vec<_>>>()?;
 
-        let provide_liquidity = CosmosMsg::Wasm(WasmMsg::Execute {
+        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: self.pair_addr.to_string(),
             msg: to_binary(&msg)?,
             funds,
+        }));
+
+        Ok(msgs)
+    }
+
+    /// Returns the slippage tolerance to provide `assets` to the pair with.
+    ///
+    /// XYK pairs reject deposits that would mint less than `min_out` LP
+    /// tokens with the returned tolerance, see [`xyk_slippage_tolerance`].
+    /// For other pair types the tolerance does not correspond to the amount
+    /// of LP tokens minted, so the maximum tolerance the pair allows is used
+    /// and `min_out` is asserted by a callback after providing liquidity.
+    fn provide_slippage_tolerance(
+        &self,
+        deps: Deps,
+        assets: &AssetList,
+        min_out: Uint128,
+    ) -> Result<Decimal, CwDexError> {
+        match &self.pair_type {
+            PairType::Xyk {} => {
+                let PoolResponse {
+                    assets: pools,
+                    total_share,
+                } = self.query_pool_info(&deps.querier)?;
+                let max_lp_amount = pools
+                    .iter()
+                    .filter(|pool| !pool.amount.is_zero())
+                    .map(|pool| {
+                        assets
+                            .find(&pool.info.clone().into())
+                            .map(|a| a.amount)
+                            .unwrap_or_default()
+                            .multiply_ratio(total_share, pool.amount)
+                    })
+                    .max()
+                    .unwrap_or_default();
+                Ok(xyk_slippage_tolerance(min_out, max_lp_amount)?)
+            }
+            _ => Ok(Decimal::from_str(MAX_ALLOWED_SLIPPAGE)?),
+        }
+    }
+
+    /// Computes how much of `asset` to swap into the other asset of an XYK
+    /// pair before providing liquidity with the remainder and the return of
+    /// the swap, and the expected amount of LP tokens.
+    fn xyk_simulate_zap_in(&self, deps: Deps, asset: &Asset) -> Result<ZapIn, CwDexError> {
+        let PoolResponse {
+            assets: pools,
+            total_share,
+        } = self.query_pool_info(&deps.querier)?;
+        assert_two_pool_assets(&pools)?;
+
+        let offer_index = find_pool_asset_index(&pools, &asset.info)?;
+        let offer_pool = pools[offer_index].amount;
+        let ask_pool = &pools[1 - offer_index];
+        if total_share.is_zero() || offer_pool.is_zero() || ask_pool.amount.is_zero() {
+            return Err(CwDexError::InvalidProvideLPsWithSingleToken {});
+        }
+        let ask_info: AssetInfo = ask_pool.info.clone().into();
+
+        // The commission rate depends on the factory config, so we derive it
+        // from a simulation instead of querying it.
+        let simulation = self.simulate_swap_detailed(deps, asset.clone(), ask_info.clone())?;
+        let fee = Decimal256::checked_from_ratio(
+            simulation.commission_amount,
+            simulation
+                .return_amount
+                .checked_add(simulation.commission_amount)?,
+        )
+        .map_err(|_| StdError::generic_err("Zap in amount too small"))?;
+
+        let swap_amount = xyk_zap_in_swap_amount(asset.amount, offer_pool, fee)?;
+        let return_amount = self.simulate_swap(
+            deps,
+            Asset::new(asset.info.clone(), swap_amount),
+            ask_info.clone(),
+        )?;
+
+        // Part of the commission is sent to the maker, so the reserve of the
+        // asked asset after the swap may be slightly lower than this, which
+        // only increases the amount of LP tokens received.
+        let lp_amounts = [
+            asset
+                .amount
+                .checked_sub(swap_amount)?
+                .multiply_ratio(total_share, offer_pool.checked_add(swap_amount)?),
+            return_amount.multiply_ratio(total_share, ask_pool.amount.checked_sub(return_amount)?),
+        ];
+
+        Ok(ZapIn {
+            swap_amount,
+            return_asset: Asset::new(ask_info, return_amount),
+            lp_amount: lp_amounts[0].min(lp_amounts[1]),
+            max_lp_amount: lp_amounts[0].max(lp_amounts[1]),
+        })
+    }
+
+    /// Returns an error if any of `assets` is not one of the assets of the pool
+    fn validate_assets(&self, assets: &AssetList) -> Result<(), CwDexError> {
+        match assets
+            .iter()
+            .find(|asset| !self.pool_assets.contains(&asset.info))
+        {
+            Some(asset) => Err(CwDexError::AssetNotInPool {
+                asset: asset.info.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Queries the parameters of an Astroport concentrated pair
+    pub fn query_concentrated_params(
+        &self,
+        querier: &QuerierWrapper,
+    ) -> StdResult<ConcentratedPoolParams> {
+        let config: ConfigResponse =
+            querier.query_wasm_smart(self.pair_addr.to_string(), &PairQueryMsg::Config {})?;
+        from_binary(
+            &config
+                .params
+                .ok_or_else(|| StdError::generic_err("Concentrated pair params not set"))?,
+        )
+    }
+
+    /// Math for providing liquidity to an Astroport passive concentrated
+    /// liquidity pool.
+    ///
+    /// This logic is ported from the astroport implementation here:
+    /// https://github.com/astroport-fi/astroport-core/blob/v2.8.0/contracts/pair_concentrated/src/contract.rs
+    fn concentrated_simulate_provide_liquidity(
+        &self,
+        deps: Deps,
+        _env: &Env,
+        assets: AssetList,
+    ) -> Result<Asset, CwDexError> {
+        let params = self.query_concentrated_params(&deps.querier)?;
+        let PoolResponse {
+            assets: pools,
+            total_share,
+        } = self.query_pool_info(&deps.querier)?;
+        assert_two_pool_assets(&pools)?;
+
+        let deposits = [
+            assets
+                .find(&pools[0].info.clone().into())
+                .map(|a| a.amount)
+                .unwrap_or_default(),
+            assets
+                .find(&pools[1].info.clone().into())
+                .map(|a| a.amount)
+                .unwrap_or_default(),
+        ];
+        if deposits[0].is_zero() && deposits[1].is_zero() {
+            return Err(CwDexError::InvalidZeroAmount {});
+        }
+
+        // Convert all amounts to Decimal256 in units of the token, with the
+        // second asset denominated in the first asset using the price scale.
+        let price_scale = Decimal256::from(params.price_scale);
+        let to_dec = |amount: Uint128, precision: u8| {
+            Decimal256::from_atomics(amount, precision.into())
+                .map_err(|e| StdError::generic_err(e.to_string()))
+        };
+        let precisions = [
+            query_token_precision(&deps.querier, pools[0].info.clone())?,
+            query_token_precision(&deps.querier, pools[1].info.clone())?,
+        ];
+        let ideposits = [
+            to_dec(deposits[0], precisions[0])?,
+            to_dec(deposits[1], precisions[1])?.checked_mul(price_scale)?,
+        ];
+        let old_xp = [
+            to_dec(pools[0].amount, precisions[0])?,
+            to_dec(pools[1].amount, precisions[1])?.checked_mul(price_scale)?,
+        ];
+        let new_xp = [
+            old_xp[0].checked_add(ideposits[0])?,
+            old_xp[1].checked_add(ideposits[1])?,
+        ];
+
+        let ann = Decimal256::from(params.amp).checked_mul(Decimal256::percent(400))?;
+        let gamma = Decimal256::from(params.gamma);
+        let new_d = concentrated_compute_d(ann, gamma, &new_xp)?;
+
+        let lp_precision = query_token_precision(
+            &deps.querier,
+            AstroAssetInfo::Token {
+                contract_addr: self.lp_token_addr.clone(),
+            },
+        )?;
+        let share = if total_share.is_zero() {
+            // Initial share = D / (N * sqrt(price_scale)), of which
+            // MINIMUM_LIQUIDITY_AMOUNT is locked in the pair.
+            let xcp = new_d
+                .checked_div(Decimal256::percent(200).checked_mul(price_scale.sqrt())?)
+                .map_err(|e| StdError::generic_err(e.to_string()))?;
+            decimal256_to_uint(xcp, lp_precision)?
+                .checked_sub(Uint128::new(MINIMUM_LIQUIDITY_AMOUNT))
+                .map_err(|_| CwDexError::LiquidityAmountTooSmall {})?
+        } else {
+            let old_d = concentrated_compute_d(ann, gamma, &old_xp)?;
+            if new_d <= old_d {
+                return Err(CwDexError::LiquidityAmountTooSmall {});
+            }
+
+            // share = total_share * (D1 / D0 - 1) * (1 - provide_fee)
+            let provide_fee = concentrated_provide_fee(&params, &ideposits, &new_xp)?;
+            let share = total_share
+                .multiply_ratio(new_d.atomics(), old_d.atomics())
+                .checked_sub(total_share)?;
+            decimal256_to_uint(
+                Decimal256::from_ratio(share, 1u128)
+                    .checked_mul(Decimal256::one().checked_sub(provide_fee)?)?,
+                0,
+            )?
+        };
+
+        if share.is_zero() {
+            return Err(CwDexError::LiquidityAmountTooSmall {});
+        }
+
+        Ok(Asset {
+            info: AssetInfo::Cw20(self.lp_token_addr.clone()),
+            amount: share,
+        })
+    }
+
+    /// Price of `base_asset` in `quote_asset` of an Astroport concentrated
+    /// pair, approximated by the pair's internal price scale.
+    fn concentrated_spot_price(
+        &self,
+        deps: Deps,
+        base_asset: &AssetInfo,
+        quote_asset: &AssetInfo,
+    ) -> StdResult<Decimal> {
+        let params = self.query_concentrated_params(&deps.querier)?;
+        let pools = self.query_pool_info(&deps.querier)?.assets;
+        assert_two_pool_assets(&pools)?;
+        let base = find_pool_asset(&pools, base_asset)?;
+        let quote = find_pool_asset(&pools, quote_asset)?;
+
+        let base_precision = query_token_precision(&deps.querier, base.info.clone())?;
+        let quote_precision = query_token_precision(&deps.querier, quote.info.clone())?;
+
+        // The price scale is the price of the second asset denominated in the
+        // first asset, in units of the tokens.
+        let price = if base.info == pools[1].info {
+            params.price_scale
+        } else {
+            Decimal::one()
+                .checked_div(params.price_scale)
+                .map_err(|e| StdError::generic_err(e.to_string()))?
+        };
+
+        // Convert the price of whole tokens to a price of the raw amounts.
+        Ok(price
+            * Decimal::from_ratio(
+                10u128.pow(quote_precision.into()),
+                10u128.pow(base_precision.into()),
+            ))
+    }
+
+    /// Marginal price of `base_asset` in `quote_asset` of an Astroport stable
+    /// swap pool, derived from the stable swap invariant.
+    fn stable_spot_price(
+        &self,
+        deps: Deps,
+        env: &Env,
+        base_asset: &AssetInfo,
+        quote_asset: &AssetInfo,
+    ) -> StdResult<Decimal> {
+        let config = query_pair_config(&deps.querier, self.pair_addr.clone())?;
+        let pools = config
+            .pair_info
+            .query_pools(&deps.querier, self.pair_addr.to_owned())?;
+        let base = find_pool_asset_index(&pools, base_asset)?;
+        let quote = find_pool_asset_index(&pools, quote_asset)?;
+
+        let token_precisions = pools
+            .iter()
+            .map(|pool| query_token_precision(&deps.querier, pool.info.clone()))
+            .collect::<StdResult<Vec<u8>>>()?;
+        let greater_precision = token_precisions.iter().copied().max().unwrap_or_default();
+        let base_precision = token_precisions[base];
+        let quote_precision = token_precisions[quote];
+
+        let leverage = compute_current_amp(&config, env)?
+            .checked_mul(pools.len() as u64)
+            .ok_or_else(|| StdError::generic_err("Amplification overflow"))?;
+
+        let amounts = pools
+            .iter()
+            .zip(token_precisions.iter())
+            .map(|(pool, precision)| {
+                Ok(adjust_precision(pool.amount, *precision, greater_precision)?.u128())
+            })
+            .collect::<StdResult<Vec<u128>>>()?;
+
+        let price = compute_stable_spot_price(leverage, &amounts, base, quote)
+            .ok_or_else(|| StdError::generic_err("Failed to compute stable swap spot price"))?;
+
+        // Convert the price of the precision adjusted amounts back to a price
+        // of the raw amounts.
+        Ok(price
+            * Decimal::from_ratio(
+                10u128.pow(quote_precision.into()),
+                10u128.pow(base_precision.into()),
+            ))
+    }
+
+    /// Conservative lower bound of the value of the reserves of an Astroport
+    /// stable swap pool at `prices`.
+    ///
+    /// The stable swap curve lies between the constant sum curve
+    /// `sum(x_i) = D` and the constant product curve, so the reserves sum to at
+    /// least D at every point of the curve, including the one arbitrage
+    /// moves the pool to at `prices`. D valued at the lowest of `prices` is
+    /// therefore never more than the fair value of the pool. The bound is
+    /// tight while the assets trade at their peg and undervalues the pool
+    /// when `prices` diverge from it.
+    fn stable_value_lower_bound(
+        &self,
+        deps: Deps,
+        env: &Env,
+        prices: &[(AssetInfo, Decimal)],
+    ) -> Result<Decimal256, CwDexError> {
+        let config = query_pair_config(&deps.querier, self.pair_addr.clone())?;
+        let pools = config
+            .pair_info
+            .query_pools(&deps.querier, self.pair_addr.to_owned())?;
+
+        let token_precisions = pools
+            .iter()
+            .map(|pool| query_token_precision(&deps.querier, pool.info.clone()))
+            .collect::<StdResult<Vec<u8>>>()?;
+        let greater_precision = token_precisions.iter().copied().max().unwrap_or_default();
+
+        let leverage = compute_current_amp(&config, env)?
+            .checked_mul(pools.len() as u64)
+            .ok_or_else(|| StdError::generic_err("Amplification overflow"))?;
+
+        let amounts = pools
+            .iter()
+            .zip(token_precisions.iter())
+            .map(|(pool, precision)| {
+                Ok(adjust_precision(pool.amount, *precision, greater_precision)?.u128())
+            })
+            .collect::<StdResult<Vec<u128>>>()?;
+        let d = compute_d(leverage, &amounts)?;
+
+        // The price of one precision adjusted unit of each asset
+        let mut min_price: Option<Decimal256> = None;
+        for (pool, precision) in pools.iter().zip(token_precisions) {
+            let price = Decimal256::from(reference_price(prices, &pool.info.clone().into())?)
+                .checked_mul(Decimal256::from_ratio(
+                    1u128,
+                    10u128.pow((greater_precision - precision).into()),
+                ))?;
+            min_price = Some(min_price.map_or(price, |min| min.min(price)));
+        }
+
+        Ok(Decimal256::from_ratio(d, 1u128).checked_mul(min_price.unwrap_or_default())?)
+    }
+
+    /// Conservative lower bound of the value of the reserves of an Astroport
+    /// passive concentrated liquidity pool at `prices`.
+    ///
+    /// Like the stable swap curve, the concentrated curve lies between the
+    /// constant sum and constant product curves of the reserves scaled by the
+    /// price scale, so D valued at the lowest of `prices` of one unit of the
+    /// scaled reserves is never more than the fair value of the pool. The
+    /// bound is tight while `prices` are close to the price scale.
+    fn concentrated_value_lower_bound(
+        &self,
+        deps: Deps,
+        prices: &[(AssetInfo, Decimal)],
+    ) -> Result<Decimal256, CwDexError> {
+        let params = self.query_concentrated_params(&deps.querier)?;
+        let pools = self.query_pool_info(&deps.querier)?.assets;
+        assert_two_pool_assets(&pools)?;
+
+        let price_scale = Decimal256::from(params.price_scale);
+        let precisions = [
+            query_token_precision(&deps.querier, pools[0].info.clone())?,
+            query_token_precision(&deps.querier, pools[1].info.clone())?,
+        ];
+        let to_dec = |amount: Uint128, precision: u8| {
+            Decimal256::from_atomics(amount, precision.into())
+                .map_err(|e| StdError::generic_err(e.to_string()))
+        };
+        let xp = [
+            to_dec(pools[0].amount, precisions[0])?,
+            to_dec(pools[1].amount, precisions[1])?.checked_mul(price_scale)?,
+        ];
+
+        let ann = Decimal256::from(params.amp).checked_mul(Decimal256::percent(400))?;
+        let gamma = Decimal256::from(params.gamma);
+        let d = concentrated_compute_d(ann, gamma, &xp)?;
+
+        // The price of one unit of each scaled reserve, i.e. one whole token
+        // of the first asset and 1 / price_scale whole tokens of the second.
+        let whole_token_price = |index: usize| -> Result<Decimal256, CwDexError> {
+            Ok(
+                Decimal256::from(reference_price(prices, &pools[index].info.clone().into())?)
+                    .checked_mul(Decimal256::from_ratio(
+                        10u128.pow(precisions[index].into()),
+                        1u128,
+                    ))?,
+            )
+        };
+        let scaled_prices = [
+            whole_token_price(0)?,
+            whole_token_price(1)?
+                .checked_div(price_scale)
+                .map_err(|e| StdError::generic_err(e.to_string()))?,
+        ];
+
+        Ok(d.checked_mul(scaled_prices[0].min(scaled_prices[1]))?)
+    }
+}
+
+/// The swap and expected outcome of zapping into an XYK pair
+struct ZapIn {
+    /// The amount of the zapped asset to swap
+    swap_amount: Uint128,
+    /// The expected return of the swap
+    return_asset: Asset,
+    /// The expected amount of LP tokens
+    lp_amount: Uint128,
+    /// The amount of LP tokens the larger of the deposits relative to the
+    /// reserves after the swap would mint
+    max_lp_amount: Uint128,
+}
+
+/// Returns `a * b / c`, rounded up.
+fn multiply_ratio_ceil(a: Uint128, b: Uint128, c: Uint128) -> StdResult<Uint128> {
+    let c = Uint256::from(c);
+    let result = a
+        .full_mul(b)
+        .checked_add(c.checked_sub(Uint256::from(1u128))?)?
+        .checked_div(c)?;
+    Ok(Uint128::try_from(result)?)
+}
+
+/// Returns the asset in `pools` matching `info`.
+fn find_pool_asset<'a>(
+    pools: &'a [AstroAsset],
+    info: &AssetInfo,
+) -> Result<&'a AstroAsset, CwDexError> {
+    Ok(&pools[find_pool_asset_index(pools, info)?])
+}
+
+/// Returns the index of the asset in `pools` matching `info`.
+fn find_pool_asset_index(pools: &[AstroAsset], info: &AssetInfo) -> Result<usize, CwDexError> {
+    pools
+        .iter()
+        .position(|a| AssetInfo::from(a.info.clone()) == *info)
+        .ok_or_else(|| CwDexError::AssetNotInPool {
+            asset: info.clone(),
+        })
+}
+
+/// Asserts that the pair has exactly two assets, for the XYK and concentrated
+/// pair math that indexes the assets directly.
+fn assert_two_pool_assets(pools: &[AstroAsset]) -> Result<(), CwDexError> {
+    if pools.len() != 2 {
+        return Err(CwDexError::InvalidPoolAssetCount {
+            expected: 2,
+            found: pools.len(),
         });
+    }
+    Ok(())
+}
+
+impl Pool for AstroportPool {
+    fn provide_liquidity(
+        &self,
+        deps: Deps,
+        env: &Env,
+        assets: AssetList,
+        min_out: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        let simulation = self.simulate_provide_liquidity_detailed(deps, env, assets)?;
+        if min_out > simulation.lp_token.amount {
+            return Err(CwDexError::MinOutNotReceived {
+                min_out,
+                received: simulation.lp_token.amount,
+            });
+        }
+
+        let slippage_tolerance =
+            self.provide_slippage_tolerance(deps, &simulation.assets_to_use, min_out)?;
+        // Only XYK pairs enforce `min_out` through the slippage tolerance
+        let assert_min_out_msg = match &self.pair_type {
+            PairType::Xyk {} => None,
+            _ => CwDexCallbackMsg::assert_min_out(
+                deps,
+                env,
+                &vec![Asset::new(self.lp_token(), min_out)].into(),
+            )?,
+        };
 
         let event = Event::new("apollo/cw-dex/provide_liquidity")
             .add_attribute("pair_addr", &self.pair_addr)
-            .add_attribute("assets", format!("{:?}", assets));
+            .add_attribute("assets", format!("{:?}", simulation.assets_to_use))
+            .add_attribute("leftover", format!("{:?}", simulation.leftover))
+            .add_attribute("slippage_tolerance", slippage_tolerance.to_string());
 
-        Ok(Response::new()
-            .add_messages(allowance_msgs)
-            .add_message(provide_liquidity)
-            .add_event(event))
+        Ok(CwDexResponse::new()
+            .add_messages(self.provide_liquidity_msgs(
+                env,
+                &simulation.assets_to_use,
+                slippage_tolerance,
+            )?)
+            .add_messages(assert_min_out_msg)
+            .add_event(event)
+            .with_expected_out(vec![simulation.lp_token])
+            .with_leftover(simulation.leftover))
+    }
+
+    fn zap_in(
+        &self,
+        deps: Deps,
+        env: &Env,
+        asset: Asset,
+        min_out: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        self.validate_assets(&vec![asset.clone()].into())?;
+
+        match &self.pair_type {
+            PairType::Xyk {} => {
+                let zap = self.xyk_simulate_zap_in(deps, &asset)?;
+                if min_out > zap.lp_amount {
+                    return Err(CwDexError::MinOutNotReceived {
+                        min_out,
+                        received: zap.lp_amount,
+                    });
+                }
+
+                let swap = self.swap(
+                    deps,
+                    env,
+                    Asset::new(asset.info.clone(), zap.swap_amount),
+                    zap.return_asset.info.clone(),
+                    zap.return_asset.amount,
+                )?;
+                let provide_assets: AssetList = vec![
+                    Asset::new(asset.info.clone(), asset.amount - zap.swap_amount),
+                    zap.return_asset.clone(),
+                ]
+                .into();
+
+                let event = Event::new("apollo/cw-dex/zap_in")
+                    .add_attribute("pair_addr", &self.pair_addr)
+                    .add_attribute("asset", asset.to_string())
+                    .add_attribute("swap_amount", zap.swap_amount)
+                    .add_attribute("expected_lp", zap.lp_amount);
+
+                Ok(CwDexResponse::new()
+                    .add_submessages(swap.messages)
+                    .add_messages(self.provide_liquidity_msgs(
+                        env,
+                        &provide_assets,
+                        xyk_slippage_tolerance(min_out, zap.max_lp_amount)?,
+                    )?)
+                    .add_events(swap.events)
+                    .add_event(event)
+                    .with_expected_out(vec![Asset::new(self.lp_token(), zap.lp_amount)]))
+            }
+            // Stable swap and concentrated pairs accept single sided provides
+            _ => self.provide_liquidity(deps, env, vec![asset].into(), min_out),
+        }
     }
 
     fn withdraw_liquidity(
         &self,
-        _deps: Deps,
-        _env: &Env,
+        deps: Deps,
+        env: &Env,
         asset: Asset,
-    ) -> Result<Response, CwDexError> {
+        min_out: AssetList,
+    ) -> Result<CwDexResponse, CwDexError> {
         if let AssetInfoBase::Cw20(token_addr) = &asset.info {
+            // Astroport pairs have no minimum amounts out when withdrawing, so
+            // they are asserted by a callback after the withdrawal. Return an
+            // error early if the simulation already falls short.
+            let expected_out = self.simulate_withdraw_liquidity(deps, &asset)?;
+            assert_min_out(&expected_out, &min_out)?;
+            let assert_min_out_msg = CwDexCallbackMsg::assert_min_out(deps, env, &min_out)?;
+
             let withdraw_liquidity = CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: token_addr.to_string(),
                 msg: to_binary(&Cw20ExecuteMsg::Send {
@@ -319,26 +1025,59 @@ impl Pool for AstroportPool {
                 .add_attribute("asset", format!("{:?}", asset))
                 .add_attribute("token_amount", asset.amount);
 
-            Ok(Response::new()
+            Ok(CwDexResponse::new()
                 .add_message(withdraw_liquidity)
-                .add_event(event))
+                .add_messages(assert_min_out_msg)
+                .add_event(event)
+                .with_expected_out(expected_out))
         } else {
             Err(CwDexError::InvalidInAsset { a: asset })
         }
     }
 
+    fn zap_out(
+        &self,
+        deps: Deps,
+        env: &Env,
+        lp_token: Asset,
+        ask_asset_info: AssetInfo,
+        min_out: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        let event = Event::new("apollo/cw-dex/zap_out")
+            .add_attribute("pair_addr", &self.pair_addr)
+            .add_attribute("lp_token", lp_token.to_string())
+            .add_attribute("ask_asset", ask_asset_info.to_string())
+            .add_attribute("minimum_out_amount", min_out);
+
+        Ok(
+            withdraw_and_swap(self, deps, env, lp_token, &ask_asset_info, min_out)?
+                .add_event(event),
+        )
+    }
+
     fn swap(
         &self,
-        _deps: Deps,
+        deps: Deps,
         env: &Env,
         offer_asset: Asset,
         ask_asset_info: AssetInfo,
         min_out: Uint128,
-    ) -> Result<Response, CwDexError> {
+    ) -> Result<CwDexResponse, CwDexError> {
+        let expected_out = self.simulate_swap(deps, offer_asset.clone(), ask_asset_info.clone())?;
+
         // Setting belief price to the minimium acceptable return and max spread to zero
         // simplifies things Astroport will make the best possible swap that
-        // returns at least `min_out`.
-        let belief_price = Some(Decimal::from_ratio(offer_asset.amount, min_out));
+        // returns at least `min_out`. Without a minimum there is no belief
+        // price, so the swap is only limited by the maximum spread the pair
+        // allows.
+        let (belief_price, max_spread) = if min_out.is_zero() {
+            (None, Decimal::from_str(MAX_ALLOWED_SLIPPAGE)?)
+        } else {
+            (
+                Some(Decimal::from_ratio(offer_asset.amount, min_out)),
+                Decimal::zero(),
+            )
+        };
         let swap_msg = match &offer_asset.info {
             AssetInfo::Native(_) => {
                 let asset = offer_asset.clone().into();
@@ -347,7 +1086,7 @@ impl Pool for AstroportPool {
                     &PairExecuteMsg::Swap {
                         offer_asset: asset,
                         belief_price,
-                        max_spread: Some(Decimal::zero()),
+                        max_spread: Some(max_spread),
                         to: Some(env.contract.address.to_string()),
                     },
                     vec![offer_asset.clone().try_into()?],
@@ -360,7 +1099,7 @@ impl Pool for AstroportPool {
                     amount: offer_asset.amount,
                     msg: to_binary(&PairCw20HookMsg::Swap {
                         belief_price,
-                        max_spread: Some(Decimal::zero()),
+                        max_spread: Some(max_spread),
                         to: Some(env.contract.address.to_string()),
                     })?,
                 },
@@ -372,12 +1111,48 @@ impl Pool for AstroportPool {
             .add_attribute("ask_asset", format!("{:?}", ask_asset_info))
             .add_attribute("offer_asset", format!("{:?}", offer_asset.info))
             .add_attribute("minimum_out_amount", min_out);
-        Ok(Response::new().add_message(swap_msg).add_event(event))
+        Ok(CwDexResponse::new()
+            .add_message(swap_msg)
+            .add_event(event)
+            .with_expected_out(vec![Asset::new(ask_asset_info, expected_out)]))
+    }
+
+    fn swap_exact_out(
+        &self,
+        deps: Deps,
+        env: &Env,
+        ask_asset: Asset,
+        offer_asset_info: AssetInfo,
+        max_in: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        if ask_asset.amount.is_zero() {
+            return Err(CwDexError::InvalidZeroAmount {});
+        }
+
+        // Astroport has no exact out swap message, so we offer the amount
+        // returned by the reverse simulation and require at least the asked
+        // amount back.
+        let offer_amount =
+            self.simulate_swap_exact_out(deps, ask_asset.clone(), offer_asset_info.clone())?;
+        if offer_amount > max_in {
+            return Err(CwDexError::MaxInExceeded {
+                max_in,
+                required: offer_amount,
+            });
+        }
+
+        self.swap(
+            deps,
+            env,
+            Asset::new(offer_asset_info, offer_amount),
+            ask_asset.info,
+            ask_asset.amount,
+        )
     }
 
     fn get_pool_liquidity(&self, deps: Deps) -> Result<AssetList, CwDexError> {
         let resp = self.query_pool_info(&deps.querier)?;
-        Ok(resp.assets.to_vec().into())
+        Ok(resp.assets.into())
     }
 
     fn simulate_provide_liquidity(
@@ -386,12 +1161,20 @@ impl Pool for AstroportPool {
         env: &Env,
         assets: AssetList,
     ) -> Result<Asset, CwDexError> {
-        match self.pair_type {
-            PairType::Xyk {} => self.xyk_simulate_provide_liquidity(deps, env, assets),
-            PairType::Stable {} => self.stable_simulate_provide_liquidity(deps, env, assets),
-            PairType::Custom(_) => Err(CwDexError::Std(StdError::generic_err(
-                "custom pair type not supported",
-            ))),
+        Ok(self
+            .simulate_provide_liquidity_detailed(deps, env, assets)?
+            .lp_token)
+    }
+
+    fn simulate_zap_in(&self, deps: Deps, env: &Env, asset: Asset) -> Result<Asset, CwDexError> {
+        self.validate_assets(&vec![asset.clone()].into())?;
+
+        match &self.pair_type {
+            PairType::Xyk {} => Ok(Asset::new(
+                self.lp_token(),
+                self.xyk_simulate_zap_in(deps, &asset)?.lp_amount,
+            )),
+            _ => self.simulate_provide_liquidity(deps, env, vec![asset].into()),
         }
     }
 
@@ -418,12 +1201,22 @@ impl Pool for AstroportPool {
             .into())
     }
 
+    fn simulate_zap_out(
+        &self,
+        deps: Deps,
+        lp_token: &Asset,
+        ask_asset_info: AssetInfo,
+    ) -> Result<Asset, CwDexError> {
+        let simulation = simulate_zap_out(self, deps, lp_token, &ask_asset_info)?;
+
+        Ok(Asset::new(ask_asset_info, simulation.return_amount))
+    }
+
     fn simulate_swap(
         &self,
         deps: Deps,
         offer_asset: Asset,
         _ask_asset_info: AssetInfo,
-        _sender: Option<String>,
     ) -> StdResult<Uint128> {
         Ok(deps
             .querier
@@ -436,6 +1229,165 @@ impl Pool for AstroportPool {
             .return_amount)
     }
 
+    fn simulate_swap_detailed(
+        &self,
+        deps: Deps,
+        offer_asset: Asset,
+        _ask_asset_info: AssetInfo,
+    ) -> StdResult<SwapSimulation> {
+        let SimulationResponse {
+            return_amount,
+            spread_amount,
+            commission_amount,
+        } = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: self.pair_addr.to_string(),
+            msg: to_binary(&PairQueryMsg::Simulation {
+                offer_asset: offer_asset.into(),
+            })?,
+        }))?;
+
+        Ok(SwapSimulation {
+            return_amount,
+            spread_amount,
+            commission_amount,
+        })
+    }
+
+    fn spot_price(
+        &self,
+        deps: Deps,
+        env: &Env,
+        base_asset: &AssetInfo,
+        quote_asset: &AssetInfo,
+    ) -> StdResult<Decimal> {
+        match &self.pair_type {
+            PairType::Xyk {} => {
+                let pools = self.query_pool_info(&deps.querier)?.assets;
+                let base = find_pool_asset(&pools, base_asset)?;
+                let quote = find_pool_asset(&pools, quote_asset)?;
+
+                Decimal::checked_from_ratio(quote.amount, base.amount)
+                    .map_err(|_| StdError::generic_err("Pool has no liquidity"))
+            }
+            PairType::Stable {} => self.stable_spot_price(deps, env, base_asset, quote_asset),
+            PairType::Custom(t) if t == CONCENTRATED_PAIR_TYPE => {
+                self.concentrated_spot_price(deps, base_asset, quote_asset)
+            }
+            PairType::Custom(_) => Err(StdError::generic_err("custom pair type not supported")),
+        }
+    }
+
+    fn pool_params(&self, deps: Deps, env: &Env) -> Result<PoolParams, CwDexError> {
+        match &self.pair_type {
+            PairType::Custom(t) if t == CONCENTRATED_PAIR_TYPE => {
+                let params = self.query_concentrated_params(&deps.querier)?;
+                let pools = self.query_pool_info(&deps.querier)?.assets;
+                assert_two_pool_assets(&pools)?;
+
+                // The fee depends on how far the balances, in units of the
+                // tokens and denominated in the first asset, are from balanced
+                let mut xp = [Decimal256::zero(); 2];
+                for (i, pool) in pools.iter().enumerate() {
+                    let precision = query_token_precision(&deps.querier, pool.info.clone())?;
+                    xp[i] = Decimal256::from_atomics(pool.amount, precision.into())
+                        .map_err(|e| StdError::generic_err(e.to_string()))?;
+                }
+                xp[1] = xp[1].checked_mul(params.price_scale.into())?;
+                let swap_fee = concentrated_fee(&params, &xp)?;
+
+                Ok(PoolParams {
+                    pool_type: PoolType::ConcentratedLiquidity,
+                    swap_fee: Decimal::new(
+                        Uint128::try_from(swap_fee.atomics()).map_err(StdError::from)?,
+                    ),
+                    exit_fee: Decimal::zero(),
+                    weights: None,
+                    amp: Some(params.amp),
+                })
+            }
+            PairType::Custom(_) => {
+                Err(StdError::generic_err("custom pair type not supported").into())
+            }
+            pair_type => {
+                let factory_addr = query_pair_factory(&deps.querier, self.pair_addr.clone())?;
+                let fee_info = query_fee_info(&deps.querier, factory_addr, pair_type.clone())?;
+                let (pool_type, amp) = match pair_type {
+                    PairType::Stable {} => {
+                        let config = query_pair_config(&deps.querier, self.pair_addr.clone())?;
+                        let amp = compute_current_amp(&config, env)?;
+                        (
+                            PoolType::StableSwap,
+                            Some(Decimal::from_ratio(amp, AMP_PRECISION)),
+                        )
+                    }
+                    _ => (PoolType::ConstantProduct, None),
+                };
+
+                Ok(PoolParams {
+                    pool_type,
+                    swap_fee: fee_info.total_fee_rate,
+                    exit_fee: Decimal::zero(),
+                    weights: None,
+                    amp,
+                })
+            }
+        }
+    }
+
+    /// For XYK pairs this is the fair price of the LP token. For stable and
+    /// concentrated pairs it is a conservative lower bound of the fair price,
+    /// see `stable_value_lower_bound` and `concentrated_value_lower_bound`.
+    fn fair_lp_token_price(
+        &self,
+        deps: Deps,
+        env: &Env,
+        prices: &[(AssetInfo, Decimal)],
+    ) -> Result<Decimal, CwDexError> {
+        let pool = self.query_pool_info(&deps.querier)?;
+        let value = match &self.pair_type {
+            PairType::Xyk {} => match &pool.assets[..] {
+                [x, y] => xyk_fair_value([
+                    (x.amount, reference_price(prices, &x.info.clone().into())?),
+                    (y.amount, reference_price(prices, &y.info.clone().into())?),
+                ])?,
+                _ => {
+                    return Err(CwDexError::InvalidPoolAssetCount {
+                        expected: 2,
+                        found: pool.assets.len(),
+                    })
+                }
+            },
+            PairType::Stable {} => self.stable_value_lower_bound(deps, env, prices)?,
+            PairType::Custom(t) if t == CONCENTRATED_PAIR_TYPE => {
+                self.concentrated_value_lower_bound(deps, prices)?
+            }
+            PairType::Custom(_) => {
+                return Err(CwDexError::UnsupportedPoolOperation {
+                    operation: "fair_lp_token_price".to_string(),
+                })
+            }
+        };
+
+        Ok(lp_price_from_value(value, pool.total_share)?)
+    }
+
+    fn simulate_swap_exact_out(
+        &self,
+        deps: Deps,
+        ask_asset: Asset,
+        _offer_asset_info: AssetInfo,
+    ) -> StdResult<Uint128> {
+        Ok(deps
+            .querier
+            .query::<ReverseSimulationResponse>(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: self.pair_addr.to_string(),
+                msg: to_binary(&PairQueryMsg::ReverseSimulation {
+                    ask_asset: ask_asset.into(),
+                })?,
+            }))?
+            .offer_amount)
+    }
+
     fn lp_token(&self) -> AssetInfo {
         AssetInfoBase::Cw20(self.lp_token_addr.clone())
     }
@@ -444,3 +1396,602 @@ impl Pool for AstroportPool {
         Ok(self.pool_assets.clone())
     }
 }
+
+/// Astroport pairs only expose their cumulative prices, so TWAPs are computed
+/// from snapshots stored by the caller.
+impl PriceOracle for AstroportPool {
+    fn query_cumulative_prices(
+        &self,
+        deps: Deps,
+        env: &Env,
+    ) -> Result<CumulativePricesSnapshot, CwDexError> {
+        let response = deps
+            .querier
+            .query::<CumulativePricesResponse>(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: self.pair_addr.to_string(),
+                msg: to_binary(&PairQueryMsg::CumulativePrices {})?,
+            }))?;
+
+        // The pair accumulates the prices up to the current block when queried
+        Ok(CumulativePricesSnapshot {
+            timestamp: env.block.time.seconds(),
+            cumulative_prices: response
+                .cumulative_prices
+                .into_iter()
+                .map(|(base, quote, price)| (base.into(), quote.into(), price))
+                .collect(),
+        })
+    }
+
+    fn query_twap_price(
+        &self,
+        deps: Deps,
+        env: &Env,
+        base_asset_info: &AssetInfo,
+        quote_asset_info: &AssetInfo,
+        window: u64,
+        snapshots: &[CumulativePricesSnapshot],
+    ) -> Result<Decimal, CwDexError> {
+        let now = env.block.time.seconds();
+        let start = snapshots
+            .iter()
+            .filter(|s| s.timestamp < now && s.timestamp <= now.saturating_sub(window))
+            .max_by_key(|s| s.timestamp)
+            .ok_or_else(|| CwDexError::TwapUnavailable {
+                reason: format!("no snapshot taken at least {} seconds ago", window),
+            })?;
+        let end = self.query_cumulative_prices(deps, env)?;
+
+        let cumulative_price = |snapshot: &CumulativePricesSnapshot| {
+            snapshot
+                .cumulative_price(base_asset_info, quote_asset_info)
+                .ok_or_else(|| CwDexError::TwapUnavailable {
+                    reason: format!(
+                        "no cumulative price of {} in {}",
+                        base_asset_info, quote_asset_info
+                    ),
+                })
+        };
+
+        Ok(twap_from_cumulative_prices(
+            cumulative_price(start)?,
+            cumulative_price(&end)?,
+            now - start.timestamp,
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{coin, Binary, ContractResult, SystemResult};
+
+    use super::*;
+    use crate::implementations::astroport::msg::{Config, FeeInfoResponse, PairFactoryConfig};
+
+    fn pool(pair_type: PairType) -> AstroportPool {
+        AstroportPool {
+            pair_addr: Addr::unchecked("pair"),
+            lp_token_addr: Addr::unchecked("lp_token"),
+            pool_assets: vec![
+                AssetInfo::native("uatom"),
+                AssetInfo::cw20(Addr::unchecked("astro")),
+            ],
+            pair_type,
+        }
+    }
+
+    #[test]
+    fn test_simulate_provide_liquidity_asset_not_in_pool() {
+        let deps = mock_dependencies();
+        let assets: AssetList = vec![
+            Asset::native("uatom", 100u128),
+            Asset::native("uosmo", 100u128),
+        ]
+        .into();
+
+        for pair_type in [
+            PairType::Xyk {},
+            PairType::Stable {},
+            PairType::Custom(CONCENTRATED_PAIR_TYPE.to_string()),
+        ] {
+            let err = pool(pair_type)
+                .simulate_provide_liquidity(deps.as_ref(), &mock_env(), assets.clone())
+                .unwrap_err();
+            assert_eq!(
+                err,
+                CwDexError::AssetNotInPool {
+                    asset: AssetInfo::native("uosmo")
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_xyk_simulate_provide_liquidity_leftover() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == "pair" => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&PoolResponse {
+                        assets: vec![
+                            Asset::native("uatom", 1_000_000u128).into(),
+                            Asset::cw20(Addr::unchecked("astro"), 2_000_000u128).into(),
+                        ],
+                        total_share: Uint128::new(1_000_000),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => panic!("unexpected query"),
+        });
+
+        let assets: AssetList = vec![
+            Asset::native("uatom", 1_000u128),
+            Asset::cw20(Addr::unchecked("astro"), 3_000u128),
+        ]
+        .into();
+        let simulation = pool(PairType::Xyk {})
+            .simulate_provide_liquidity_detailed(deps.as_ref(), &mock_env(), assets)
+            .unwrap();
+
+        assert_eq!(
+            simulation.lp_token,
+            Asset::cw20(Addr::unchecked("lp_token"), 1_000u128)
+        );
+        assert_eq!(
+            simulation.assets_to_use,
+            vec![
+                Asset::native("uatom", 1_000u128),
+                Asset::cw20(Addr::unchecked("astro"), 2_000u128),
+            ]
+            .into()
+        );
+        assert_eq!(
+            simulation.leftover,
+            vec![Asset::cw20(Addr::unchecked("astro"), 1_000u128)].into()
+        );
+    }
+
+    #[test]
+    fn test_stable_simulate_provide_liquidity_initial_share_n_assets() {
+        let denoms = ["uatom", "uosmo", "ujuno"];
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(move |query| {
+            let res = match query {
+                WasmQuery::Raw { contract_addr, .. } if contract_addr == "pair" => {
+                    to_binary(&Config {
+                        pair_info: PairInfo {
+                            asset_infos: denoms
+                                .iter()
+                                .map(|denom| AstroAssetInfo::NativeToken {
+                                    denom: denom.to_string(),
+                                })
+                                .collect(),
+                            contract_addr: Addr::unchecked("pair"),
+                            liquidity_token: Addr::unchecked("lp_token"),
+                            pair_type: PairType::Stable {},
+                        },
+                        factory_addr: Addr::unchecked("factory"),
+                        block_time_last: 0,
+                        price0_cumulative_last: Uint128::zero(),
+                        price1_cumulative_last: Uint128::zero(),
+                        init_amp: 100 * AMP_PRECISION,
+                        init_amp_time: 0,
+                        next_amp: 100 * AMP_PRECISION,
+                        next_amp_time: 0,
+                    })
+                }
+                WasmQuery::Smart { contract_addr, .. } if contract_addr == "lp_token" => {
+                    to_binary(&cw20::TokenInfoResponse {
+                        name: "lp".to_string(),
+                        symbol: "LP".to_string(),
+                        decimals: 6,
+                        total_supply: Uint128::zero(),
+                    })
+                }
+                _ => panic!("unexpected query"),
+            };
+            SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+        });
+        let pool = AstroportPool {
+            pool_assets: denoms
+                .iter()
+                .map(|denom| AssetInfo::native(*denom))
+                .collect(),
+            ..pool(PairType::Stable {})
+        };
+        let deposit = |amount: u128| -> AssetList {
+            denoms
+                .iter()
+                .map(|denom| Asset::native(*denom, amount))
+                .collect::<Vec<_>>()
+                .into()
+        };
+
+        // The initial share of a pair with more than two assets is D, of which
+        // MINIMUM_LIQUIDITY_AMOUNT is locked in the pair
+        let lp_token = pool
+            .simulate_provide_liquidity(deps.as_ref(), &mock_env(), deposit(1_000_000))
+            .unwrap();
+        let d = compute_d(100 * AMP_PRECISION * 3, &[1_000_000; 3]).unwrap();
+        assert_eq!(
+            lp_token,
+            Asset::cw20(Addr::unchecked("lp_token"), d - MINIMUM_LIQUIDITY_AMOUNT)
+        );
+
+        let err = pool
+            .simulate_provide_liquidity(deps.as_ref(), &mock_env(), deposit(100))
+            .unwrap_err();
+        assert_eq!(err, CwDexError::LiquidityAmountTooSmall {});
+    }
+
+    #[test]
+    fn test_stable_fair_lp_token_price_lower_bound() {
+        let denoms = ["uatom", "uosmo"];
+        let mut deps = mock_dependencies();
+        deps.querier.update_balance(
+            "pair",
+            vec![coin(1_000_000, "uatom"), coin(1_000_000, "uosmo")],
+        );
+        deps.querier.update_wasm(move |query| {
+            let res = match query {
+                WasmQuery::Raw { contract_addr, .. } if contract_addr == "pair" => {
+                    to_binary(&Config {
+                        pair_info: PairInfo {
+                            asset_infos: denoms
+                                .iter()
+                                .map(|denom| AstroAssetInfo::NativeToken {
+                                    denom: denom.to_string(),
+                                })
+                                .collect(),
+                            contract_addr: Addr::unchecked("pair"),
+                            liquidity_token: Addr::unchecked("lp_token"),
+                            pair_type: PairType::Stable {},
+                        },
+                        factory_addr: Addr::unchecked("factory"),
+                        block_time_last: 0,
+                        price0_cumulative_last: Uint128::zero(),
+                        price1_cumulative_last: Uint128::zero(),
+                        init_amp: 100 * AMP_PRECISION,
+                        init_amp_time: 0,
+                        next_amp: 100 * AMP_PRECISION,
+                        next_amp_time: 0,
+                    })
+                }
+                WasmQuery::Smart { contract_addr, .. } if contract_addr == "pair" => {
+                    to_binary(&PoolResponse {
+                        assets: vec![
+                            Asset::native("uatom", 1_000_000u128).into(),
+                            Asset::native("uosmo", 1_000_000u128).into(),
+                        ],
+                        total_share: Uint128::new(2_000_000),
+                    })
+                }
+                _ => panic!("unexpected query"),
+            };
+            SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+        });
+        let pool = AstroportPool {
+            pool_assets: denoms
+                .iter()
+                .map(|denom| AssetInfo::native(*denom))
+                .collect(),
+            ..pool(PairType::Stable {})
+        };
+        let prices = [
+            (AssetInfo::native("uatom"), Decimal::percent(200)),
+            (AssetInfo::native("uosmo"), Decimal::one()),
+        ];
+
+        // D valued at the lowest price, which is less than the value of the
+        // reserves at the reference prices
+        let price = pool
+            .fair_lp_token_price(deps.as_ref(), &mock_env(), &prices)
+            .unwrap();
+        let d = compute_d(100 * AMP_PRECISION * 2, &[1_000_000; 2]).unwrap();
+        assert_eq!(price, Decimal::from_ratio(d, 2_000_000u128));
+        assert!(price < Decimal::from_ratio(3_000_000u128, 2_000_000u128));
+    }
+
+    #[test]
+    fn test_concentrated_fair_lp_token_price_lower_bound() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| {
+            let res = match query {
+                WasmQuery::Smart { contract_addr, msg } if contract_addr == "pair" => {
+                    match from_binary(msg).unwrap() {
+                        PairQueryMsg::Pool {} => to_binary(&PoolResponse {
+                            assets: vec![
+                                Asset::native("uatom", 1_000_000u128).into(),
+                                Asset::native("uosmo", 500_000u128).into(),
+                            ],
+                            total_share: Uint128::new(1_000_000),
+                        }),
+                        PairQueryMsg::Config {} => to_binary(&ConfigResponse {
+                            block_time_last: 0,
+                            params: Some(Binary::from(
+                                br#"{"amp":"10","gamma":"0.000145","mid_fee":"0.0026","out_fee":"0.0045","fee_gamma":"0.00023","repeg_profit_threshold":"0.000002","min_price_scale_delta":"0.000146","price_scale":"2","ma_half_time":600}"#
+                                    .as_slice(),
+                            )),
+                        }),
+                        _ => panic!("unexpected query"),
+                    }
+                }
+                _ => panic!("unexpected query"),
+            };
+            SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+        });
+        let pool = AstroportPool {
+            pool_assets: vec![AssetInfo::native("uatom"), AssetInfo::native("uosmo")],
+            ..pool(PairType::Custom(CONCENTRATED_PAIR_TYPE.to_string()))
+        };
+
+        // The reserves scaled by the price scale are balanced at the price
+        // scale, so the bound is the value of the reserves
+        let d = concentrated_compute_d(
+            Decimal256::from_ratio(40u128, 1u128),
+            Decimal256::from_str("0.000145").unwrap(),
+            &[Decimal256::one(), Decimal256::one()],
+        )
+        .unwrap();
+        let price = pool
+            .fair_lp_token_price(
+                deps.as_ref(),
+                &mock_env(),
+                &[
+                    (AssetInfo::native("uatom"), Decimal::one()),
+                    (AssetInfo::native("uosmo"), Decimal::percent(200)),
+                ],
+            )
+            .unwrap();
+        assert_eq!(
+            price,
+            lp_price_from_value(
+                d.checked_mul(Decimal256::from_ratio(1_000_000u128, 1u128))
+                    .unwrap(),
+                Uint128::new(1_000_000)
+            )
+            .unwrap()
+        );
+
+        // Reference prices away from the price scale are undervalued
+        let price = pool
+            .fair_lp_token_price(
+                deps.as_ref(),
+                &mock_env(),
+                &[
+                    (AssetInfo::native("uatom"), Decimal::one()),
+                    (AssetInfo::native("uosmo"), Decimal::percent(400)),
+                ],
+            )
+            .unwrap();
+        assert!(price < Decimal::from_ratio(3u128, 1u128));
+    }
+
+    #[test]
+    fn test_xyk_pool_params() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| {
+            let res = match query {
+                WasmQuery::Raw { contract_addr, .. } if contract_addr == "pair" => {
+                    to_binary(&PairFactoryConfig {
+                        factory_addr: Addr::unchecked("factory"),
+                    })
+                }
+                WasmQuery::Smart { contract_addr, .. } if contract_addr == "factory" => {
+                    to_binary(&FeeInfoResponse {
+                        fee_address: None,
+                        total_fee_bps: 30,
+                        maker_fee_bps: 10,
+                    })
+                }
+                _ => panic!("unexpected query"),
+            };
+            SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+        });
+
+        let params = pool(PairType::Xyk {})
+            .pool_params(deps.as_ref(), &mock_env())
+            .unwrap();
+        assert_eq!(
+            params,
+            PoolParams {
+                pool_type: PoolType::ConstantProduct,
+                swap_fee: Decimal::permille(3),
+                exit_fee: Decimal::zero(),
+                weights: None,
+                amp: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_provide_slippage_tolerance_non_xyk() {
+        let deps = mock_dependencies();
+        let assets: AssetList = vec![
+            Asset::native("uatom", 1_000u128),
+            Asset::cw20(Addr::unchecked("astro"), 1_000u128),
+        ]
+        .into();
+
+        // Stable and PCL pairs are sent the maximum tolerance, `min_out` is
+        // asserted by the callback instead
+        for pair_type in [
+            PairType::Stable {},
+            PairType::Custom(CONCENTRATED_PAIR_TYPE.to_string()),
+        ] {
+            let slippage_tolerance = pool(pair_type)
+                .provide_slippage_tolerance(deps.as_ref(), &assets, Uint128::new(1_000))
+                .unwrap();
+            assert_eq!(
+                slippage_tolerance,
+                Decimal::from_str(MAX_ALLOWED_SLIPPAGE).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_swap_exact_out_zero_amount() {
+        let deps = mock_dependencies();
+
+        let err = pool(PairType::Xyk {})
+            .swap_exact_out(
+                deps.as_ref(),
+                &mock_env(),
+                Asset::cw20(Addr::unchecked("astro"), 0u128),
+                AssetInfo::native("uatom"),
+                Uint128::new(1_000),
+            )
+            .unwrap_err();
+        assert_eq!(err, CwDexError::InvalidZeroAmount {});
+    }
+
+    #[test]
+    fn test_find_pool_asset_index() {
+        let pools: Vec<AstroAsset> = vec![
+            Asset::native("uatom", 100u128).into(),
+            Asset::cw20(Addr::unchecked("astro"), 100u128).into(),
+        ];
+
+        assert_eq!(
+            find_pool_asset_index(&pools, &AssetInfo::cw20(Addr::unchecked("astro"))).unwrap(),
+            1
+        );
+        assert_eq!(
+            find_pool_asset_index(&pools, &AssetInfo::native("uosmo")).unwrap_err(),
+            CwDexError::AssetNotInPool {
+                asset: AssetInfo::native("uosmo")
+            }
+        );
+
+        assert!(assert_two_pool_assets(&pools).is_ok());
+        assert_eq!(
+            assert_two_pool_assets(&pools[..1]).unwrap_err(),
+            CwDexError::InvalidPoolAssetCount {
+                expected: 2,
+                found: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_new_pair_addr_mismatch() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, .. } => SystemResult::Ok(ContractResult::Ok(
+                to_binary(&PairInfo {
+                    asset_infos: vec![
+                        AstroAssetInfo::NativeToken {
+                            denom: "uatom".to_string(),
+                        },
+                        AstroAssetInfo::Token {
+                            contract_addr: Addr::unchecked("astro"),
+                        },
+                    ],
+                    // Only "pair" returns its own address
+                    contract_addr: Addr::unchecked("pair"),
+                    liquidity_token: Addr::unchecked(format!("{}_lp_token", contract_addr)),
+                    pair_type: PairType::Xyk {},
+                })
+                .unwrap(),
+            )),
+            _ => panic!("unexpected query"),
+        });
+
+        let pool = AstroportPool::new(deps.as_ref(), Addr::unchecked("pair")).unwrap();
+        assert_eq!(pool.pair_addr, Addr::unchecked("pair"));
+
+        let err = AstroportPool::new(deps.as_ref(), Addr::unchecked("other_pair")).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Pair other_pair returned pair info of pair")
+        );
+    }
+
+    #[test]
+    fn test_from_factory() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "factory" => {
+                let asset_infos = match from_binary(msg).unwrap() {
+                    FactoryQueryMsg::Pair { asset_infos } => asset_infos,
+                    _ => panic!("unexpected query"),
+                };
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&PairInfo {
+                        asset_infos,
+                        contract_addr: Addr::unchecked("pair"),
+                        liquidity_token: Addr::unchecked("lp_token"),
+                        pair_type: PairType::Xyk {},
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => panic!("unexpected query"),
+        });
+
+        let factory_pool = AstroportPool::from_factory(
+            deps.as_ref(),
+            Addr::unchecked("factory"),
+            vec![
+                AssetInfo::native("uatom"),
+                AssetInfo::cw20(Addr::unchecked("astro")),
+            ],
+        )
+        .unwrap();
+        assert_eq!(factory_pool, pool(PairType::Xyk {}));
+    }
+
+    #[test]
+    fn test_query_twap_price() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == "pair" => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&CumulativePricesResponse {
+                        assets: vec![],
+                        total_share: Uint128::zero(),
+                        cumulative_prices: vec![(
+                            AssetInfo::native("uatom").into(),
+                            AssetInfo::cw20(Addr::unchecked("astro")).into(),
+                            Uint128::new(500_000_000),
+                        )],
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => panic!("unexpected query"),
+        });
+        let env = mock_env();
+        let now = env.block.time.seconds();
+        let base = AssetInfo::native("uatom");
+        let quote = AssetInfo::cw20(Addr::unchecked("astro"));
+        let snapshot = |seconds_ago: u64, price: u128| CumulativePricesSnapshot {
+            timestamp: now - seconds_ago,
+            cumulative_prices: vec![(base.clone(), quote.clone(), Uint128::new(price))],
+        };
+        let pool = pool(PairType::Xyk {});
+
+        // The most recent snapshot at least the window old is used
+        let snapshots = vec![
+            snapshot(300, 0),
+            snapshot(200, 100_000_000),
+            snapshot(50, 450_000_000),
+        ];
+        let price = pool
+            .query_twap_price(deps.as_ref(), &env, &base, &quote, 100, &snapshots)
+            .unwrap();
+        assert_eq!(price, Decimal::from_ratio(2u128, 1u128));
+
+        let err = pool
+            .query_twap_price(deps.as_ref(), &env, &base, &quote, 400, &snapshots)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            CwDexError::TwapUnavailable {
+                reason: "no snapshot taken at least 400 seconds ago".to_string()
+            }
+        );
+    }
+}
@@ -48,6 +48,20 @@ impl Stake for AstroportStaking {
 
         Ok(Response::new().add_message(stake_msg).add_event(event))
     }
+
+    fn query_staked_balance(
+        &self,
+        querier: &QuerierWrapper,
+        user: &Addr,
+    ) -> Result<Uint128, CwDexError> {
+        Ok(querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: self.generator_addr.to_string(),
+            msg: to_binary(&GeneratorQueryMsg::Deposit {
+                lp_token: self.lp_token_addr.to_string(),
+                user: user.to_string(),
+            })?,
+        }))?)
+    }
 }
 
 impl Rewards for AstroportStaking {
@@ -120,3 +134,41 @@ impl Unstake for AstroportStaking {
         Ok(Response::new().add_message(unstake_msg).add_event(event))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::mock_dependencies;
+    use cosmwasm_std::{from_binary, ContractResult, SystemResult};
+
+    use super::*;
+
+    #[test]
+    fn test_query_staked_balance() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "generator" => {
+                match from_binary(msg).unwrap() {
+                    GeneratorQueryMsg::Deposit { lp_token, user } => {
+                        assert_eq!(lp_token, "lp_token");
+                        assert_eq!(user, "user");
+                        SystemResult::Ok(ContractResult::Ok(
+                            to_binary(&Uint128::new(1_000)).unwrap(),
+                        ))
+                    }
+                    _ => panic!("unexpected query"),
+                }
+            }
+            _ => panic!("unexpected query"),
+        });
+
+        let staking = AstroportStaking {
+            lp_token_addr: Addr::unchecked("lp_token"),
+            generator_addr: Addr::unchecked("generator"),
+            astro_addr: Addr::unchecked("astro"),
+        };
+        let balance = staking
+            .query_staked_balance(&deps.as_ref().querier, &Addr::unchecked("user"))
+            .unwrap();
+        assert_eq!(balance, Uint128::new(1_000));
+    }
+}
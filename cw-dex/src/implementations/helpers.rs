@@ -0,0 +1,67 @@
+//! Helpers shared by the pool implementations
+
+use cw_asset::AssetList;
+
+#[cfg(doc)]
+use crate::CwDexCallbackMsg;
+use crate::CwDexError;
+
+/// Asserts that the simulated `assets_out` of an action contain at least the
+/// amount of each asset in `min_out`. Used for actions where the DEX does not
+/// support minimum amounts out natively, to return an error early when the
+/// simulation already falls short. The minimums are enforced on chain by
+/// [`CwDexCallbackMsg::assert_min_out`], since messages before the action in
+/// the same transaction may change the state of the pool.
+pub(crate) fn assert_min_out(
+    assets_out: &AssetList,
+    min_out: &AssetList,
+) -> Result<(), CwDexError> {
+    for min in min_out.into_iter() {
+        let received = assets_out
+            .find(&min.info)
+            .ok_or_else(|| CwDexError::AssetNotInPool {
+                asset: min.info.clone(),
+            })?
+            .amount;
+        if received < min.amount {
+            return Err(CwDexError::MinOutNotReceived {
+                min_out: min.amount,
+                received,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::Uint128;
+    use cw_asset::{Asset, AssetInfo};
+
+    use super::*;
+
+    #[test]
+    fn test_assert_min_out() {
+        let assets_out: AssetList = vec![
+            Asset::native("uatom", 100u128),
+            Asset::native("uosmo", 200u128),
+        ]
+        .into();
+
+        assert!(assert_min_out(&assets_out, &AssetList::new()).is_ok());
+        assert!(assert_min_out(&assets_out, &vec![Asset::native("uosmo", 200u128)].into()).is_ok());
+        assert_eq!(
+            assert_min_out(&assets_out, &vec![Asset::native("uatom", 101u128)].into()),
+            Err(CwDexError::MinOutNotReceived {
+                min_out: Uint128::new(101),
+                received: Uint128::new(100)
+            })
+        );
+        assert_eq!(
+            assert_min_out(&assets_out, &vec![Asset::native("ujuno", 1u128)].into()),
+            Err(CwDexError::AssetNotInPool {
+                asset: AssetInfo::native("ujuno")
+            })
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use apollo_utils::assets::separate_natives_and_cw20s;
 use cosmwasm_std::{
-    to_binary, Addr, Coin, CosmosMsg, Decimal, Env, StdError, StdResult, Uint128, WasmMsg,
+    to_binary, Addr, Coin, CosmosMsg, Decimal, Decimal256, Env, StdError, StdResult, Uint128,
+    Uint256, Uint512, WasmMsg,
 };
 use cw20::Cw20ExecuteMsg;
 use cw20_0_10_3::Denom;
@@ -215,6 +216,137 @@ pub(crate) fn juno_get_token1_amount_required(
         .checked_div(token2_reserve)?)
 }
 
+/// The scale of the fees in the WasmSwap swap math, i.e. fees are applied in
+/// basis points.
+const FEE_SCALE_FACTOR: u128 = 10_000;
+
+/// Converts a fee rate to basis points, rounding down like WasmSwap does.
+fn fee_to_bps(fee: Decimal) -> StdResult<Uint128> {
+    Ok(fee
+        .atomics()
+        .checked_mul(Uint128::new(FEE_SCALE_FACTOR))?
+        .checked_div(Decimal::one().atomics())?)
+}
+
+/// Returns the amount of input tokens required to receive at least
+/// `output_amount` of output tokens when swapping on Junoswap with a total
+/// fee of `fee` on the input amount.
+///
+/// This is the inverse of the `get_input_price` function in the WasmSwap
+/// source code:
+/// https://github.com/Wasmswap/wasmswap-contracts/blob/v1.1.0-beta/src/contract.rs
+pub(crate) fn juno_get_input_amount_required(
+    output_amount: Uint128,
+    input_reserve: Uint128,
+    output_reserve: Uint128,
+    fee: Decimal,
+) -> Result<Uint128, CwDexError> {
+    if output_amount >= output_reserve {
+        return Err(StdError::generic_err("Not enough liquidity in pool").into());
+    }
+
+    let numerator = input_reserve
+        .full_mul(output_amount)
+        .checked_mul(Uint256::from(FEE_SCALE_FACTOR))?;
+    let denominator = output_reserve
+        .checked_sub(output_amount)?
+        .full_mul(Uint128::new(FEE_SCALE_FACTOR).checked_sub(fee_to_bps(fee)?)?);
+
+    // Round up so that the output of the swap is at least `output_amount`
+    let input_amount = numerator
+        .checked_add(denominator.checked_sub(Uint256::from(1u128))?)?
+        .checked_div(denominator)?;
+    Ok(Uint128::try_from(input_amount).map_err(StdError::from)?)
+}
+
+/// Returns the amount of output tokens received for swapping `input_amount` of
+/// input tokens on Junoswap with a total fee of `fee` on the input amount.
+///
+/// Copied from WasmSwap source code:
+/// https://github.com/Wasmswap/wasmswap-contracts/blob/v1.1.0-beta/src/contract.rs
+pub(crate) fn juno_get_input_price(
+    input_amount: Uint128,
+    input_reserve: Uint128,
+    output_reserve: Uint128,
+    fee: Decimal,
+) -> Result<Uint128, CwDexError> {
+    if input_reserve.is_zero() || output_reserve.is_zero() {
+        return Err(StdError::generic_err("No liquidity").into());
+    }
+
+    let fee_reduction = Uint128::new(FEE_SCALE_FACTOR).checked_sub(fee_to_bps(fee)?)?;
+    let input_amount_with_fee = Uint512::from(input_amount.full_mul(fee_reduction));
+    let numerator = input_amount_with_fee.checked_mul(Uint512::from(output_reserve))?;
+    let denominator = Uint512::from(input_reserve)
+        .checked_mul(Uint512::from(FEE_SCALE_FACTOR))?
+        .checked_add(input_amount_with_fee)?;
+
+    Ok(Uint128::try_from(numerator.checked_div(denominator)?).map_err(StdError::from)?)
+}
+
+/// Returns the part of `input_amount` of a swap on Junoswap that is sent to
+/// the protocol fee recipient instead of being added to the input reserve.
+///
+/// Copied from WasmSwap source code:
+/// https://github.com/Wasmswap/wasmswap-contracts/blob/v1.1.0-beta/src/contract.rs
+pub(crate) fn juno_get_protocol_fee_amount(
+    input_amount: Uint128,
+    protocol_fee: Decimal,
+) -> Result<Uint128, CwDexError> {
+    if protocol_fee.is_zero() {
+        return Ok(Uint128::zero());
+    }
+
+    Ok(input_amount.multiply_ratio(fee_to_bps(protocol_fee)?, FEE_SCALE_FACTOR))
+}
+
+/// Returns the amount of an asset to swap on Junoswap with `input_reserve` of
+/// the asset before providing liquidity with `amount` of it, such that the
+/// remaining amount and the return of the swap are in the same ratio as the
+/// reserves after the swap. Junoswap charges a total fee of `fee` on the input,
+/// of which `protocol_fee` is not added to the input reserve, so with
+/// g = 1 - fee and p = protocol_fee (both in whole basis points, like WasmSwap)
+/// the swap amount solves:
+/// g * (1 - p) * s^2 + (1 + g) * input_reserve * s - amount * input_reserve = 0
+pub(crate) fn juno_zap_in_swap_amount(
+    amount: Uint128,
+    input_reserve: Uint128,
+    fee: Decimal,
+    protocol_fee: Decimal,
+) -> Result<Uint128, CwDexError> {
+    let g = Decimal256::from_ratio(
+        Uint128::new(FEE_SCALE_FACTOR).checked_sub(fee_to_bps(fee)?)?,
+        FEE_SCALE_FACTOR,
+    );
+    let a = g.checked_mul(Decimal256::from_ratio(
+        Uint128::new(FEE_SCALE_FACTOR).checked_sub(fee_to_bps(protocol_fee)?)?,
+        FEE_SCALE_FACTOR,
+    ))?;
+    let amount = Decimal256::from_ratio(amount, 1u128);
+    let reserve = Decimal256::from_ratio(input_reserve, 1u128);
+
+    let b = (Decimal256::one() + g).checked_mul(reserve)?;
+    let sqrt_discriminant = b
+        .checked_mul(b)?
+        .checked_add(
+            Decimal256::from_ratio(4u128, 1u128)
+                .checked_mul(a)?
+                .checked_mul(amount)?
+                .checked_mul(reserve)?,
+        )?
+        .sqrt();
+
+    let swap_amount = sqrt_discriminant
+        .checked_sub(b)?
+        .checked_div(Decimal256::from_ratio(2u128, 1u128).checked_mul(a)?)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    Ok(
+        Uint128::try_from(swap_amount.atomics() / Decimal256::one().atomics())
+            .map_err(StdError::from)?,
+    )
+}
+
 pub(crate) struct JunoProvideLiquidityInfo {
     pub token1_to_use: Asset,
     pub token2_to_use: Asset,
@@ -292,3 +424,86 @@ pub(crate) fn juno_simulate_provide_liquidity(
         lp_token_expected_amount: expected_lps,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_juno_zap_in_swap_amount() {
+        let reserve = Uint128::new(1_000_000_000);
+        let amount = Uint128::new(10_000_000);
+
+        // (total fee, protocol fee)
+        let fees = [
+            (Decimal::permille(3), Decimal::zero()),
+            (Decimal::percent(1), Decimal::zero()),
+            (Decimal::percent(1), Decimal::permille(4)),
+        ];
+        for (fee, protocol_fee) in fees {
+            let swap_amount = juno_zap_in_swap_amount(amount, reserve, fee, protocol_fee).unwrap();
+            let return_amount = juno_get_input_price(swap_amount, reserve, reserve, fee).unwrap();
+            let protocol_fee_amount =
+                juno_get_protocol_fee_amount(swap_amount, protocol_fee).unwrap();
+
+            // The remaining amount and the return of the swap are in the same
+            // ratio as the reserves after the swap.
+            let lhs = (amount - swap_amount) * (reserve - return_amount);
+            let rhs = return_amount * (reserve + swap_amount - protocol_fee_amount);
+            assert!(lhs.max(rhs) - lhs.min(rhs) < lhs / Uint128::new(10_000));
+        }
+    }
+
+    #[test]
+    fn test_juno_get_protocol_fee_amount() {
+        let amount = Uint128::new(10_000);
+
+        assert_eq!(
+            juno_get_protocol_fee_amount(amount, Decimal::zero()).unwrap(),
+            Uint128::zero()
+        );
+        assert_eq!(
+            juno_get_protocol_fee_amount(amount, Decimal::permille(4)).unwrap(),
+            Uint128::new(40)
+        );
+    }
+
+    #[test]
+    fn test_juno_get_input_price() {
+        let reserve = Uint128::new(1_000_000);
+
+        // A fee of 0.3% is 30 basis points
+        assert_eq!(
+            juno_get_input_price(Uint128::new(10_000), reserve, reserve, Decimal::permille(3))
+                .unwrap(),
+            Uint128::new(9_871)
+        );
+        // A fee of 1% is 100 basis points
+        assert_eq!(
+            juno_get_input_price(Uint128::new(10_000), reserve, reserve, Decimal::percent(1))
+                .unwrap(),
+            Uint128::new(9_802)
+        );
+    }
+
+    #[test]
+    fn test_juno_get_input_amount_required() {
+        let reserve = Uint128::new(1_000_000);
+        let fee = Decimal::percent(1);
+
+        // The smallest amount that returns at least the output amount
+        let input_amount =
+            juno_get_input_amount_required(Uint128::new(9_802), reserve, reserve, fee).unwrap();
+        assert_eq!(input_amount, Uint128::new(10_000));
+        assert_eq!(
+            juno_get_input_price(input_amount, reserve, reserve, fee).unwrap(),
+            Uint128::new(9_802)
+        );
+        assert_eq!(
+            juno_get_input_price(input_amount - Uint128::one(), reserve, reserve, fee).unwrap(),
+            Uint128::new(9_801)
+        );
+
+        assert!(juno_get_input_amount_required(reserve, reserve, reserve, fee).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 //! Module containing Pool and Staking implementations for Junoswap
 
 mod helpers;
+mod msg;
 mod pool;
 mod staking;
 
@@ -0,0 +1,49 @@
+//! Contains a subset of the messages for the DAO DAO stake-cw20-external-rewards
+//! contract, and responses of the stake-cw20 contract, used by the Junoswap
+//! implementation.
+//!
+//! The contracts are not published as crates compatible with our dependencies,
+//! so the messages are defined here. See
+//! <https://github.com/DA0-DA0/dao-contracts/tree/v1.0.0/contracts/stake-cw20-external-rewards>.
+#![allow(missing_docs)]
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Uint128};
+use serde::Deserialize;
+
+#[cw_serde]
+pub enum StakeCw20ExternalRewardsExecuteMsg {
+    Claim {},
+}
+
+#[cw_serde]
+pub enum StakeCw20ExternalRewardsQueryMsg {
+    GetPendingRewards { address: String },
+}
+
+#[cw_serde]
+pub enum Denom {
+    Native(String),
+    Cw20(Addr),
+}
+
+#[cw_serde]
+pub struct PendingRewardsResponse {
+    pub address: String,
+    pub pending_rewards: Uint128,
+    pub denom: Denom,
+    pub last_update_block: u64,
+}
+
+/// A claim of unstaked tokens in the stake-cw20 contract, as returned by the
+/// `Claims` query. Defined here since stake-cw20 uses `cw-controllers` 0.11.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Claim {
+    pub amount: Uint128,
+    pub release_at: cw_utils_0_11::Expiration,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ClaimsResponse {
+    pub claims: Vec<Claim>,
+}
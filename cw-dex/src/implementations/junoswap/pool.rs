@@ -4,7 +4,7 @@ use std::vec;
 
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    to_binary, Addr, CosmosMsg, Deps, Env, Event, QuerierWrapper, QueryRequest, Response, StdError,
+    to_binary, Addr, CosmosMsg, Decimal, Deps, Env, Event, QuerierWrapper, QueryRequest, StdError,
     StdResult, Uint128, WasmMsg, WasmQuery,
 };
 use cw_asset::{Asset, AssetInfo, AssetList};
@@ -12,12 +12,17 @@ use wasmswap::msg::{
     ExecuteMsg, InfoResponse, QueryMsg, Token1ForToken2PriceResponse, TokenSelect,
 };
 
-use crate::traits::Pool;
+use crate::implementations::valuation::{lp_price_from_value, reference_price, xyk_fair_value};
+use crate::implementations::zap::{simulate_zap_out, withdraw_and_swap};
+use crate::response::CwDexResponse;
+use crate::traits::{Pool, PoolParams, PoolType, PriceOracle, SwapSimulation};
 use crate::CwDexError;
 
 use super::helpers::{
-    juno_simulate_provide_liquidity, prepare_funds_and_increase_allowances, JunoAsset,
-    JunoAssetInfo, JunoAssetList,
+    juno_get_input_amount_required, juno_get_input_price, juno_get_protocol_fee_amount,
+    juno_simulate_provide_liquidity, juno_zap_in_swap_amount,
+    prepare_funds_and_increase_allowances, JunoAsset, JunoAssetInfo, JunoAssetList,
+    JunoProvideLiquidityInfo,
 };
 
 /// Represents an AMM pool on Astroport
@@ -30,6 +35,15 @@ pub struct JunoswapPool {
 }
 
 impl JunoswapPool {
+    /// Creates a new instance of `JunoswapPool`
+    ///
+    /// Arguments:
+    /// - `addr`: Address of the pool contract
+    /// - `lp_token`: Address of the LP token contract of the pool
+    pub fn new(addr: Addr, lp_token: Addr) -> Self {
+        Self { addr, lp_token }
+    }
+
     /// Queries the pool contract for information
     pub fn query_info(&self, querier: &QuerierWrapper) -> StdResult<InfoResponse> {
         querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
@@ -37,6 +51,172 @@ impl JunoswapPool {
             msg: to_binary(&QueryMsg::Info {})?,
         }))
     }
+
+    /// Returns the reserves of `offer_asset_info` and `ask_asset_info` in the
+    /// pool, in that order.
+    fn query_reserves(
+        &self,
+        querier: &QuerierWrapper,
+        offer_asset_info: &AssetInfo,
+        ask_asset_info: &AssetInfo,
+    ) -> StdResult<(Uint128, Uint128)> {
+        let pool_info = self.query_info(querier)?;
+        reserves(&pool_info, offer_asset_info, ask_asset_info)
+    }
+
+    /// Returns the messages to provide liquidity as computed by
+    /// `juno_simulate_provide_liquidity`, including the messages increasing
+    /// the allowance of any Cw20 tokens.
+    fn provide_liquidity_msgs(
+        &self,
+        env: &Env,
+        provide_liquidity_info: &JunoProvideLiquidityInfo,
+        min_out: Uint128,
+    ) -> Result<Vec<CosmosMsg>, CwDexError> {
+        // Increase allowance for cw20 tokens and add native tokens to the funds vec.
+        let assets_to_use = vec![
+            provide_liquidity_info.token1_to_use.clone(),
+            provide_liquidity_info.token2_to_use.clone(),
+        ]
+        .into();
+
+        // Separate the assets to pass in the funds and build messages
+        // to increase allowances for cw20 tokens.
+        let (funds, mut msgs) =
+            prepare_funds_and_increase_allowances(env, &assets_to_use, &self.addr)?;
+
+        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: self.addr.to_string(),
+            funds,
+            msg: to_binary(&ExecuteMsg::AddLiquidity {
+                token1_amount: provide_liquidity_info.token1_to_use.amount,
+                min_liquidity: min_out,
+                max_token2: provide_liquidity_info.token2_to_use.amount,
+                expiration: None,
+            })?,
+        }));
+
+        Ok(msgs)
+    }
+
+    /// Computes how much of `asset` to swap into the other pool asset before
+    /// providing liquidity with the remainder and the return of the swap, and
+    /// how the liquidity will be provided to the pool after the swap.
+    fn simulate_zap_in_info(&self, deps: Deps, asset: &Asset) -> Result<ZapIn, CwDexError> {
+        let pool_info = self.query_info(&deps.querier)?;
+
+        let token1 = JunoAssetInfo(pool_info.token1_denom.clone());
+        let token2 = JunoAssetInfo(pool_info.token2_denom.clone());
+        let (offer_is_token1, input_reserve, output_reserve, ask_info) = if token1 == asset.info {
+            (
+                true,
+                pool_info.token1_reserve,
+                pool_info.token2_reserve,
+                token2,
+            )
+        } else if token2 == asset.info {
+            (
+                false,
+                pool_info.token2_reserve,
+                pool_info.token1_reserve,
+                token1,
+            )
+        } else {
+            return Err(CwDexError::AssetNotInPool {
+                asset: asset.info.clone(),
+            });
+        };
+        if pool_info.lp_token_supply.is_zero() {
+            return Err(CwDexError::InvalidProvideLPsWithSingleToken {});
+        }
+
+        let fee = swap_fee(&pool_info)?;
+        let protocol_fee = protocol_fee(&pool_info);
+        let swap_amount = juno_zap_in_swap_amount(asset.amount, input_reserve, fee, protocol_fee)?;
+        let return_amount = juno_get_input_price(swap_amount, input_reserve, output_reserve, fee)?;
+
+        // Simulate providing liquidity to the pool as it will be after the swap.
+        // The protocol fee is sent to the fee recipient instead of the reserve.
+        let input_reserve = input_reserve
+            .checked_add(swap_amount)?
+            .checked_sub(juno_get_protocol_fee_amount(swap_amount, protocol_fee)?)?;
+        let output_reserve = output_reserve.checked_sub(return_amount)?;
+        let (token1_reserve, token2_reserve) = if offer_is_token1 {
+            (input_reserve, output_reserve)
+        } else {
+            (output_reserve, input_reserve)
+        };
+        let assets = JunoAssetList(vec![
+            JunoAsset {
+                info: asset.info.clone().try_into()?,
+                amount: asset.amount.checked_sub(swap_amount)?,
+            },
+            JunoAsset {
+                info: ask_info.clone(),
+                amount: return_amount,
+            },
+        ]);
+        let provide_liquidity_info = juno_simulate_provide_liquidity(
+            &assets,
+            InfoResponse {
+                token1_reserve,
+                token2_reserve,
+                ..pool_info
+            },
+        )?;
+
+        Ok(ZapIn {
+            swap_amount,
+            return_asset: Asset::new(ask_info, return_amount),
+            provide_liquidity_info,
+        })
+    }
+}
+
+/// The swap and liquidity provision of zapping into a Junoswap pool
+struct ZapIn {
+    /// The amount of the zapped asset to swap
+    swap_amount: Uint128,
+    /// The expected return of the swap
+    return_asset: Asset,
+    /// The liquidity to provide after the swap
+    provide_liquidity_info: JunoProvideLiquidityInfo,
+}
+
+/// Returns the reserves of `offer_asset_info` and `ask_asset_info` in the pool
+/// described by `pool_info`, in that order.
+fn reserves(
+    pool_info: &InfoResponse,
+    offer_asset_info: &AssetInfo,
+    ask_asset_info: &AssetInfo,
+) -> StdResult<(Uint128, Uint128)> {
+    let token1 = JunoAssetInfo(pool_info.token1_denom.clone());
+    let token2 = JunoAssetInfo(pool_info.token2_denom.clone());
+
+    if token1 == *offer_asset_info && token2 == *ask_asset_info {
+        Ok((pool_info.token1_reserve, pool_info.token2_reserve))
+    } else if token2 == *offer_asset_info && token1 == *ask_asset_info {
+        Ok((pool_info.token2_reserve, pool_info.token1_reserve))
+    } else {
+        Err(StdError::generic_err(
+            "Offered or asked asset is not in the pool",
+        ))
+    }
+}
+
+/// Returns the total fee charged on the offered amount of a swap in the pool
+/// described by `pool_info`. Wasmswap fees are given in percent.
+fn swap_fee(pool_info: &InfoResponse) -> StdResult<Decimal> {
+    Ok(pool_info
+        .lp_fee_percent
+        .checked_add(pool_info.protocol_fee_percent)?
+        * Decimal::percent(1))
+}
+
+/// Returns the part of the fee in the pool described by `pool_info` that is
+/// sent to the protocol fee recipient instead of being added to the reserves.
+fn protocol_fee(pool_info: &InfoResponse) -> Decimal {
+    pool_info.protocol_fee_percent * Decimal::percent(1)
 }
 
 impl Pool for JunoswapPool {
@@ -46,12 +226,12 @@ impl Pool for JunoswapPool {
         env: &Env,
         assets: AssetList,
         min_out: Uint128,
-    ) -> Result<Response, CwDexError> {
+    ) -> Result<CwDexResponse, CwDexError> {
         let pool_info = self.query_info(&deps.querier)?;
 
         // Calculate minimum LPs from slippage tolerance
         let provide_liquidity_info =
-            juno_simulate_provide_liquidity(&assets.try_into()?, pool_info)?;
+            juno_simulate_provide_liquidity(&assets.clone().try_into()?, pool_info)?;
 
         // Check if minimum LPs is met
         let lp_out = provide_liquidity_info.lp_token_expected_amount;
@@ -62,50 +242,95 @@ impl Pool for JunoswapPool {
             });
         }
 
-        // Increase allowance for cw20 tokens and add native tokens to the funds vec.
-        let assets_to_use = vec![
+        let event = Event::new("apollo/cw-dex/provide_liquidity").add_attribute("type", "junoswap");
+
+        let mut leftover = assets;
+        let used: AssetList = vec![
             provide_liquidity_info.token1_to_use.clone(),
             provide_liquidity_info.token2_to_use.clone(),
         ]
         .into();
+        leftover.deduct_many(used.clone().purge())?;
 
-        // Separate the assets to pass in the funds and build messages
-        // to increase allowances for cw20 tokens.
-        let (funds, increase_allowances) =
-            prepare_funds_and_increase_allowances(env, &assets_to_use, &self.addr)?;
+        Ok(CwDexResponse::new()
+            .add_messages(self.provide_liquidity_msgs(env, &provide_liquidity_info, min_out)?)
+            .add_event(event)
+            .with_expected_out(vec![Asset::new(self.lp_token(), lp_out)])
+            .with_leftover(leftover))
+    }
 
-        let provide_liquidity = CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: self.addr.to_string(),
-            funds,
-            msg: to_binary(&ExecuteMsg::AddLiquidity {
-                token1_amount: provide_liquidity_info.token1_to_use.amount,
-                min_liquidity: min_out,
-                max_token2: provide_liquidity_info.token2_to_use.amount,
-                expiration: None,
-            })?,
-        });
+    fn zap_in(
+        &self,
+        deps: Deps,
+        env: &Env,
+        asset: Asset,
+        min_out: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        let zap = self.simulate_zap_in_info(deps, &asset)?;
 
-        let event = Event::new("apollo/cw-dex/provide_liquidity").add_attribute("type", "junoswap");
+        let lp_out = zap.provide_liquidity_info.lp_token_expected_amount;
+        if min_out > lp_out {
+            return Err(CwDexError::MinOutNotReceived {
+                min_out,
+                received: lp_out,
+            });
+        }
 
-        Ok(Response::new()
-            .add_messages(increase_allowances)
-            .add_message(provide_liquidity)
-            .add_event(event))
+        let swap = self.swap(
+            deps,
+            env,
+            Asset::new(asset.info.clone(), zap.swap_amount),
+            zap.return_asset.info.clone(),
+            zap.return_asset.amount,
+        )?;
+
+        let event = Event::new("apollo/cw-dex/zap_in")
+            .add_attribute("type", "junoswap")
+            .add_attribute("asset", asset.to_string())
+            .add_attribute("swap_amount", zap.swap_amount)
+            .add_attribute("expected_lp", lp_out);
+
+        Ok(CwDexResponse::new()
+            .add_submessages(swap.messages)
+            .add_messages(self.provide_liquidity_msgs(env, &zap.provide_liquidity_info, min_out)?)
+            .add_events(swap.events)
+            .add_event(event)
+            .with_expected_out(vec![Asset::new(self.lp_token(), lp_out)]))
     }
 
     fn withdraw_liquidity(
         &self,
-        _deps: Deps,
+        deps: Deps,
         _env: &Env,
         asset: Asset,
-    ) -> Result<Response, CwDexError> {
+        min_out: AssetList,
+    ) -> Result<CwDexResponse, CwDexError> {
+        let expected_out = self.simulate_withdraw_liquidity(deps, &asset)?;
+        let pool_info = self.query_info(&deps.querier)?;
+        let token1 = JunoAssetInfo(pool_info.token1_denom);
+        let token2 = JunoAssetInfo(pool_info.token2_denom);
+
+        let mut min_token1 = Uint128::zero();
+        let mut min_token2 = Uint128::zero();
+        for min in min_out.into_iter() {
+            if token1 == min.info {
+                min_token1 = min.amount;
+            } else if token2 == min.info {
+                min_token2 = min.amount;
+            } else {
+                return Err(CwDexError::AssetNotInPool {
+                    asset: min.info.clone(),
+                });
+            }
+        }
+
         let withdraw_liquidity = CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: self.addr.to_string(),
             funds: vec![],
             msg: to_binary(&ExecuteMsg::RemoveLiquidity {
                 amount: asset.amount,
-                min_token1: Uint128::zero(),
-                min_token2: Uint128::zero(),
+                min_token1,
+                min_token2,
                 expiration: None,
             })?,
         });
@@ -114,9 +339,30 @@ impl Pool for JunoswapPool {
             .add_attribute("type", "junoswap")
             .add_attribute("asset", format!("{:?}", asset));
 
-        Ok(Response::new()
+        Ok(CwDexResponse::new()
             .add_message(withdraw_liquidity)
-            .add_event(event))
+            .add_event(event)
+            .with_expected_out(expected_out))
+    }
+
+    fn zap_out(
+        &self,
+        deps: Deps,
+        env: &Env,
+        lp_token: Asset,
+        ask_asset_info: AssetInfo,
+        min_out: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        let event = Event::new("apollo/cw-dex/zap_out")
+            .add_attribute("type", "junoswap")
+            .add_attribute("lp_token", lp_token.to_string())
+            .add_attribute("ask_asset", ask_asset_info.to_string())
+            .add_attribute("minimum_out_amount", min_out);
+
+        Ok(
+            withdraw_and_swap(self, deps, env, lp_token, &ask_asset_info, min_out)?
+                .add_event(event),
+        )
     }
 
     fn swap(
@@ -126,17 +372,31 @@ impl Pool for JunoswapPool {
         offer_asset: Asset,
         ask_asset_info: AssetInfo,
         min_out: Uint128,
-    ) -> Result<Response, CwDexError> {
+    ) -> Result<CwDexResponse, CwDexError> {
         let pool_info = self.query_info(&deps.querier)?;
+        let fee = swap_fee(&pool_info)?;
 
         let output_token: AssetInfo;
         let input_token;
+        let expected_out;
         if JunoAssetInfo(pool_info.token1_denom.clone()) == offer_asset.info {
             input_token = TokenSelect::Token1;
             output_token = JunoAssetInfo(pool_info.token2_denom).into();
+            expected_out = juno_get_input_price(
+                offer_asset.amount,
+                pool_info.token1_reserve,
+                pool_info.token2_reserve,
+                fee,
+            )?;
         } else if JunoAssetInfo(pool_info.token2_denom) == offer_asset.info {
             input_token = TokenSelect::Token2;
             output_token = JunoAssetInfo(pool_info.token1_denom).into();
+            expected_out = juno_get_input_price(
+                offer_asset.amount,
+                pool_info.token2_reserve,
+                pool_info.token1_reserve,
+                fee,
+            )?;
         } else {
             return Err(CwDexError::Std(StdError::generic_err(
                 "Offered asset is not in the pool",
@@ -175,10 +435,39 @@ impl Pool for JunoswapPool {
             .add_attribute("ask_asset_info", format!("{:?}", ask_asset_info))
             .add_attribute("minimum_out_amount", min_out.to_string());
 
-        Ok(Response::new()
+        Ok(CwDexResponse::new()
             .add_messages(increase_allowances)
             .add_message(swap)
-            .add_event(event))
+            .add_event(event)
+            .with_expected_out(vec![Asset::new(ask_asset_info, expected_out)]))
+    }
+
+    fn swap_exact_out(
+        &self,
+        deps: Deps,
+        env: &Env,
+        ask_asset: Asset,
+        offer_asset_info: AssetInfo,
+        max_in: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        // Junoswap has no exact out swap message, so we calculate the amount to
+        // offer and require at least the asked amount back.
+        let offer_amount =
+            self.simulate_swap_exact_out(deps, ask_asset.clone(), offer_asset_info.clone())?;
+        if offer_amount > max_in {
+            return Err(CwDexError::MaxInExceeded {
+                max_in,
+                required: offer_amount,
+            });
+        }
+
+        self.swap(
+            deps,
+            env,
+            Asset::new(offer_asset_info, offer_amount),
+            ask_asset.info,
+            ask_asset.amount,
+        )
     }
 
     fn get_pool_liquidity(&self, deps: Deps) -> Result<AssetList, CwDexError> {
@@ -216,6 +505,15 @@ impl Pool for JunoswapPool {
         })
     }
 
+    fn simulate_zap_in(&self, deps: Deps, _env: &Env, asset: Asset) -> Result<Asset, CwDexError> {
+        let zap = self.simulate_zap_in_info(deps, &asset)?;
+
+        Ok(Asset::new(
+            self.lp_token(),
+            zap.provide_liquidity_info.lp_token_expected_amount,
+        ))
+    }
+
     fn simulate_withdraw_liquidity(
         &self,
         deps: Deps,
@@ -246,15 +544,22 @@ impl Pool for JunoswapPool {
         .into())
     }
 
+    fn simulate_zap_out(
+        &self,
+        deps: Deps,
+        lp_token: &Asset,
+        ask_asset_info: AssetInfo,
+    ) -> Result<Asset, CwDexError> {
+        let simulation = simulate_zap_out(self, deps, lp_token, &ask_asset_info)?;
+
+        Ok(Asset::new(ask_asset_info, simulation.return_amount))
+    }
+
     fn simulate_swap(
         &self,
         deps: Deps,
         offer_asset: Asset,
         ask_asset_info: AssetInfo,
-        //For some reason Osmosis requires us to send a sender address for simulation.
-        //This obviously makes no sense and I guess we'll have to make a PR to
-        //Osmosis to fix this, or perhaps copy their math and perform the calculation here...
-        _sender: Option<String>,
     ) -> StdResult<Uint128> {
         let pool_info = self.query_info(&deps.querier)?;
 
@@ -302,7 +607,294 @@ impl Pool for JunoswapPool {
         Ok(amount)
     }
 
+    fn simulate_swap_detailed(
+        &self,
+        deps: Deps,
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+    ) -> StdResult<SwapSimulation> {
+        let pool_info = self.query_info(&deps.querier)?;
+        let (input_reserve, output_reserve) =
+            reserves(&pool_info, &offer_asset.info, &ask_asset_info)?;
+        if input_reserve.is_zero() {
+            return Err(StdError::generic_err("No liquidity"));
+        }
+
+        let return_amount = self.simulate_swap(deps, offer_asset.clone(), ask_asset_info)?;
+
+        // Junoswap does not return the spread and commission of a swap, so we
+        // derive them from the amount that would be returned at the spot price.
+        // The fee is taken from the offered amount, so the commission is the
+        // fee amount valued at the spot price.
+        let spot_amount = offer_asset
+            .amount
+            .multiply_ratio(output_reserve, input_reserve);
+        let commission_amount = (offer_asset.amount * swap_fee(&pool_info)?)
+            .multiply_ratio(output_reserve, input_reserve);
+        let spread_amount = spot_amount
+            .saturating_sub(commission_amount)
+            .saturating_sub(return_amount);
+
+        Ok(SwapSimulation {
+            return_amount,
+            spread_amount,
+            commission_amount,
+        })
+    }
+
+    fn spot_price(
+        &self,
+        deps: Deps,
+        _env: &Env,
+        base_asset: &AssetInfo,
+        quote_asset: &AssetInfo,
+    ) -> StdResult<Decimal> {
+        let (base_reserve, quote_reserve) =
+            self.query_reserves(&deps.querier, base_asset, quote_asset)?;
+
+        Decimal::checked_from_ratio(quote_reserve, base_reserve)
+            .map_err(|_| StdError::generic_err("No liquidity"))
+    }
+
+    fn pool_params(&self, deps: Deps, _env: &Env) -> Result<PoolParams, CwDexError> {
+        let pool_info = self.query_info(&deps.querier)?;
+
+        Ok(PoolParams {
+            pool_type: PoolType::ConstantProduct,
+            swap_fee: swap_fee(&pool_info)?,
+            exit_fee: Decimal::zero(),
+            weights: None,
+            amp: None,
+        })
+    }
+
+    fn fair_lp_token_price(
+        &self,
+        deps: Deps,
+        _env: &Env,
+        prices: &[(AssetInfo, Decimal)],
+    ) -> Result<Decimal, CwDexError> {
+        let pool_info = self.query_info(&deps.querier)?;
+        let token1: AssetInfo = JunoAssetInfo(pool_info.token1_denom).into();
+        let token2: AssetInfo = JunoAssetInfo(pool_info.token2_denom).into();
+
+        let value = xyk_fair_value([
+            (pool_info.token1_reserve, reference_price(prices, &token1)?),
+            (pool_info.token2_reserve, reference_price(prices, &token2)?),
+        ])?;
+        Ok(lp_price_from_value(value, pool_info.lp_token_supply)?)
+    }
+
+    fn simulate_swap_exact_out(
+        &self,
+        deps: Deps,
+        ask_asset: Asset,
+        offer_asset_info: AssetInfo,
+    ) -> StdResult<Uint128> {
+        let pool_info = self.query_info(&deps.querier)?;
+        let (input_reserve, output_reserve) =
+            reserves(&pool_info, &offer_asset_info, &ask_asset.info)?;
+
+        Ok(juno_get_input_amount_required(
+            ask_asset.amount,
+            input_reserve,
+            output_reserve,
+            swap_fee(&pool_info)?,
+        )?)
+    }
+
     fn lp_token(&self) -> AssetInfo {
         AssetInfo::Cw20(self.lp_token.clone())
     }
 }
+
+/// Wasmswap pairs keep no record of past prices, so TWAPs are not supported.
+impl PriceOracle for JunoswapPool {}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{from_binary, Binary, ContractResult, SystemResult};
+    use cw20_0_10_3::Denom;
+
+    use super::*;
+
+    fn mock_pool_querier(query: &WasmQuery) -> SystemResult<ContractResult<Binary>> {
+        let res = match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "pool" => {
+                match from_binary(msg).unwrap() {
+                    QueryMsg::Info {} => to_binary(&InfoResponse {
+                        token1_reserve: Uint128::new(1_000_000),
+                        token1_denom: Denom::Native("uatom".to_string()),
+                        token2_reserve: Uint128::new(1_000_000),
+                        token2_denom: Denom::Native("ujuno".to_string()),
+                        lp_token_supply: Uint128::new(1_000_000),
+                        lp_token_address: "lp_token".to_string(),
+                        owner: None,
+                        lp_fee_percent: Decimal::percent(20),
+                        protocol_fee_percent: Decimal::percent(10),
+                        protocol_fee_recipient: "protocol".to_string(),
+                    }),
+                    QueryMsg::Token1ForToken2Price { token1_amount } => {
+                        // Swap on the constant product curve with a 0.3% fee
+                        let input_with_fee = token1_amount * Uint128::new(997);
+                        to_binary(&Token1ForToken2PriceResponse {
+                            token2_amount: input_with_fee * Uint128::new(1_000_000)
+                                / (Uint128::new(1_000_000_000) + input_with_fee),
+                        })
+                    }
+                    _ => return SystemResult::Ok(ContractResult::Err("unexpected query".into())),
+                }
+            }
+            _ => return SystemResult::Ok(ContractResult::Err("unexpected query".into())),
+        };
+        SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+    }
+
+    #[test]
+    fn test_simulate_swap_detailed() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(mock_pool_querier);
+        let pool = JunoswapPool::new(Addr::unchecked("pool"), Addr::unchecked("lp_token"));
+
+        let simulation = pool
+            .simulate_swap_detailed(
+                deps.as_ref(),
+                Asset::native("uatom", 10_000u128),
+                AssetInfo::native("ujuno"),
+            )
+            .unwrap();
+
+        // The fee of 0.2% + 0.1% of the offered amount, valued at the spot
+        // price of 1
+        assert_eq!(
+            simulation,
+            SwapSimulation {
+                return_amount: Uint128::new(9_871),
+                spread_amount: Uint128::new(99),
+                commission_amount: Uint128::new(30),
+            }
+        );
+    }
+
+    /// Mocks a pool with a total fee of 1%, of which 0.4% goes to the protocol
+    fn mock_one_percent_fee_pool_querier(
+        query: &WasmQuery,
+    ) -> SystemResult<ContractResult<Binary>> {
+        match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == "pool" => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&InfoResponse {
+                        token1_reserve: Uint128::new(1_000_000),
+                        token1_denom: Denom::Native("uatom".to_string()),
+                        token2_reserve: Uint128::new(1_000_000),
+                        token2_denom: Denom::Native("ujuno".to_string()),
+                        lp_token_supply: Uint128::new(1_000_000),
+                        lp_token_address: "lp_token".to_string(),
+                        owner: None,
+                        lp_fee_percent: Decimal::permille(600),
+                        protocol_fee_percent: Decimal::permille(400),
+                        protocol_fee_recipient: "protocol".to_string(),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => SystemResult::Ok(ContractResult::Err("unexpected query".into())),
+        }
+    }
+
+    #[test]
+    fn test_swap_exact_out_with_pool_fee() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(mock_one_percent_fee_pool_querier);
+        let env = mock_env();
+        let pool = JunoswapPool::new(Addr::unchecked("pool"), Addr::unchecked("lp_token"));
+        let ask_asset = Asset::native("ujuno", 9_802u128);
+
+        let response = pool
+            .swap_exact_out(
+                deps.as_ref(),
+                &env,
+                ask_asset.clone(),
+                AssetInfo::native("uatom"),
+                Uint128::new(10_000),
+            )
+            .unwrap();
+        assert_eq!(response.expected_out, vec![ask_asset.clone()].into());
+        match &response.messages[0].msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => match from_binary(msg).unwrap() {
+                ExecuteMsg::Swap {
+                    input_amount,
+                    min_output,
+                    ..
+                } => {
+                    assert_eq!(input_amount, Uint128::new(10_000));
+                    assert_eq!(min_output, Uint128::new(9_802));
+                }
+                _ => panic!("unexpected execute msg"),
+            },
+            _ => panic!("unexpected msg"),
+        }
+
+        let err = pool
+            .swap_exact_out(
+                deps.as_ref(),
+                &env,
+                ask_asset,
+                AssetInfo::native("uatom"),
+                Uint128::new(9_999),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err,
+            CwDexError::MaxInExceeded {
+                max_in: Uint128::new(9_999),
+                required: Uint128::new(10_000),
+            }
+        );
+    }
+
+    #[test]
+    fn test_zap_in_with_pool_fee() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(mock_one_percent_fee_pool_querier);
+        let env = mock_env();
+        let pool = JunoswapPool::new(Addr::unchecked("pool"), Addr::unchecked("lp_token"));
+        let reserve = Uint128::new(1_000_000);
+
+        let response = pool
+            .zap_in(
+                deps.as_ref(),
+                &env,
+                Asset::native("uatom", 20_000u128),
+                Uint128::zero(),
+            )
+            .unwrap();
+        let (swap_amount, min_output) = match &response.messages[0].msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => match from_binary(msg).unwrap() {
+                ExecuteMsg::Swap {
+                    input_amount,
+                    min_output,
+                    ..
+                } => (input_amount, min_output),
+                _ => panic!("unexpected execute msg"),
+            },
+            _ => panic!("unexpected msg"),
+        };
+
+        // The swap must not revert with the 1% fee the pool charges
+        assert_eq!(
+            min_output,
+            juno_get_input_price(swap_amount, reserve, reserve, Decimal::percent(1)).unwrap()
+        );
+
+        // The remaining amount and the return of the swap are in the same
+        // ratio as the reserves after the swap, of which the protocol fee is
+        // not part.
+        let protocol_fee_amount =
+            juno_get_protocol_fee_amount(swap_amount, Decimal::permille(4)).unwrap();
+        let lhs = (Uint128::new(20_000) - swap_amount) * (reserve - min_output);
+        let rhs = min_output * (reserve + swap_amount - protocol_fee_amount);
+        assert!(lhs.max(rhs) - lhs.min(rhs) < lhs / Uint128::new(1_000));
+    }
+}
@@ -2,25 +2,25 @@
 
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    to_binary, Addr, CosmosMsg, Deps, Env, Event, QuerierWrapper, QueryRequest, Response, Uint128,
-    WasmMsg, WasmQuery,
+    to_binary, Addr, CosmosMsg, Deps, Env, Event, QuerierWrapper, QueryRequest, Response,
+    StdResult, Timestamp, Uint128, WasmMsg, WasmQuery,
 };
 use cw20::Cw20ExecuteMsg;
 
-use cw_asset::AssetList;
-use cw_utils::Duration;
+use cw_asset::{Asset, AssetInfo, AssetList};
+use cw_utils::{Duration, Expiration};
 use stake_cw20::msg::{
-    ExecuteMsg as Cw20StakeExecuteMsg, QueryMsg as Cw20StakeQueryMsg,
-    ReceiveMsg as Cw20StakeReceiveMsg,
+    ExecuteMsg as Cw20StakeExecuteMsg, GetHooksResponse, QueryMsg as Cw20StakeQueryMsg,
+    ReceiveMsg as Cw20StakeReceiveMsg, StakedBalanceAtHeightResponse,
 };
 use stake_cw20::state::Config;
 
-use crate::traits::{LockedStaking, Rewards, Stake, Unlock, Unstake};
+use super::msg::{
+    ClaimsResponse, Denom, PendingRewardsResponse, StakeCw20ExternalRewardsExecuteMsg,
+    StakeCw20ExternalRewardsQueryMsg,
+};
+use crate::traits::{LockedStaking, Rewards, Stake, UnbondingPosition, Unlock, Unstake};
 use crate::CwDexError;
-// use stake_cw20_external_rewards::msg::{
-//     ExecuteMsg as StakeCw20ExternalRewardsExecuteMsg, PendingRewardsResponse,
-//     QueryMsg as StakeCw20ExternalRewardsQueryMsg,
-// };
 
 /// Represents staking of LP tokens on Junoswap
 #[cw_serde]
@@ -56,6 +56,23 @@ impl Stake for JunoswapStaking {
 
         Ok(Response::new().add_message(stake_msg).add_event(event))
     }
+
+    fn query_staked_balance(
+        &self,
+        querier: &QuerierWrapper,
+        user: &Addr,
+    ) -> Result<Uint128, CwDexError> {
+        let res = querier.query::<StakedBalanceAtHeightResponse>(&QueryRequest::Wasm(
+            WasmQuery::Smart {
+                contract_addr: self.addr.to_string(),
+                msg: to_binary(&Cw20StakeQueryMsg::StakedBalanceAtHeight {
+                    address: user.to_string(),
+                    height: None,
+                })?,
+            },
+        ))?;
+        Ok(res.balance)
+    }
 }
 
 impl Unstake for JunoswapStaking {
@@ -79,81 +96,101 @@ impl Unstake for JunoswapStaking {
     }
 }
 
+impl JunoswapStaking {
+    /// Creates a new instance of `JunoswapStaking`.
+    ///
+    /// Arguments:
+    /// - `addr`: Address of the staking contract
+    /// - `lp_token_addr`: Address of the LP token contract
+    pub fn new(addr: Addr, lp_token_addr: Addr) -> Self {
+        Self {
+            addr,
+            lp_token_addr,
+        }
+    }
+
+    /// Returns the addresses of the hooks registered on the staking contract.
+    /// External rewards contracts are registered as hooks, but other
+    /// contracts may be as well.
+    fn query_hooks(&self, querier: &QuerierWrapper) -> StdResult<Vec<String>> {
+        Ok(querier
+            .query::<GetHooksResponse>(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: self.addr.to_string(),
+                msg: to_binary(&Cw20StakeQueryMsg::GetHooks {})?,
+            }))?
+            .hooks)
+    }
+
+    /// Returns the pending rewards of `user` in each external rewards
+    /// contract registered as a hook on the staking contract, together with
+    /// the address of the rewards contract.
+    fn query_pending_rewards_by_hook(
+        &self,
+        querier: &QuerierWrapper,
+        user: &Addr,
+    ) -> StdResult<Vec<(String, PendingRewardsResponse)>> {
+        let mut pending_rewards = vec![];
+        for hook in self.query_hooks(querier)? {
+            // Since we can't be sure that the hook is actually a rewards
+            // contract we ignore failed queries instead of failing the entire
+            // query.
+            let response = querier
+                .query::<PendingRewardsResponse>(&QueryRequest::Wasm(WasmQuery::Smart {
+                    contract_addr: hook.clone(),
+                    msg: to_binary(&StakeCw20ExternalRewardsQueryMsg::GetPendingRewards {
+                        address: user.to_string(),
+                    })?,
+                }))
+                .ok();
+
+            if let Some(response) = response {
+                pending_rewards.push((hook, response));
+            }
+        }
+        Ok(pending_rewards)
+    }
+}
+
 impl Rewards for JunoswapStaking {
-    fn claim_rewards(&self, _deps: Deps, _env: &Env) -> Result<Response, CwDexError> {
-        todo!("Implement JunoswapStaking::claim_rewards")
-        // let claim_messages = deps
-        //     .querier
-        //     .query::<GetHooksResponse>(&QueryRequest::Wasm(WasmQuery::Smart {
-        //         contract_addr: self.addr.to_string(),
-        //         msg: to_binary(&Cw20StakeQueryMsg::GetHooks {})?,
-        //     }))?
-        //     .hooks
-        //     .iter()
-        //     .map(|addr| {
-        //         // Call as SubMsg since we don't know if the contracts in the
-        // hooks are         // always going to be reward contracts. If
-        // not the messages for that         // contract should fail
-        // without failing the transaction.         Ok(SubMsg {
-        //             id: 0,
-        //             msg: CosmosMsg::Wasm(WasmMsg::Execute {
-        //                 contract_addr: addr.to_string(),
-        //                 funds: vec![],
-        //                 msg:
-        // to_binary(&StakeCw20ExternalRewardsExecuteMsg::Claim {})?,
-        //             }),
-        //             gas_limit: None,
-        //             reply_on: ReplyOn::Error,
-        //         })
-        //     })
-        //     .collect::<StdResult<Vec<SubMsg>>>()?;
-        //
-        // let event =
-        // Event::new("apollo/cw-dex/claim_rewards").add_attribute("type",
-        // "junoswap");
-        //
-        // Ok(Response::new().add_submessages(claim_messages).add_event(event))
+    /// Claims rewards from the external rewards contracts registered as hooks
+    /// on the staking contract that have pending rewards for the contract.
+    fn claim_rewards(&self, deps: Deps, env: &Env) -> Result<Response, CwDexError> {
+        let claim_messages = self
+            .query_pending_rewards_by_hook(&deps.querier, &env.contract.address)?
+            .into_iter()
+            .filter(|(_, pending_rewards)| !pending_rewards.pending_rewards.is_zero())
+            .map(|(addr, _)| {
+                Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: addr,
+                    funds: vec![],
+                    msg: to_binary(&StakeCw20ExternalRewardsExecuteMsg::Claim {})?,
+                }))
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let event = Event::new("apollo/cw-dex/claim_rewards").add_attribute("type", "junoswap");
+
+        Ok(Response::new()
+            .add_messages(claim_messages)
+            .add_event(event))
     }
 
     fn query_pending_rewards(
         &self,
-        _querier: &QuerierWrapper,
-        _user: &Addr,
+        querier: &QuerierWrapper,
+        user: &Addr,
     ) -> Result<AssetList, CwDexError> {
-        todo!("Implement JunoswapStaking::query_pending_rewards")
-        // let hooks = querier
-        //     .query::<GetHooksResponse>(&QueryRequest::Wasm(WasmQuery::Smart {
-        //         contract_addr: self.addr.to_string(),
-        //         msg: to_binary(&Cw20StakeQueryMsg::GetHooks {})?,
-        //     }))?
-        //     .hooks;
-        //
-        // let mut assets = AssetList::new();
-        // for hook in hooks {
-        //     // Since we can't be sure that the hook is actually a reward
-        // contract we must     // do .ok() and match only on `Some`
-        // values to avoid failing the entire query.
-        //     let pending_rewards = querier
-        //         .query::<PendingRewardsResponse>(&
-        // QueryRequest::Wasm(WasmQuery::Smart {
-        // contract_addr: hook.to_string(),             msg:
-        // to_binary(&StakeCw20ExternalRewardsQueryMsg::GetPendingRewards {
-        //                 address: user.to_string(),
-        //             })?,
-        //         }))
-        //         .ok();
-        //
-        //     if let Some(pending_rewards) = pending_rewards {
-        //         let asset_info = match pending_rewards.denom {
-        //             Denom::Native(x) => AssetInfo::Native(x),
-        //             Denom::Cw20(x) => AssetInfo::Cw20(x),
-        //         };
-        //
-        //         assets.add(&Asset::new(asset_info,
-        // pending_rewards.pending_rewards))?;     }
-        // }
-        //
-        // Ok(assets)
+        let mut assets = AssetList::new();
+        for (_, pending_rewards) in self.query_pending_rewards_by_hook(querier, user)? {
+            let asset_info = match pending_rewards.denom {
+                Denom::Native(x) => AssetInfo::Native(x),
+                Denom::Cw20(x) => AssetInfo::Cw20(x),
+            };
+
+            assets.add(&Asset::new(asset_info, pending_rewards.pending_rewards))?;
+        }
+
+        Ok(assets)
     }
 }
 
@@ -192,6 +229,36 @@ impl Unlock for JunoswapStaking {
 
         Ok(Response::new().add_message(unstake_msg).add_event(event))
     }
+
+    fn query_unbonding_positions(
+        &self,
+        querier: &QuerierWrapper,
+        user: &Addr,
+    ) -> Result<Vec<UnbondingPosition>, CwDexError> {
+        let claims = querier
+            .query::<ClaimsResponse>(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: self.addr.to_string(),
+                msg: to_binary(&Cw20StakeQueryMsg::Claims {
+                    address: user.to_string(),
+                })?,
+            }))?
+            .claims;
+
+        Ok(claims
+            .into_iter()
+            .map(|claim| UnbondingPosition {
+                id: None,
+                amount: claim.amount,
+                release_at: match claim.release_at {
+                    cw_utils_0_11::Expiration::AtHeight(x) => Expiration::AtHeight(x),
+                    cw_utils_0_11::Expiration::AtTime(x) => {
+                        Expiration::AtTime(Timestamp::from_nanos(x.nanos()))
+                    }
+                    cw_utils_0_11::Expiration::Never {} => Expiration::Never {},
+                },
+            })
+            .collect())
+    }
 }
 
 impl LockedStaking for JunoswapStaking {
@@ -213,3 +280,157 @@ impl LockedStaking for JunoswapStaking {
         Ok(duration)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MOCK_CONTRACT_ADDR};
+    use cosmwasm_std::{from_binary, Binary, ContractResult, SubMsg, SystemError, SystemResult};
+
+    use super::*;
+
+    #[test]
+    fn test_query_pending_rewards_skips_non_rewards_hooks() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "staking" => {
+                match from_binary(msg).unwrap() {
+                    Cw20StakeQueryMsg::GetHooks {} => SystemResult::Ok(ContractResult::Ok(
+                        to_binary(&GetHooksResponse {
+                            hooks: vec!["rewards".to_string(), "other_hook".to_string()],
+                        })
+                        .unwrap(),
+                    )),
+                    _ => SystemResult::Ok(ContractResult::Err("unexpected query".to_string())),
+                }
+            }
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == "rewards" => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&PendingRewardsResponse {
+                        address: "user".to_string(),
+                        pending_rewards: Uint128::new(100),
+                        denom: Denom::Native("ujuno".to_string()),
+                        last_update_block: 1,
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "not a rewards contract".to_string(),
+            }),
+        });
+
+        let staking = JunoswapStaking::new(Addr::unchecked("staking"), Addr::unchecked("lp_token"));
+        let rewards = staking
+            .query_pending_rewards(&deps.as_ref().querier, &Addr::unchecked("user"))
+            .unwrap();
+
+        assert_eq!(
+            rewards,
+            AssetList::from(vec![Asset::native("ujuno", 100u128)])
+        );
+    }
+
+    #[test]
+    fn test_claim_rewards_only_claims_pending_rewards() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "staking" => {
+                match from_binary(msg).unwrap() {
+                    Cw20StakeQueryMsg::GetHooks {} => SystemResult::Ok(ContractResult::Ok(
+                        to_binary(&GetHooksResponse {
+                            hooks: vec![
+                                "rewards".to_string(),
+                                "empty_rewards".to_string(),
+                                "other_hook".to_string(),
+                            ],
+                        })
+                        .unwrap(),
+                    )),
+                    _ => SystemResult::Ok(ContractResult::Err("unexpected query".to_string())),
+                }
+            }
+            WasmQuery::Smart { contract_addr, .. }
+                if contract_addr == "rewards" || contract_addr == "empty_rewards" =>
+            {
+                let pending_rewards = if contract_addr == "rewards" {
+                    Uint128::new(100)
+                } else {
+                    Uint128::zero()
+                };
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&PendingRewardsResponse {
+                        address: MOCK_CONTRACT_ADDR.to_string(),
+                        pending_rewards,
+                        denom: Denom::Native("ujuno".to_string()),
+                        last_update_block: 1,
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "not a rewards contract".to_string(),
+            }),
+        });
+
+        let staking = JunoswapStaking::new(Addr::unchecked("staking"), Addr::unchecked("lp_token"));
+        let res = staking.claim_rewards(deps.as_ref(), &mock_env()).unwrap();
+
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: "rewards".to_string(),
+                funds: vec![],
+                msg: to_binary(&StakeCw20ExternalRewardsExecuteMsg::Claim {}).unwrap(),
+            })]
+        );
+    }
+
+    #[test]
+    fn test_query_unbonding_positions() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "staking" => {
+                match from_binary(msg).unwrap() {
+                    Cw20StakeQueryMsg::Claims { address } => {
+                        assert_eq!(address, "user");
+                        SystemResult::Ok(ContractResult::Ok(Binary::from(
+                            br#"{"claims":[{"amount":"100","release_at":{"at_height":12345}},{"amount":"200","release_at":{"at_time":"1700000000000000001"}},{"amount":"300","release_at":{"never":{}}}]}"#.as_slice(),
+                        )))
+                    }
+                    _ => SystemResult::Ok(ContractResult::Err("unexpected query".to_string())),
+                }
+            }
+            _ => panic!("unexpected query"),
+        });
+
+        let staking = JunoswapStaking::new(Addr::unchecked("staking"), Addr::unchecked("lp_token"));
+        let positions = staking
+            .query_unbonding_positions(&deps.as_ref().querier, &Addr::unchecked("user"))
+            .unwrap();
+
+        // The cw-utils 0.11 expirations of the claims are converted without
+        // losing precision
+        assert_eq!(
+            positions,
+            vec![
+                UnbondingPosition {
+                    id: None,
+                    amount: Uint128::new(100),
+                    release_at: Expiration::AtHeight(12345),
+                },
+                UnbondingPosition {
+                    id: None,
+                    amount: Uint128::new(200),
+                    release_at: Expiration::AtTime(Timestamp::from_nanos(
+                        1_700_000_000_000_000_001
+                    )),
+                },
+                UnbondingPosition {
+                    id: None,
+                    amount: Uint128::new(300),
+                    release_at: Expiration::Never {},
+                },
+            ]
+        );
+    }
+}
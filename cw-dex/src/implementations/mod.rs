@@ -14,5 +14,18 @@ pub mod junoswap;
 pub mod osmosis;
 
 pub mod pool;
+pub mod reply_ids;
+pub mod router;
+pub mod staking;
+
+#[cfg(any(feature = "astroport", feature = "osmosis-cl"))]
+mod helpers;
+#[cfg(any(feature = "astroport", feature = "junoswap", feature = "osmosis"))]
+mod valuation;
+#[cfg(any(feature = "astroport", feature = "junoswap", feature = "osmosis"))]
+mod zap;
 
 pub use pool::*;
+pub use reply_ids::*;
+pub use router::*;
+pub use staking::*;
@@ -0,0 +1,277 @@
+//! Tick and liquidity math for Osmosis concentrated liquidity pools.
+//!
+//! Ported from the Osmosis concentrated-liquidity module:
+//! - <https://github.com/osmosis-labs/osmosis/blob/v16.0.0/x/concentrated-liquidity/math/tick.go>
+//! - <https://github.com/osmosis-labs/osmosis/blob/v16.0.0/x/concentrated-liquidity/math/math.go>
+
+use cosmwasm_std::{Decimal256, StdError, StdResult, Uint256};
+
+/// The minimum tick of a concentrated liquidity pool, corresponding to a
+/// price of 10^-12.
+pub const MIN_TICK: i64 = -108_000_000;
+/// The maximum tick of a concentrated liquidity pool, corresponding to a
+/// price of 10^38.
+pub const MAX_TICK: i64 = 342_000_000;
+
+/// The exponent of the additive increment per tick at a price of one.
+const EXPONENT_AT_PRICE_ONE: i64 = -6;
+/// The number of ticks after which the additive increment per tick is
+/// multiplied by ten.
+const GEOMETRIC_EXPONENT_INCREMENT_DISTANCE_IN_TICKS: i64 = 9_000_000;
+
+fn checked_div(a: Decimal256, b: Decimal256) -> StdResult<Decimal256> {
+    a.checked_div(b)
+        .map_err(|e| StdError::generic_err(e.to_string()))
+}
+
+/// Returns 10^`exponent`. Exponents below -18 return zero.
+fn pow_ten(exponent: i64) -> StdResult<Decimal256> {
+    let ten = Uint256::from(10u128);
+    if exponent >= 0 {
+        Ok(Decimal256::from_ratio(
+            ten.checked_pow(exponent as u32)?,
+            1u128,
+        ))
+    } else {
+        Ok(Decimal256::from_ratio(
+            1u128,
+            ten.checked_pow(exponent.unsigned_abs() as u32)?,
+        ))
+    }
+}
+
+/// Returns the price corresponding to `tick`.
+pub(crate) fn tick_to_price(tick: i64) -> StdResult<Decimal256> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(StdError::generic_err(format!(
+            "tick {} is out of range [{}, {}]",
+            tick, MIN_TICK, MAX_TICK
+        )));
+    }
+    if tick == 0 {
+        return Ok(Decimal256::one());
+    }
+
+    // Integer division truncates towards zero, as in Go.
+    let geometric_exponent_delta = tick / GEOMETRIC_EXPONENT_INCREMENT_DISTANCE_IN_TICKS;
+    let mut exponent_at_current_tick = EXPONENT_AT_PRICE_ONE + geometric_exponent_delta;
+    if tick < 0 {
+        // Step up in precision when going below a price of one.
+        exponent_at_current_tick -= 1;
+    }
+    let current_additive_increment_in_ticks = pow_ten(exponent_at_current_tick)?;
+    let num_additive_ticks =
+        tick - geometric_exponent_delta * GEOMETRIC_EXPONENT_INCREMENT_DISTANCE_IN_TICKS;
+    let additive = Decimal256::from_ratio(num_additive_ticks.unsigned_abs(), 1u128)
+        .checked_mul(current_additive_increment_in_ticks)?;
+
+    let price = pow_ten(geometric_exponent_delta)?;
+    if num_additive_ticks < 0 {
+        Ok(price.checked_sub(additive)?)
+    } else {
+        Ok(price.checked_add(additive)?)
+    }
+}
+
+/// Returns the square root of the price corresponding to `tick`.
+pub(crate) fn tick_to_sqrt_price(tick: i64) -> StdResult<Decimal256> {
+    Ok(tick_to_price(tick)?.sqrt())
+}
+
+/// Returns the liquidity provided by `amount0` of token0 in the range
+/// `[sqrt_price_a, sqrt_price_b]`.
+fn liquidity0(
+    amount0: Decimal256,
+    sqrt_price_a: Decimal256,
+    sqrt_price_b: Decimal256,
+) -> StdResult<Decimal256> {
+    let (a, b) = (
+        sqrt_price_a.min(sqrt_price_b),
+        sqrt_price_a.max(sqrt_price_b),
+    );
+    checked_div(amount0.checked_mul(a)?.checked_mul(b)?, b - a)
+}
+
+/// Returns the liquidity provided by `amount1` of token1 in the range
+/// `[sqrt_price_a, sqrt_price_b]`.
+fn liquidity1(
+    amount1: Decimal256,
+    sqrt_price_a: Decimal256,
+    sqrt_price_b: Decimal256,
+) -> StdResult<Decimal256> {
+    let (a, b) = (
+        sqrt_price_a.min(sqrt_price_b),
+        sqrt_price_a.max(sqrt_price_b),
+    );
+    checked_div(amount1, b - a)
+}
+
+/// Returns the amount of token0 backing `liquidity` in the range
+/// `[sqrt_price_a, sqrt_price_b]`.
+fn amount0_delta(
+    liquidity: Decimal256,
+    sqrt_price_a: Decimal256,
+    sqrt_price_b: Decimal256,
+) -> StdResult<Decimal256> {
+    let (a, b) = (
+        sqrt_price_a.min(sqrt_price_b),
+        sqrt_price_a.max(sqrt_price_b),
+    );
+    checked_div(checked_div(liquidity.checked_mul(b - a)?, b)?, a)
+}
+
+/// Returns the amount of token1 backing `liquidity` in the range
+/// `[sqrt_price_a, sqrt_price_b]`.
+fn amount1_delta(
+    liquidity: Decimal256,
+    sqrt_price_a: Decimal256,
+    sqrt_price_b: Decimal256,
+) -> StdResult<Decimal256> {
+    let (a, b) = (
+        sqrt_price_a.min(sqrt_price_b),
+        sqrt_price_a.max(sqrt_price_b),
+    );
+    Ok(liquidity.checked_mul(b - a)?)
+}
+
+/// Returns the maximum liquidity that can be created in the range
+/// `[sqrt_price_lower, sqrt_price_upper]` with at most `amount0` of token0
+/// and `amount1` of token1 at the current square root price.
+pub(crate) fn liquidity_for_amounts(
+    sqrt_price_current: Decimal256,
+    sqrt_price_lower: Decimal256,
+    sqrt_price_upper: Decimal256,
+    amount0: Decimal256,
+    amount1: Decimal256,
+) -> StdResult<Decimal256> {
+    if sqrt_price_current <= sqrt_price_lower {
+        liquidity0(amount0, sqrt_price_lower, sqrt_price_upper)
+    } else if sqrt_price_current >= sqrt_price_upper {
+        liquidity1(amount1, sqrt_price_lower, sqrt_price_upper)
+    } else {
+        Ok(
+            liquidity0(amount0, sqrt_price_current, sqrt_price_upper)?.min(liquidity1(
+                amount1,
+                sqrt_price_lower,
+                sqrt_price_current,
+            )?),
+        )
+    }
+}
+
+/// Returns the amounts of token0 and token1 backing `liquidity` in the range
+/// `[sqrt_price_lower, sqrt_price_upper]` at the current square root price.
+pub(crate) fn amounts_for_liquidity(
+    sqrt_price_current: Decimal256,
+    sqrt_price_lower: Decimal256,
+    sqrt_price_upper: Decimal256,
+    liquidity: Decimal256,
+) -> StdResult<(Decimal256, Decimal256)> {
+    if sqrt_price_current <= sqrt_price_lower {
+        Ok((
+            amount0_delta(liquidity, sqrt_price_lower, sqrt_price_upper)?,
+            Decimal256::zero(),
+        ))
+    } else if sqrt_price_current >= sqrt_price_upper {
+        Ok((
+            Decimal256::zero(),
+            amount1_delta(liquidity, sqrt_price_lower, sqrt_price_upper)?,
+        ))
+    } else {
+        Ok((
+            amount0_delta(liquidity, sqrt_price_current, sqrt_price_upper)?,
+            amount1_delta(liquidity, sqrt_price_lower, sqrt_price_current)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_to_price() {
+        assert_eq!(tick_to_price(0).unwrap(), Decimal256::one());
+        // Each tick between price one and ten adds 10^-6
+        assert_eq!(
+            tick_to_price(1).unwrap(),
+            Decimal256::from_ratio(1_000_001u128, 1_000_000u128)
+        );
+        assert_eq!(
+            tick_to_price(9_000_000).unwrap(),
+            Decimal256::from_ratio(10u128, 1u128)
+        );
+        assert_eq!(
+            tick_to_price(9_000_001).unwrap(),
+            Decimal256::from_ratio(1_000_001u128, 100_000u128)
+        );
+        // Each tick between price 0.1 and one subtracts 10^-7
+        assert_eq!(
+            tick_to_price(-1).unwrap(),
+            Decimal256::from_ratio(9_999_999u128, 10_000_000u128)
+        );
+        assert_eq!(
+            tick_to_price(-9_000_000).unwrap(),
+            Decimal256::from_ratio(1u128, 10u128)
+        );
+        assert!(tick_to_price(MIN_TICK - 1).is_err());
+        assert!(tick_to_price(MAX_TICK + 1).is_err());
+    }
+
+    #[test]
+    fn test_liquidity_round_trip_in_range() {
+        let sqrt_price_current = Decimal256::one();
+        let sqrt_price_lower = tick_to_sqrt_price(-1_000_000).unwrap();
+        let sqrt_price_upper = tick_to_sqrt_price(1_000_000).unwrap();
+        let amount = Decimal256::from_ratio(1_000_000u128, 1u128);
+
+        let liquidity = liquidity_for_amounts(
+            sqrt_price_current,
+            sqrt_price_lower,
+            sqrt_price_upper,
+            amount,
+            amount,
+        )
+        .unwrap();
+        let (amount0, amount1) = amounts_for_liquidity(
+            sqrt_price_current,
+            sqrt_price_lower,
+            sqrt_price_upper,
+            liquidity,
+        )
+        .unwrap();
+
+        // One of the amounts is used fully, the other one at most fully
+        let tolerance = Decimal256::from_ratio(1u128, 1_000u128);
+        assert!(amount0 <= amount + tolerance && amount1 <= amount + tolerance);
+        assert!(
+            amount - amount0.min(amount) < tolerance || amount - amount1.min(amount) < tolerance
+        );
+    }
+
+    #[test]
+    fn test_liquidity_out_of_range_uses_single_asset() {
+        let sqrt_price_lower = tick_to_sqrt_price(1_000_000).unwrap();
+        let sqrt_price_upper = tick_to_sqrt_price(2_000_000).unwrap();
+        let amount = Decimal256::from_ratio(1_000_000u128, 1u128);
+
+        // Current price below the range, only token0 is used
+        let liquidity = liquidity_for_amounts(
+            Decimal256::one(),
+            sqrt_price_lower,
+            sqrt_price_upper,
+            amount,
+            amount,
+        )
+        .unwrap();
+        let (amount0, amount1) = amounts_for_liquidity(
+            Decimal256::one(),
+            sqrt_price_lower,
+            sqrt_price_upper,
+            liquidity,
+        )
+        .unwrap();
+        assert!(amount1.is_zero());
+        assert!(amount - amount0.min(amount) < Decimal256::from_ratio(1u128, 1_000u128));
+    }
+}
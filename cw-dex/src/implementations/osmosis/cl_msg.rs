@@ -0,0 +1,374 @@
+//! Messages and queries for the Osmosis concentrated liquidity and
+//! poolmanager modules. Requires the `osmosis-cl` feature.
+//!
+//! The version of `osmosis-std` used by this crate predates concentrated
+//! liquidity and the poolmanager module, so the protobuf types are defined
+//! here and sent as `CosmosMsg::Stargate` messages and
+//! `QueryRequest::Stargate` queries.
+//! See <https://github.com/osmosis-labs/osmosis/tree/v16.0.0/proto/osmosis>.
+
+#![allow(missing_docs)]
+
+use cosmwasm_std::{Binary, CosmosMsg, QuerierWrapper, QueryRequest, StdResult};
+use osmosis_std::types::cosmos::base::v1beta1::Coin as ProtoCoin;
+use osmosis_std::types::osmosis::gamm::v1beta1::{SwapAmountInRoute, SwapAmountOutRoute};
+use prost::Message;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+/// Implements `From<$msg> for CosmosMsg` by encoding the message as a
+/// `CosmosMsg::Stargate` with the given type url.
+macro_rules! impl_stargate_msg {
+    ($msg:ty, $type_url:literal) => {
+        impl $msg {
+            pub const TYPE_URL: &'static str = $type_url;
+        }
+
+        impl<T> From<$msg> for CosmosMsg<T> {
+            fn from(msg: $msg) -> Self {
+                CosmosMsg::Stargate {
+                    type_url: <$msg>::TYPE_URL.to_string(),
+                    value: Binary(msg.encode_to_vec()),
+                }
+            }
+        }
+    };
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgCreatePosition {
+    #[prost(uint64, tag = "1")]
+    pub pool_id: u64,
+    #[prost(string, tag = "2")]
+    pub sender: String,
+    #[prost(int64, tag = "3")]
+    pub lower_tick: i64,
+    #[prost(int64, tag = "4")]
+    pub upper_tick: i64,
+    #[prost(message, repeated, tag = "5")]
+    pub tokens_provided: Vec<ProtoCoin>,
+    #[prost(string, tag = "6")]
+    pub token_min_amount0: String,
+    #[prost(string, tag = "7")]
+    pub token_min_amount1: String,
+}
+impl_stargate_msg!(
+    MsgCreatePosition,
+    "/osmosis.concentratedliquidity.v1beta1.MsgCreatePosition"
+);
+
+/// The response data of a `MsgCreatePosition`, returned in the reply of the
+/// submessage.
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgCreatePositionResponse {
+    #[prost(uint64, tag = "1")]
+    pub position_id: u64,
+    #[prost(string, tag = "2")]
+    pub amount0: String,
+    #[prost(string, tag = "3")]
+    pub amount1: String,
+    #[prost(string, tag = "5")]
+    pub liquidity_created: String,
+    #[prost(int64, tag = "6")]
+    pub lower_tick: i64,
+    #[prost(int64, tag = "7")]
+    pub upper_tick: i64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgWithdrawPosition {
+    #[prost(uint64, tag = "1")]
+    pub position_id: u64,
+    #[prost(string, tag = "2")]
+    pub sender: String,
+    #[prost(string, tag = "3")]
+    pub liquidity_amount: String,
+}
+impl_stargate_msg!(
+    MsgWithdrawPosition,
+    "/osmosis.concentratedliquidity.v1beta1.MsgWithdrawPosition"
+);
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgCollectSpreadRewards {
+    #[prost(uint64, repeated, tag = "1")]
+    pub position_ids: Vec<u64>,
+    #[prost(string, tag = "2")]
+    pub sender: String,
+}
+impl_stargate_msg!(
+    MsgCollectSpreadRewards,
+    "/osmosis.concentratedliquidity.v1beta1.MsgCollectSpreadRewards"
+);
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgCollectIncentives {
+    #[prost(uint64, repeated, tag = "1")]
+    pub position_ids: Vec<u64>,
+    #[prost(string, tag = "2")]
+    pub sender: String,
+}
+impl_stargate_msg!(
+    MsgCollectIncentives,
+    "/osmosis.concentratedliquidity.v1beta1.MsgCollectIncentives"
+);
+
+/// Swaps through the pools of `routes`, which may be of any pool type. The
+/// routes are encoded the same as the gamm `SwapAmountInRoute`. Only used for
+/// concentrated liquidity pools, gamm pools are swapped through gamm messages
+/// which are also supported by chains without the poolmanager module.
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgSwapExactAmountIn {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    #[prost(message, repeated, tag = "2")]
+    pub routes: Vec<SwapAmountInRoute>,
+    #[prost(message, optional, tag = "3")]
+    pub token_in: Option<ProtoCoin>,
+    #[prost(string, tag = "4")]
+    pub token_out_min_amount: String,
+}
+impl_stargate_msg!(
+    MsgSwapExactAmountIn,
+    "/osmosis.poolmanager.v1beta1.MsgSwapExactAmountIn"
+);
+
+/// Swaps through the pools of `routes`, which may be of any pool type. The
+/// routes are encoded the same as the gamm `SwapAmountOutRoute`. Only used for
+/// concentrated liquidity pools.
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgSwapExactAmountOut {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    #[prost(message, repeated, tag = "2")]
+    pub routes: Vec<SwapAmountOutRoute>,
+    #[prost(string, tag = "3")]
+    pub token_in_max_amount: String,
+    #[prost(message, optional, tag = "4")]
+    pub token_out: Option<ProtoCoin>,
+}
+impl_stargate_msg!(
+    MsgSwapExactAmountOut,
+    "/osmosis.poolmanager.v1beta1.MsgSwapExactAmountOut"
+);
+
+/// Sends a stargate query with the protobuf encoded `request` to `path` and
+/// deserializes the JSON response.
+fn query_stargate<Req: Message, Res: DeserializeOwned>(
+    querier: &QuerierWrapper,
+    path: &str,
+    request: Req,
+) -> StdResult<Res> {
+    querier.query(&QueryRequest::Stargate {
+        path: path.to_string(),
+        data: Binary(request.encode_to_vec()),
+    })
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct PoolRequest {
+    #[prost(uint64, tag = "1")]
+    pub pool_id: u64,
+}
+
+/// The concentrated liquidity pool returned by the poolmanager `Pool` query.
+/// Only the fields used by this crate are deserialized.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ClPool {
+    pub token0: String,
+    pub token1: String,
+    pub current_sqrt_price: String,
+    pub current_tick: String,
+    pub spread_factor: String,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct PoolResponse {
+    pub pool: ClPool,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct TotalPoolLiquidityRequest {
+    #[prost(uint64, tag = "1")]
+    pub pool_id: u64,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct JsonCoin {
+    pub denom: String,
+    pub amount: String,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct TotalPoolLiquidityResponse {
+    pub liquidity: Vec<JsonCoin>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct SpotPriceRequest {
+    #[prost(uint64, tag = "1")]
+    pub pool_id: u64,
+    #[prost(string, tag = "2")]
+    pub base_asset_denom: String,
+    #[prost(string, tag = "3")]
+    pub quote_asset_denom: String,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct SpotPriceResponse {
+    pub spot_price: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct EstimateSwapExactAmountInRequest {
+    #[prost(uint64, tag = "2")]
+    pub pool_id: u64,
+    #[prost(string, tag = "3")]
+    pub token_in: String,
+    #[prost(message, repeated, tag = "4")]
+    pub routes: Vec<SwapAmountInRoute>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct EstimateSwapExactAmountInResponse {
+    pub token_out_amount: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct EstimateSwapExactAmountOutRequest {
+    #[prost(uint64, tag = "2")]
+    pub pool_id: u64,
+    #[prost(message, repeated, tag = "3")]
+    pub routes: Vec<SwapAmountOutRoute>,
+    #[prost(string, tag = "4")]
+    pub token_out: String,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct EstimateSwapExactAmountOutResponse {
+    pub token_in_amount: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct PositionByIdRequest {
+    #[prost(uint64, tag = "1")]
+    pub position_id: u64,
+}
+
+/// A concentrated liquidity position. Only the fields used by this crate are
+/// deserialized.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Position {
+    pub liquidity: String,
+}
+
+/// A position with its claimable rewards. Only the fields used by this crate
+/// are deserialized.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct FullPositionBreakdown {
+    pub position: Position,
+    pub claimable_spread_rewards: Vec<JsonCoin>,
+    pub claimable_incentives: Vec<JsonCoin>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct PositionByIdResponse {
+    pub position: FullPositionBreakdown,
+}
+
+/// Querier for the concentrated liquidity module
+pub struct ConcentratedLiquidityQuerier<'a> {
+    querier: &'a QuerierWrapper<'a>,
+}
+
+impl<'a> ConcentratedLiquidityQuerier<'a> {
+    pub fn new(querier: &'a QuerierWrapper<'a>) -> Self {
+        Self { querier }
+    }
+
+    pub fn position_by_id(&self, position_id: u64) -> StdResult<PositionByIdResponse> {
+        query_stargate(
+            self.querier,
+            "/osmosis.concentratedliquidity.v1beta1.Query/PositionById",
+            PositionByIdRequest { position_id },
+        )
+    }
+}
+
+/// Querier for the poolmanager module
+pub struct PoolmanagerQuerier<'a> {
+    querier: &'a QuerierWrapper<'a>,
+}
+
+impl<'a> PoolmanagerQuerier<'a> {
+    pub fn new(querier: &'a QuerierWrapper<'a>) -> Self {
+        Self { querier }
+    }
+
+    pub fn pool(&self, pool_id: u64) -> StdResult<PoolResponse> {
+        query_stargate(
+            self.querier,
+            "/osmosis.poolmanager.v1beta1.Query/Pool",
+            PoolRequest { pool_id },
+        )
+    }
+
+    pub fn total_pool_liquidity(&self, pool_id: u64) -> StdResult<TotalPoolLiquidityResponse> {
+        query_stargate(
+            self.querier,
+            "/osmosis.poolmanager.v1beta1.Query/TotalPoolLiquidity",
+            TotalPoolLiquidityRequest { pool_id },
+        )
+    }
+
+    pub fn spot_price(
+        &self,
+        pool_id: u64,
+        base_asset_denom: String,
+        quote_asset_denom: String,
+    ) -> StdResult<SpotPriceResponse> {
+        query_stargate(
+            self.querier,
+            "/osmosis.poolmanager.v1beta1.Query/SpotPrice",
+            SpotPriceRequest {
+                pool_id,
+                base_asset_denom,
+                quote_asset_denom,
+            },
+        )
+    }
+
+    pub fn estimate_swap_exact_amount_in(
+        &self,
+        pool_id: u64,
+        token_in: String,
+        routes: Vec<SwapAmountInRoute>,
+    ) -> StdResult<EstimateSwapExactAmountInResponse> {
+        query_stargate(
+            self.querier,
+            "/osmosis.poolmanager.v1beta1.Query/EstimateSwapExactAmountIn",
+            EstimateSwapExactAmountInRequest {
+                pool_id,
+                token_in,
+                routes,
+            },
+        )
+    }
+
+    pub fn estimate_swap_exact_amount_out(
+        &self,
+        pool_id: u64,
+        routes: Vec<SwapAmountOutRoute>,
+        token_out: String,
+    ) -> StdResult<EstimateSwapExactAmountOutResponse> {
+        query_stargate(
+            self.querier,
+            "/osmosis.poolmanager.v1beta1.Query/EstimateSwapExactAmountOut",
+            EstimateSwapExactAmountOutRequest {
+                pool_id,
+                routes,
+                token_out,
+            },
+        )
+    }
+}
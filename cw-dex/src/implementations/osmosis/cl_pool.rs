@@ -0,0 +1,774 @@
+//! Pool trait implementation for Osmosis concentrated liquidity pools
+
+use std::str::FromStr;
+
+use apollo_utils::assets::{assert_native_asset_info, assert_native_coin};
+use osmosis_std::types::cosmos::base::v1beta1::Coin as ProtoCoin;
+use osmosis_std::types::osmosis::gamm::v1beta1::{SwapAmountInRoute, SwapAmountOutRoute};
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    Addr, Coin, Decimal, Decimal256, Deps, Env, Event, QuerierWrapper, ReplyOn, Response, StdError,
+    StdResult, SubMsg, Uint128,
+};
+use cw_asset::{Asset, AssetInfo, AssetList};
+
+use crate::callback::CwDexCallbackMsg;
+use crate::implementations::helpers::assert_min_out;
+use crate::implementations::reply_ids::ReplyIds;
+use crate::response::CwDexResponse;
+use crate::traits::{
+    CumulativePricesSnapshot, Pool, PoolParams, PoolType, PriceOracle, Rewards, SwapSimulation,
+};
+use crate::CwDexError;
+
+use super::cl_math::{
+    amounts_for_liquidity, liquidity_for_amounts, tick_to_sqrt_price, MAX_TICK, MIN_TICK,
+};
+use super::cl_msg::{
+    ClPool, ConcentratedLiquidityQuerier, FullPositionBreakdown, MsgCollectIncentives,
+    MsgCollectSpreadRewards, MsgCreatePosition, MsgSwapExactAmountIn, MsgSwapExactAmountOut,
+    MsgWithdrawPosition, PoolmanagerQuerier,
+};
+use super::helpers::{
+    parse_osmosis_dec, parse_osmosis_dec256, query_arithmetic_twap, swap_simulation_from_spot_price,
+};
+use super::math::{floor, to_decimal256};
+
+pub use crate::implementations::reply_ids::OSMOSIS_CREATE_POSITION_REPLY_ID;
+
+/// Struct for interacting with a position in an Osmosis concentrated liquidity
+/// pool within the tick range `[lower_tick, upper_tick]`.
+///
+/// Concentrated liquidity positions are not represented by LP tokens. Instead
+/// the liquidity of the position, truncated to an integer, is used as the
+/// amount of the pseudo LP token returned by [`Pool::lp_token`]. The id of the
+/// position is returned in the reply of the `MsgCreatePosition` submessage
+/// with id [`OSMOSIS_CREATE_POSITION_REPLY_ID`], unless configured otherwise
+/// with [`OsmosisClPool::with_reply_ids`]. It can be parsed with
+/// [`parse_create_position_reply`](super::reply::parse_create_position_reply)
+/// and must be set with
+/// [`OsmosisClPool::with_position_id`] before withdrawing liquidity or
+/// collecting rewards.
+///
+/// Withdrawing liquidity with a non-zero `min_out` sends a
+/// [`CwDexCallbackMsg`] to the calling contract, which must handle it. The
+/// spread rewards and incentives of the position are claimed through
+/// [`Rewards`].
+///
+/// Requires the `osmosis-cl` feature and a chain with the concentrated
+/// liquidity and poolmanager modules of Osmosis v16 or later.
+#[cw_serde]
+#[derive(Copy)]
+pub struct OsmosisClPool {
+    /// The pool id of the pool to interact with
+    pool_id: u64,
+    /// The lower tick of the position
+    lower_tick: i64,
+    /// The upper tick of the position
+    upper_tick: i64,
+    /// The id of the position, if it has been created
+    position_id: Option<u64>,
+    /// Reply IDs of the submessages sent by this implementation
+    #[serde(default)]
+    reply_ids: ReplyIds,
+}
+
+impl OsmosisClPool {
+    /// Creates a new `OsmosisClPool` instance with the given `pool_id` and tick
+    /// range and validates that the pool exists and that the range is valid.
+    pub fn new(pool_id: u64, lower_tick: i64, upper_tick: i64, deps: Deps) -> StdResult<Self> {
+        let pool = Self::unchecked(pool_id, lower_tick, upper_tick);
+        if lower_tick >= upper_tick || lower_tick < MIN_TICK || upper_tick > MAX_TICK {
+            return Err(StdError::generic_err(format!(
+                "osmosis error: invalid tick range [{}, {}]",
+                lower_tick, upper_tick
+            )));
+        }
+        // If this query succeeds then the pool exists and is a concentrated
+        // liquidity pool
+        pool.query_pool(&deps.querier)?;
+        Ok(pool)
+    }
+
+    /// Creates an unchecked pool for use in testing.
+    pub fn unchecked(pool_id: u64, lower_tick: i64, upper_tick: i64) -> Self {
+        Self {
+            pool_id,
+            lower_tick,
+            upper_tick,
+            position_id: None,
+            reply_ids: ReplyIds::default(),
+        }
+    }
+
+    /// Returns a copy of the pool that sends submessages with the given reply
+    /// IDs instead of the default ones.
+    pub fn with_reply_ids(self, reply_ids: ReplyIds) -> Self {
+        Self { reply_ids, ..self }
+    }
+
+    /// Returns a copy of the pool with the position id set to `position_id`.
+    pub fn with_position_id(self, position_id: u64) -> Self {
+        Self {
+            position_id: Some(position_id),
+            ..self
+        }
+    }
+
+    /// Returns the pool id of the pool
+    pub fn pool_id(&self) -> u64 {
+        self.pool_id
+    }
+
+    /// Returns the lower tick of the position
+    pub fn lower_tick(&self) -> i64 {
+        self.lower_tick
+    }
+
+    /// Returns the upper tick of the position
+    pub fn upper_tick(&self) -> i64 {
+        self.upper_tick
+    }
+
+    /// Returns the id of the position, if it has been set
+    pub fn position_id(&self) -> Option<u64> {
+        self.position_id
+    }
+
+    /// Queries the poolmanager module for the state of the pool.
+    fn query_pool(&self, querier: &QuerierWrapper) -> StdResult<ClPool> {
+        Ok(PoolmanagerQuerier::new(querier).pool(self.pool_id)?.pool)
+    }
+
+    /// Queries the poolmanager module for the spot price of `base_asset`
+    /// denominated in `quote_asset`.
+    fn query_spot_price(
+        &self,
+        querier: &QuerierWrapper,
+        base_asset: &AssetInfo,
+        quote_asset: &AssetInfo,
+    ) -> StdResult<Decimal> {
+        let response = PoolmanagerQuerier::new(querier).spot_price(
+            self.pool_id,
+            assert_native_asset_info(base_asset)?,
+            assert_native_asset_info(quote_asset)?,
+        )?;
+        parse_osmosis_dec(&response.spot_price)
+    }
+
+    fn position_id_or_err(&self) -> StdResult<u64> {
+        self.position_id
+            .ok_or_else(|| StdError::generic_err("osmosis error: position id not set"))
+    }
+
+    /// Queries the concentrated liquidity module for the position and its
+    /// claimable rewards.
+    fn query_position(&self, querier: &QuerierWrapper) -> StdResult<FullPositionBreakdown> {
+        Ok(ConcentratedLiquidityQuerier::new(querier)
+            .position_by_id(self.position_id_or_err()?)?
+            .position)
+    }
+
+    /// Returns the liquidity to withdraw for `lp_amount` of the pseudo LP
+    /// token. The pseudo LP amount of the position is its liquidity truncated
+    /// to an integer, so withdrawing the whole position withdraws its exact
+    /// liquidity instead, leaving no dust liquidity in the position.
+    fn withdraw_liquidity_amount(
+        &self,
+        querier: &QuerierWrapper,
+        lp_amount: Uint128,
+    ) -> StdResult<Decimal256> {
+        if self.position_id.is_none() {
+            return Ok(to_decimal256(lp_amount));
+        }
+        let position_liquidity =
+            parse_osmosis_dec256(&self.query_position(querier)?.position.liquidity)?;
+        if lp_amount == floor(position_liquidity)? {
+            Ok(position_liquidity)
+        } else {
+            Ok(to_decimal256(lp_amount))
+        }
+    }
+
+    /// Returns the current, lower and upper square root prices of the position.
+    fn sqrt_prices(&self, pool: &ClPool) -> StdResult<(Decimal256, Decimal256, Decimal256)> {
+        Ok((
+            parse_osmosis_dec256(&pool.current_sqrt_price)?,
+            tick_to_sqrt_price(self.lower_tick)?,
+            tick_to_sqrt_price(self.upper_tick)?,
+        ))
+    }
+
+    /// Simulates creating a position in the tick range with at most `assets`.
+    /// Returns the liquidity of the position and the assets used to create it.
+    pub fn simulate_create_position(
+        &self,
+        deps: Deps,
+        assets: &AssetList,
+    ) -> StdResult<(Decimal256, AssetList)> {
+        let pool = self.query_pool(&deps.querier)?;
+        let (sqrt_price_current, sqrt_price_lower, sqrt_price_upper) = self.sqrt_prices(&pool)?;
+
+        let amount_of = |denom: &str| {
+            assets
+                .find(&AssetInfo::Native(denom.to_string()))
+                .map(|a| a.amount)
+                .unwrap_or_default()
+        };
+
+        let liquidity = liquidity_for_amounts(
+            sqrt_price_current,
+            sqrt_price_lower,
+            sqrt_price_upper,
+            to_decimal256(amount_of(&pool.token0)),
+            to_decimal256(amount_of(&pool.token1)),
+        )?;
+        let used = self.amounts_for_liquidity(&pool, liquidity)?;
+
+        Ok((liquidity, used))
+    }
+
+    /// Simulates the amounts of the pool's assets backing `liquidity` in the
+    /// tick range at the current price.
+    pub fn simulate_position_amounts(
+        &self,
+        deps: Deps,
+        liquidity: Decimal256,
+    ) -> StdResult<AssetList> {
+        let pool = self.query_pool(&deps.querier)?;
+        self.amounts_for_liquidity(&pool, liquidity)
+    }
+
+    fn amounts_for_liquidity(&self, pool: &ClPool, liquidity: Decimal256) -> StdResult<AssetList> {
+        let (sqrt_price_current, sqrt_price_lower, sqrt_price_upper) = self.sqrt_prices(pool)?;
+        let (amount0, amount1) = amounts_for_liquidity(
+            sqrt_price_current,
+            sqrt_price_lower,
+            sqrt_price_upper,
+            liquidity,
+        )?;
+
+        Ok(vec![
+            Coin::new(floor(amount0)?.u128(), &pool.token0),
+            Coin::new(floor(amount1)?.u128(), &pool.token1),
+        ]
+        .into())
+    }
+
+    /// Returns a `CwDexResponse` with the messages to collect the spread
+    /// rewards and incentives accrued by the position. See also
+    /// [`Rewards::claim_rewards`].
+    pub fn collect_rewards(&self, env: &Env) -> Result<CwDexResponse, CwDexError> {
+        let position_id = self.position_id_or_err()?;
+
+        let collect_spread_rewards = MsgCollectSpreadRewards {
+            position_ids: vec![position_id],
+            sender: env.contract.address.to_string(),
+        };
+        let collect_incentives = MsgCollectIncentives {
+            position_ids: vec![position_id],
+            sender: env.contract.address.to_string(),
+        };
+
+        let event = Event::new("apollo/cw-dex/claim_rewards")
+            .add_attribute("type", "osmosis_cl_pool")
+            .add_attribute("pool_id", self.pool_id.to_string())
+            .add_attribute("position_id", position_id.to_string());
+
+        Ok(CwDexResponse::new()
+            .add_message(collect_spread_rewards)
+            .add_message(collect_incentives)
+            .add_event(event))
+    }
+}
+
+impl Pool for OsmosisClPool {
+    fn provide_liquidity(
+        &self,
+        deps: Deps,
+        env: &Env,
+        assets: AssetList,
+        min_out: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        let (liquidity, used) = self.simulate_create_position(deps, &assets)?;
+        let expected_liquidity = floor(liquidity)?;
+
+        // Assert slippage tolerance
+        if min_out > expected_liquidity {
+            return Err(CwDexError::MinOutNotReceived {
+                min_out,
+                received: expected_liquidity,
+            });
+        }
+
+        // Scale the minimum amounts of each token by the ratio of min_out to
+        // the expected liquidity.
+        let min_amount = |asset: &Asset| -> StdResult<String> {
+            if expected_liquidity.is_zero() {
+                return Ok("0".to_string());
+            }
+            Ok(asset
+                .amount
+                .multiply_ratio(min_out, expected_liquidity)
+                .to_string())
+        };
+        let mut leftover = assets;
+        leftover.deduct_many(used.clone().purge())?;
+        let used = used.to_vec();
+
+        let mut tokens_provided = used
+            .iter()
+            .filter(|a| !a.amount.is_zero())
+            .map(|a| Ok(assert_native_coin(a)?.into()))
+            .collect::<StdResult<Vec<ProtoCoin>>>()?;
+        tokens_provided.sort_by(|a, b| a.denom.cmp(&b.denom));
+
+        let create_position = MsgCreatePosition {
+            pool_id: self.pool_id,
+            sender: env.contract.address.to_string(),
+            lower_tick: self.lower_tick,
+            upper_tick: self.upper_tick,
+            tokens_provided,
+            token_min_amount0: min_amount(&used[0])?,
+            token_min_amount1: min_amount(&used[1])?,
+        };
+
+        let event = Event::new("apollo/cw-dex/provide_liquidity")
+            .add_attribute("pool_id", self.pool_id.to_string())
+            .add_attribute("lower_tick", self.lower_tick.to_string())
+            .add_attribute("upper_tick", self.upper_tick.to_string())
+            .add_attribute("min_out", min_out)
+            .add_attribute("expected_liquidity", liquidity.to_string());
+
+        Ok(CwDexResponse::new()
+            .add_submessage(SubMsg {
+                id: self.reply_ids.create_position,
+                msg: create_position.into(),
+                gas_limit: None,
+                reply_on: ReplyOn::Success,
+            })
+            .add_event(event)
+            .with_expected_out(vec![Asset::new(self.lp_token(), expected_liquidity)])
+            .with_leftover(leftover))
+    }
+
+    fn withdraw_liquidity(
+        &self,
+        deps: Deps,
+        env: &Env,
+        lp_token: Asset,
+        min_out: AssetList,
+    ) -> Result<CwDexResponse, CwDexError> {
+        if lp_token.info != self.lp_token() {
+            return Err(CwDexError::InvalidLpToken {});
+        }
+        let position_id = self.position_id_or_err()?;
+
+        // MsgWithdrawPosition has no minimum amounts out, so they are asserted
+        // by a callback after the withdrawal. Return an error early if the
+        // simulation already falls short.
+        let liquidity = self.withdraw_liquidity_amount(&deps.querier, lp_token.amount)?;
+        let expected_out = self.simulate_position_amounts(deps, liquidity)?;
+        assert_min_out(&expected_out, &min_out)?;
+        let assert_min_out_msg = CwDexCallbackMsg::assert_min_out(deps, env, &min_out)?;
+
+        let withdraw_position = MsgWithdrawPosition {
+            position_id,
+            sender: env.contract.address.to_string(),
+            // sdk.Dec is encoded as an integer with 18 decimal places
+            liquidity_amount: liquidity.atomics().to_string(),
+        };
+
+        let event = Event::new("apollo/cw-dex/withdraw_liquidity")
+            .add_attribute("pool_id", self.pool_id.to_string())
+            .add_attribute("position_id", position_id.to_string())
+            .add_attribute("liquidity", liquidity.to_string());
+
+        Ok(CwDexResponse::new()
+            .add_message(withdraw_position)
+            .add_messages(assert_min_out_msg)
+            .add_event(event)
+            .with_expected_out(expected_out))
+    }
+
+    fn swap(
+        &self,
+        deps: Deps,
+        env: &Env,
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+        min_out: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        let offer = assert_native_coin(&offer_asset)?;
+        let ask_denom = assert_native_asset_info(&ask_asset_info)?;
+        let expected_out = self.simulate_swap(deps, offer_asset, ask_asset_info.clone())?;
+
+        // Min out must be greater than 0 for osmosis.
+        let min_out = min_out.max(Uint128::one());
+
+        let swap_msg = MsgSwapExactAmountIn {
+            sender: env.contract.address.to_string(),
+            routes: vec![SwapAmountInRoute {
+                pool_id: self.pool_id,
+                token_out_denom: ask_denom.clone(),
+            }],
+            token_in: Some(offer.clone().into()),
+            token_out_min_amount: min_out.to_string(),
+        };
+
+        let event = Event::new("apollo/cw-dex/swap")
+            .add_attribute("pool_id", self.pool_id.to_string())
+            .add_attribute("offer", offer.to_string())
+            .add_attribute("ask", ask_denom)
+            .add_attribute("token_out_min_amount", min_out);
+
+        Ok(CwDexResponse::new()
+            .add_message(swap_msg)
+            .add_event(event)
+            .with_expected_out(vec![Asset::new(ask_asset_info, expected_out)]))
+    }
+
+    fn swap_exact_out(
+        &self,
+        _deps: Deps,
+        env: &Env,
+        ask_asset: Asset,
+        offer_asset_info: AssetInfo,
+        max_in: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        let ask = assert_native_coin(&ask_asset)?;
+        let offer_denom = assert_native_asset_info(&offer_asset_info)?;
+
+        let swap_msg = MsgSwapExactAmountOut {
+            sender: env.contract.address.to_string(),
+            routes: vec![SwapAmountOutRoute {
+                pool_id: self.pool_id,
+                token_in_denom: offer_denom.clone(),
+            }],
+            token_in_max_amount: max_in.to_string(),
+            token_out: Some(ask.clone().into()),
+        };
+
+        let event = Event::new("apollo/cw-dex/swap_exact_out")
+            .add_attribute("pool_id", self.pool_id.to_string())
+            .add_attribute("ask", ask.to_string())
+            .add_attribute("offer", offer_denom)
+            .add_attribute("token_in_max_amount", max_in);
+
+        Ok(CwDexResponse::new()
+            .add_message(swap_msg)
+            .add_event(event)
+            .with_expected_out(vec![ask_asset]))
+    }
+
+    fn get_pool_liquidity(&self, deps: Deps) -> Result<AssetList, CwDexError> {
+        let liquidity = PoolmanagerQuerier::new(&deps.querier)
+            .total_pool_liquidity(self.pool_id)?
+            .liquidity;
+
+        Ok(liquidity
+            .into_iter()
+            .map(|coin| {
+                Ok(Asset {
+                    info: AssetInfo::Native(coin.denom),
+                    amount: Uint128::from_str(&coin.amount)?,
+                })
+            })
+            .collect::<StdResult<Vec<Asset>>>()?
+            .into())
+    }
+
+    fn simulate_provide_liquidity(
+        &self,
+        deps: Deps,
+        _env: &Env,
+        assets: AssetList,
+    ) -> Result<Asset, CwDexError> {
+        let (liquidity, _) = self.simulate_create_position(deps, &assets)?;
+        Ok(Asset::new(self.lp_token(), floor(liquidity)?))
+    }
+
+    fn simulate_withdraw_liquidity(
+        &self,
+        deps: Deps,
+        lp_token: &Asset,
+    ) -> Result<AssetList, CwDexError> {
+        if lp_token.info != self.lp_token() {
+            return Err(CwDexError::InvalidLpToken {});
+        }
+        let liquidity = self.withdraw_liquidity_amount(&deps.querier, lp_token.amount)?;
+        Ok(self.simulate_position_amounts(deps, liquidity)?)
+    }
+
+    fn simulate_swap(
+        &self,
+        deps: Deps,
+        offer: Asset,
+        ask_asset_info: AssetInfo,
+    ) -> StdResult<Uint128> {
+        let offer: Coin = offer.try_into()?;
+        let response = PoolmanagerQuerier::new(&deps.querier).estimate_swap_exact_amount_in(
+            self.pool_id,
+            offer.to_string(),
+            vec![SwapAmountInRoute {
+                pool_id: self.pool_id,
+                token_out_denom: assert_native_asset_info(&ask_asset_info)?,
+            }],
+        )?;
+        Uint128::from_str(&response.token_out_amount)
+    }
+
+    fn simulate_swap_detailed(
+        &self,
+        deps: Deps,
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+    ) -> StdResult<SwapSimulation> {
+        let return_amount =
+            self.simulate_swap(deps, offer_asset.clone(), ask_asset_info.clone())?;
+        let spot_price =
+            self.query_spot_price(&deps.querier, &offer_asset.info, &ask_asset_info)?;
+        let spread_factor = parse_osmosis_dec(&self.query_pool(&deps.querier)?.spread_factor)?;
+
+        Ok(swap_simulation_from_spot_price(
+            offer_asset.amount,
+            return_amount,
+            spot_price,
+            spread_factor,
+        ))
+    }
+
+    fn spot_price(
+        &self,
+        deps: Deps,
+        _env: &Env,
+        base_asset: &AssetInfo,
+        quote_asset: &AssetInfo,
+    ) -> StdResult<Decimal> {
+        self.query_spot_price(&deps.querier, base_asset, quote_asset)
+    }
+
+    fn pool_params(&self, deps: Deps, _env: &Env) -> Result<PoolParams, CwDexError> {
+        Ok(PoolParams {
+            pool_type: PoolType::ConcentratedLiquidity,
+            swap_fee: parse_osmosis_dec(&self.query_pool(&deps.querier)?.spread_factor)?,
+            exit_fee: Decimal::zero(),
+            weights: None,
+            amp: None,
+        })
+    }
+
+    fn simulate_swap_exact_out(
+        &self,
+        deps: Deps,
+        ask_asset: Asset,
+        offer_asset_info: AssetInfo,
+    ) -> StdResult<Uint128> {
+        let ask: Coin = ask_asset.try_into()?;
+        let response = PoolmanagerQuerier::new(&deps.querier).estimate_swap_exact_amount_out(
+            self.pool_id,
+            vec![SwapAmountOutRoute {
+                pool_id: self.pool_id,
+                token_in_denom: assert_native_asset_info(&offer_asset_info)?,
+            }],
+            ask.to_string(),
+        )?;
+        Uint128::from_str(&response.token_in_amount)
+    }
+
+    /// Concentrated liquidity positions have no LP token. This returns a
+    /// pseudo denom identifying the pool, whose amounts are the liquidity of
+    /// the position.
+    fn lp_token(&self) -> AssetInfo {
+        AssetInfo::Native(format!("cl/pool/{}", self.pool_id))
+    }
+}
+
+impl Rewards for OsmosisClPool {
+    fn claim_rewards(&self, _deps: Deps, env: &Env) -> Result<Response, CwDexError> {
+        Ok(self.collect_rewards(env)?.into_response())
+    }
+
+    /// Returns the spread rewards and incentives claimable by the position.
+    /// The position is owned by the contract that created it, so `user` is
+    /// ignored.
+    fn query_pending_rewards(
+        &self,
+        querier: &QuerierWrapper,
+        _user: &Addr,
+    ) -> Result<AssetList, CwDexError> {
+        let position = self.query_position(querier)?;
+
+        let mut rewards = AssetList::new();
+        for coin in position
+            .claimable_spread_rewards
+            .iter()
+            .chain(position.claimable_incentives.iter())
+        {
+            let amount = Uint128::from_str(&coin.amount)?;
+            if !amount.is_zero() {
+                rewards.add(&Asset::native(coin.denom.clone(), amount))?;
+            }
+        }
+        Ok(rewards)
+    }
+}
+
+impl PriceOracle for OsmosisClPool {
+    fn query_twap_price(
+        &self,
+        deps: Deps,
+        env: &Env,
+        base_asset_info: &AssetInfo,
+        quote_asset_info: &AssetInfo,
+        window: u64,
+        _snapshots: &[CumulativePricesSnapshot],
+    ) -> Result<Decimal, CwDexError> {
+        query_arithmetic_twap(
+            &deps.querier,
+            env,
+            self.pool_id,
+            base_asset_info,
+            quote_asset_info,
+            window,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use cosmwasm_std::testing::{mock_env, MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{
+        from_slice, Binary, ContractResult, CosmosMsg, Empty, OwnedDeps, Querier, QuerierResult,
+        QueryRequest, SystemResult,
+    };
+    use prost::Message;
+
+    use super::*;
+    use crate::implementations::osmosis::OsmosisPool;
+    use crate::implementations::pool::Pool as PoolEnum;
+
+    const POOL: &[u8] = br#"{"pool":{"token0":"uatom","token1":"uosmo","current_sqrt_price":"1.000000000000000000","current_tick":"0","spread_factor":"0.002000000000000000"}}"#;
+    const POSITION: &[u8] = br#"{"position":{"position":{"liquidity":"1000.500000000000000000"},"claimable_spread_rewards":[{"denom":"uatom","amount":"3"},{"denom":"uosmo","amount":"0"}],"claimable_incentives":[{"denom":"uosmo","amount":"2"}]}}"#;
+
+    /// Answers the poolmanager `Pool` query and the concentrated liquidity
+    /// `PositionById` query with a pool at price 1 and a position with a
+    /// fractional liquidity.
+    struct ClQuerier(MockQuerier);
+
+    impl Querier for ClQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            match from_slice::<QueryRequest<Empty>>(bin_request).unwrap() {
+                QueryRequest::Stargate { path, .. } => {
+                    let res = match path.as_str() {
+                        "/osmosis.poolmanager.v1beta1.Query/Pool" => POOL,
+                        "/osmosis.concentratedliquidity.v1beta1.Query/PositionById" => POSITION,
+                        _ => {
+                            return SystemResult::Ok(ContractResult::Err(
+                                "unexpected query".to_string(),
+                            ))
+                        }
+                    };
+                    SystemResult::Ok(ContractResult::Ok(Binary::from(res)))
+                }
+                _ => self.0.raw_query(bin_request),
+            }
+        }
+    }
+
+    fn mock_deps() -> OwnedDeps<MockStorage, MockApi, ClQuerier> {
+        OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: ClQuerier(MockQuerier::default()),
+            custom_query_type: PhantomData,
+        }
+    }
+
+    fn pool() -> OsmosisClPool {
+        OsmosisClPool::unchecked(1, -1000, 1000).with_position_id(7)
+    }
+
+    /// Returns the liquidity withdrawn by the `MsgWithdrawPosition` in
+    /// `response`
+    fn withdrawn_liquidity(response: &CwDexResponse) -> String {
+        match &response.messages[0].msg {
+            CosmosMsg::Stargate { type_url, value } => {
+                assert_eq!(type_url, MsgWithdrawPosition::TYPE_URL);
+                MsgWithdrawPosition::decode(value.as_slice())
+                    .unwrap()
+                    .liquidity_amount
+            }
+            _ => panic!("unexpected msg"),
+        }
+    }
+
+    #[test]
+    fn test_withdraw_liquidity_full_position() {
+        let deps = mock_deps();
+        let env = mock_env();
+        let pool = pool();
+
+        // Withdrawing the whole pseudo LP amount withdraws the exact liquidity
+        let response = pool
+            .withdraw_liquidity(
+                deps.as_ref(),
+                &env,
+                Asset::new(pool.lp_token(), 1000u128),
+                AssetList::new(),
+            )
+            .unwrap();
+        assert_eq!(withdrawn_liquidity(&response), "1000500000000000000000");
+        assert_eq!(
+            response.expected_out,
+            pool.simulate_position_amounts(deps.as_ref(), Decimal256::from_str("1000.5").unwrap())
+                .unwrap()
+        );
+
+        let response = pool
+            .withdraw_liquidity(
+                deps.as_ref(),
+                &env,
+                Asset::new(pool.lp_token(), 999u128),
+                AssetList::new(),
+            )
+            .unwrap();
+        assert_eq!(withdrawn_liquidity(&response), "999000000000000000000");
+    }
+
+    #[test]
+    fn test_rewards() {
+        let deps = mock_deps();
+        let env = mock_env();
+        let pool = PoolEnum::OsmosisCl(pool());
+
+        let rewards = pool.as_rewards().unwrap();
+        assert_eq!(
+            rewards
+                .query_pending_rewards(&deps.as_ref().querier, &env.contract.address)
+                .unwrap(),
+            vec![Asset::native("uatom", 3u128), Asset::native("uosmo", 2u128)].into()
+        );
+        let response = rewards.claim_rewards(deps.as_ref(), &env).unwrap();
+        assert_eq!(response.messages.len(), 2);
+
+        let err = PoolEnum::Osmosis(OsmosisPool::unchecked(1))
+            .as_rewards()
+            .unwrap_err();
+        assert!(matches!(err, CwDexError::UnsupportedPoolOperation { .. }));
+    }
+
+    #[test]
+    fn test_with_position_id() {
+        let pool = OsmosisClPool::unchecked(1, -100, 100);
+        assert_eq!(pool.position_id(), None);
+
+        let pool = pool.with_position_id(7);
+        assert_eq!(pool.position_id(), Some(7));
+        assert_eq!(pool.lower_tick(), -100);
+        assert_eq!(pool.upper_tick(), 100);
+    }
+}
@@ -1,5 +1,29 @@
+use std::str::FromStr;
 use std::time::Duration;
 
+use apollo_utils::assets::assert_native_asset_info;
+use cosmwasm_std::{
+    Addr, Coin, Decimal, Decimal256, Env, QuerierWrapper, StdError, StdResult, Timestamp, Uint128,
+    Uint256,
+};
+use cw_asset::AssetInfo;
+use cw_utils::Expiration;
+use osmosis_std::types::osmosis::gamm::poolmodels::stableswap::v1beta1::Pool as StableSwapPool;
+use osmosis_std::types::osmosis::gamm::v1beta1::{GammQuerier, Pool as BalancerPool};
+use osmosis_std::types::osmosis::lockup::LockupQuerier;
+use osmosis_std::types::osmosis::twap::v1beta1::TwapQuerier;
+use prost::Message;
+
+use super::math::{
+    balancer_calc_in_amt_given_out, balancer_calc_out_amt_given_in, balancer_fair_value,
+    stableswap_calc_in_amt_given_out, stableswap_calc_out_amt_given_in, stableswap_fair_value,
+    ScaledReserves,
+};
+use super::pool::OsmosisPoolType;
+use crate::implementations::valuation::{lp_price_from_value, reference_price};
+use crate::traits::{PoolParams, PoolType, SwapSimulation, UnbondingPosition};
+use crate::CwDexError;
+
 pub(crate) trait ToProtobufDuration {
     fn to_protobuf_duration(&self) -> osmosis_std::shim::Duration;
 }
@@ -12,3 +36,508 @@ impl ToProtobufDuration for Duration {
         }
     }
 }
+
+const BALANCER_POOL_TYPE_URL: &str = "/osmosis.gamm.v1beta1.Pool";
+const STABLESWAP_POOL_TYPE_URL: &str = "/osmosis.gamm.poolmodels.stableswap.v1beta1.Pool";
+
+/// The on-chain state of an Osmosis gamm pool, decoded from the `Any` returned
+/// by the gamm `Pool` query.
+pub(crate) enum OsmosisPoolState {
+    Balancer(BalancerPool),
+    StableSwap(StableSwapPool),
+}
+
+impl OsmosisPoolState {
+    /// Queries the gamm module for the pool with id `pool_id`.
+    pub(crate) fn query(querier: &QuerierWrapper, pool_id: u64) -> StdResult<Self> {
+        let pool = GammQuerier::new(querier)
+            .pool(pool_id)?
+            .pool
+            .ok_or_else(|| StdError::generic_err(format!("osmosis pool {} not found", pool_id)))?;
+
+        match pool.type_url.as_str() {
+            BALANCER_POOL_TYPE_URL => Ok(Self::Balancer(decode(&pool.value)?)),
+            STABLESWAP_POOL_TYPE_URL => Ok(Self::StableSwap(decode(&pool.value)?)),
+            type_url => Err(StdError::generic_err(format!(
+                "unsupported osmosis pool type: {}",
+                type_url
+            ))),
+        }
+    }
+
+    /// Returns the type of the pool
+    pub(crate) fn pool_type(&self) -> OsmosisPoolType {
+        match self {
+            Self::Balancer(_) => OsmosisPoolType::Balancer,
+            Self::StableSwap(_) => OsmosisPoolType::StableSwap,
+        }
+    }
+
+    /// Returns the swap fee of the pool
+    pub(crate) fn swap_fee(&self) -> StdResult<Decimal> {
+        let swap_fee = match self {
+            Self::Balancer(pool) => pool.pool_params.as_ref().map(|p| p.swap_fee.as_str()),
+            Self::StableSwap(pool) => pool.pool_params.as_ref().map(|p| p.swap_fee.as_str()),
+        }
+        .ok_or_else(|| StdError::generic_err("osmosis pool params not set"))?;
+
+        parse_osmosis_dec(swap_fee)
+    }
+
+    /// Returns the type, fees and weights of the pool
+    pub(crate) fn pool_params(&self) -> StdResult<PoolParams> {
+        let exit_fee = match self {
+            Self::Balancer(pool) => pool.pool_params.as_ref().map(|p| p.exit_fee.as_str()),
+            Self::StableSwap(pool) => pool.pool_params.as_ref().map(|p| p.exit_fee.as_str()),
+        }
+        .ok_or_else(|| StdError::generic_err("osmosis pool params not set"))?;
+
+        let (pool_type, weights) = match self {
+            Self::Balancer(pool) => {
+                let total_weight = Uint128::from_str(&pool.total_weight)?;
+                let weights = pool
+                    .pool_assets
+                    .iter()
+                    .filter_map(|pool_asset| {
+                        pool_asset
+                            .token
+                            .as_ref()
+                            .map(|token| (token.denom.as_str(), pool_asset.weight.as_str()))
+                    })
+                    .map(|(denom, weight)| {
+                        Ok((
+                            AssetInfo::native(denom),
+                            Decimal::checked_from_ratio(Uint128::from_str(weight)?, total_weight)
+                                .map_err(|e| StdError::generic_err(e.to_string()))?,
+                        ))
+                    })
+                    .collect::<StdResult<Vec<_>>>()?;
+                (PoolType::Weighted, Some(weights))
+            }
+            Self::StableSwap(_) => (PoolType::StableSwap, None),
+        };
+
+        Ok(PoolParams {
+            pool_type,
+            swap_fee: self.swap_fee()?,
+            exit_fee: parse_osmosis_dec(exit_fee)?,
+            weights,
+            amp: None,
+        })
+    }
+
+    /// Returns the fair price of one LP token of the pool at the reference
+    /// `prices` of the pool assets.
+    pub(crate) fn fair_lp_token_price(
+        &self,
+        prices: &[(AssetInfo, Decimal)],
+    ) -> Result<Decimal, CwDexError> {
+        let price_of = |denom: &str| reference_price(prices, &AssetInfo::native(denom));
+        let (value, total_shares) = match self {
+            Self::Balancer(pool) => {
+                let assets = pool
+                    .pool_assets
+                    .iter()
+                    .filter_map(|pool_asset| pool_asset.token.as_ref())
+                    .map(|token| {
+                        let (balance, weight) = balancer_pool_asset(pool, &token.denom)?;
+                        let value = Decimal256::from_ratio(balance, 1u128)
+                            .checked_mul(price_of(&token.denom)?.into())?;
+                        Ok((value, weight))
+                    })
+                    .collect::<Result<Vec<_>, CwDexError>>()?;
+                (balancer_fair_value(&assets)?, pool.total_shares.as_ref())
+            }
+            Self::StableSwap(pool) => {
+                if pool.pool_liquidity.len() != pool.scaling_factors.len() {
+                    return Err(StdError::generic_err(
+                        "osmosis stableswap pool liquidity and scaling factors length mismatch",
+                    )
+                    .into());
+                }
+                let reserves = pool
+                    .pool_liquidity
+                    .iter()
+                    .zip(&pool.scaling_factors)
+                    .map(|(coin, scaling_factor)| {
+                        Ok((
+                            Uint128::from_str(&coin.amount)?,
+                            *scaling_factor,
+                            price_of(&coin.denom)?,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, CwDexError>>()?;
+                (
+                    stableswap_fair_value(&reserves)?,
+                    pool.total_shares.as_ref(),
+                )
+            }
+        };
+
+        let total_shares = total_shares
+            .map(|coin| Uint128::from_str(&coin.amount))
+            .transpose()?
+            .unwrap_or_default();
+        Ok(lp_price_from_value(value, total_shares)?)
+    }
+
+    /// Returns the amount of `token_out_denom` received for swapping
+    /// `token_in` into the pool.
+    pub(crate) fn calc_out_amt_given_in(
+        &self,
+        token_in: &Coin,
+        token_out_denom: &str,
+    ) -> StdResult<Uint128> {
+        let swap_fee = self.swap_fee()?;
+        match self {
+            Self::Balancer(pool) => balancer_calc_out_amt_given_in(
+                balancer_pool_asset(pool, &token_in.denom)?,
+                balancer_pool_asset(pool, token_out_denom)?,
+                token_in.amount,
+                swap_fee,
+            ),
+            Self::StableSwap(pool) => stableswap_calc_out_amt_given_in(
+                &stableswap_scaled_reserves(pool, &token_in.denom, token_out_denom)?,
+                token_in.amount,
+                swap_fee,
+            ),
+        }
+    }
+
+    /// Returns the amount of `token_in_denom` that must be swapped into the
+    /// pool to receive `token_out`.
+    pub(crate) fn calc_in_amt_given_out(
+        &self,
+        token_out: &Coin,
+        token_in_denom: &str,
+    ) -> StdResult<Uint128> {
+        let swap_fee = self.swap_fee()?;
+        match self {
+            Self::Balancer(pool) => balancer_calc_in_amt_given_out(
+                balancer_pool_asset(pool, token_in_denom)?,
+                balancer_pool_asset(pool, &token_out.denom)?,
+                token_out.amount,
+                swap_fee,
+            ),
+            Self::StableSwap(pool) => stableswap_calc_in_amt_given_out(
+                &stableswap_scaled_reserves(pool, token_in_denom, &token_out.denom)?,
+                token_out.amount,
+                swap_fee,
+            ),
+        }
+    }
+}
+
+/// Returns the balance and weight of `denom` in a balancer pool.
+fn balancer_pool_asset(pool: &BalancerPool, denom: &str) -> StdResult<(Uint128, Uint128)> {
+    let pool_asset = pool
+        .pool_assets
+        .iter()
+        .find(|a| a.token.as_ref().map(|t| t.denom.as_str()) == Some(denom))
+        .ok_or_else(|| StdError::generic_err(format!("denom {} not in osmosis pool", denom)))?;
+    let balance = pool_asset
+        .token
+        .as_ref()
+        .map(|t| t.amount.as_str())
+        .unwrap_or_default();
+
+    Ok((
+        Uint128::from_str(balance)?,
+        Uint128::from_str(&pool_asset.weight)?,
+    ))
+}
+
+/// Returns the reserves and scaling factors of a stableswap pool, with the
+/// reserves of `token_in_denom` and `token_out_denom` first.
+fn stableswap_scaled_reserves(
+    pool: &StableSwapPool,
+    token_in_denom: &str,
+    token_out_denom: &str,
+) -> StdResult<ScaledReserves> {
+    if pool.pool_liquidity.len() != pool.scaling_factors.len() {
+        return Err(StdError::generic_err(
+            "osmosis stableswap pool liquidity and scaling factors length mismatch",
+        ));
+    }
+
+    let mut token_in = None;
+    let mut token_out = None;
+    let mut remaining = vec![];
+    for (coin, scaling_factor) in pool.pool_liquidity.iter().zip(&pool.scaling_factors) {
+        let reserve = (Uint128::from_str(&coin.amount)?, *scaling_factor);
+        if coin.denom == token_in_denom {
+            token_in = Some(reserve);
+        } else if coin.denom == token_out_denom {
+            token_out = Some(reserve);
+        } else {
+            remaining.push(reserve);
+        }
+    }
+
+    let not_found =
+        |denom: &str| StdError::generic_err(format!("denom {} not in osmosis pool", denom));
+    Ok(ScaledReserves {
+        token_in: token_in.ok_or_else(|| not_found(token_in_denom))?,
+        token_out: token_out.ok_or_else(|| not_found(token_out_denom))?,
+        remaining,
+    })
+}
+
+fn decode<T: Message + Default>(value: &[u8]) -> StdResult<T> {
+    T::decode(value).map_err(|e| StdError::parse_err(std::any::type_name::<T>(), e))
+}
+
+/// Parses an `sdk.Dec` string. Depending on whether it was decoded from JSON
+/// or from protobuf, an `sdk.Dec` is either represented with a decimal point
+/// or as an integer with 18 implicit decimal places.
+pub(crate) fn parse_osmosis_dec(value: &str) -> StdResult<Decimal> {
+    if value.contains('.') {
+        Decimal::from_str(value)
+    } else {
+        Decimal::from_atomics(Uint128::from_str(value)?, 18)
+            .map_err(|e| StdError::generic_err(e.to_string()))
+    }
+}
+
+/// Parses an `sdk.Dec` or `osmomath.BigDec` string into a `Decimal256`.
+/// Decimal strings with more than 18 decimal places, as used by `BigDec`, are
+/// truncated to 18 decimal places.
+#[cfg(feature = "osmosis-cl")]
+pub(crate) fn parse_osmosis_dec256(value: &str) -> StdResult<Decimal256> {
+    match value.split_once('.') {
+        Some((integer, fractional)) if fractional.len() > 18 => {
+            Decimal256::from_str(&format!("{}.{}", integer, &fractional[..18]))
+        }
+        Some(_) => Decimal256::from_str(value),
+        None => Decimal256::from_atomics(Uint256::from_str(value)?, 18)
+            .map_err(|e| StdError::generic_err(e.to_string())),
+    }
+}
+
+/// Returns the simulation of a swap of `offer_amount` that returns
+/// `return_amount`. Osmosis does not return the spread and commission of a
+/// swap, so we derive them from the amount that would be returned at
+/// `spot_price`, of which `swap_fee` is taken as commission.
+pub(crate) fn swap_simulation_from_spot_price(
+    offer_amount: Uint128,
+    return_amount: Uint128,
+    spot_price: Decimal,
+    swap_fee: Decimal,
+) -> SwapSimulation {
+    let spot_amount = offer_amount * spot_price;
+    let commission_amount = spot_amount * swap_fee;
+    let spread_amount = spot_amount
+        .saturating_sub(commission_amount)
+        .saturating_sub(return_amount);
+
+    SwapSimulation {
+        return_amount,
+        spread_amount,
+        commission_amount,
+    }
+}
+
+/// Queries the twap module for the arithmetic time weighted average price of
+/// `base_asset_info` in terms of `quote_asset_info` in pool `pool_id` over the
+/// last `window` seconds.
+pub(crate) fn query_arithmetic_twap(
+    querier: &QuerierWrapper,
+    env: &Env,
+    pool_id: u64,
+    base_asset_info: &AssetInfo,
+    quote_asset_info: &AssetInfo,
+    window: u64,
+) -> Result<Decimal, CwDexError> {
+    if window == 0 {
+        return Err(CwDexError::TwapUnavailable {
+            reason: "window must be greater than zero".to_string(),
+        });
+    }
+
+    let start_time = env.block.time.minus_seconds(window);
+    let response = TwapQuerier::new(querier).arithmetic_twap_to_now(
+        pool_id,
+        assert_native_asset_info(base_asset_info)?,
+        assert_native_asset_info(quote_asset_info)?,
+        Some(osmosis_std::shim::Timestamp {
+            seconds: start_time.seconds() as i64,
+            nanos: start_time.subsec_nanos() as i32,
+        }),
+    )?;
+
+    Ok(parse_osmosis_dec(&response.arithmetic_twap)?)
+}
+
+/// The lockup state of an account for a given denom and lockup duration.
+pub(crate) struct LockupState {
+    /// The amount that is locked and not unlocking
+    pub locked: Uint128,
+    /// The locks that are unlocking
+    pub unlocking: Vec<UnbondingPosition>,
+}
+
+impl LockupState {
+    /// Queries the locks of `owner` with exactly `duration` for the amounts of
+    /// `denom` that are locked and unlocking.
+    pub fn query(
+        querier: &QuerierWrapper,
+        owner: &Addr,
+        denom: &str,
+        duration: Duration,
+    ) -> StdResult<Self> {
+        let duration = duration.to_protobuf_duration();
+        let locks = LockupQuerier::new(querier)
+            .account_locked_longer_duration(owner.to_string(), Some(duration.clone()))?
+            .locks;
+
+        let mut state = Self {
+            locked: Uint128::zero(),
+            unlocking: vec![],
+        };
+        for lock in locks {
+            if lock.duration.as_ref() != Some(&duration) {
+                continue;
+            }
+            let amount = lock
+                .coins
+                .iter()
+                .filter(|coin| coin.denom == denom)
+                .try_fold(Uint128::zero(), |sum, coin| {
+                    sum.checked_add(Uint128::from_str(&coin.amount)?)
+                        .map_err(StdError::from)
+                })?;
+            if amount.is_zero() {
+                continue;
+            }
+
+            // Locks that have not started unlocking have no end time
+            match lock.end_time.filter(|end_time| end_time.seconds > 0) {
+                Some(end_time) => state.unlocking.push(UnbondingPosition {
+                    id: Some(lock.id),
+                    amount,
+                    release_at: Expiration::AtTime(
+                        Timestamp::from_seconds(end_time.seconds as u64)
+                            .plus_nanos(end_time.nanos as u64),
+                    ),
+                }),
+                None => state.locked = state.locked.checked_add(amount)?,
+            }
+        }
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{
+        from_slice, to_binary, ContractResult, Empty, OwnedDeps, Querier, QuerierResult,
+        QueryRequest, SystemResult,
+    };
+    use osmosis_std::shim::Timestamp as ProtobufTimestamp;
+    use osmosis_std::types::cosmos::base::v1beta1::Coin as ProtobufCoin;
+    use osmosis_std::types::osmosis::lockup::{AccountLockedLongerDurationResponse, PeriodLock};
+
+    use super::*;
+
+    const TWO_WEEKS: Duration = Duration::from_secs(1_209_600);
+
+    fn lock(
+        id: u64,
+        duration: Duration,
+        end_time: Option<ProtobufTimestamp>,
+        denom: &str,
+        amount: u128,
+    ) -> PeriodLock {
+        PeriodLock {
+            id,
+            owner: "owner".to_string(),
+            duration: Some(duration.to_protobuf_duration()),
+            end_time,
+            coins: vec![ProtobufCoin {
+                denom: denom.to_string(),
+                amount: amount.to_string(),
+            }],
+        }
+    }
+
+    /// Answers the lockup `AccountLockedLongerDuration` query with locks of
+    /// different durations, denoms and unlocking states.
+    struct MockLockupQuerier(MockQuerier);
+
+    impl Querier for MockLockupQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            match from_slice::<QueryRequest<Empty>>(bin_request).unwrap() {
+                QueryRequest::Stargate { path, .. }
+                    if path == "/osmosis.lockup.Query/AccountLockedLongerDuration" =>
+                {
+                    let locks = vec![
+                        lock(1, TWO_WEEKS, None, "gamm/pool/1", 100),
+                        // Osmosis returns the zero time for locks that have not
+                        // started unlocking
+                        lock(
+                            2,
+                            TWO_WEEKS,
+                            Some(ProtobufTimestamp {
+                                seconds: -62_135_596_800,
+                                nanos: 0,
+                            }),
+                            "gamm/pool/1",
+                            50,
+                        ),
+                        lock(
+                            3,
+                            TWO_WEEKS,
+                            Some(ProtobufTimestamp {
+                                seconds: 1_700_000_000,
+                                nanos: 1,
+                            }),
+                            "gamm/pool/1",
+                            30,
+                        ),
+                        // A longer lock
+                        lock(4, TWO_WEEKS * 2, None, "gamm/pool/1", 1_000),
+                        // A lock of another denom
+                        lock(5, TWO_WEEKS, None, "gamm/pool/2", 1_000),
+                    ];
+                    SystemResult::Ok(ContractResult::Ok(
+                        to_binary(&AccountLockedLongerDurationResponse { locks }).unwrap(),
+                    ))
+                }
+                _ => self.0.raw_query(bin_request),
+            }
+        }
+    }
+
+    #[test]
+    fn test_lockup_state_query() {
+        let deps: OwnedDeps<_, _, _, Empty> = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: MockLockupQuerier(MockQuerier::default()),
+            custom_query_type: PhantomData,
+        };
+
+        let state = LockupState::query(
+            &deps.as_ref().querier,
+            &Addr::unchecked("owner"),
+            "gamm/pool/1",
+            TWO_WEEKS,
+        )
+        .unwrap();
+
+        // Only locks with exactly the duration count, split by whether they
+        // are unlocking
+        assert_eq!(state.locked, Uint128::new(150));
+        assert_eq!(
+            state.unlocking,
+            vec![UnbondingPosition {
+                id: Some(3),
+                amount: Uint128::new(30),
+                release_at: Expiration::AtTime(Timestamp::from_nanos(1_700_000_000_000_000_001)),
+            }]
+        );
+    }
+}
@@ -0,0 +1,545 @@
+//! Swap math for Osmosis balancer and stableswap pools.
+//!
+//! Ported from the Osmosis gamm module:
+//! - <https://github.com/osmosis-labs/osmosis/blob/v13.1.2/x/gamm/pool-models/balancer/amm.go>
+//! - <https://github.com/osmosis-labs/osmosis/blob/v13.1.2/x/gamm/pool-models/stableswap/amm.go>
+//! - <https://github.com/osmosis-labs/osmosis/blob/v13.1.2/osmomath/math.go>
+
+use cosmwasm_std::{Decimal, Decimal256, StdError, StdResult, Uint128};
+
+/// Precision of the power approximation. Osmosis uses 10^-8.
+const POW_PRECISION: Decimal256 = Decimal256::raw(10_000_000_000);
+
+/// Maximum number of iterations of the stableswap binary search.
+const MAX_ITERATIONS: u32 = 256;
+
+pub(crate) fn to_decimal256(value: Uint128) -> Decimal256 {
+    Decimal256::from_ratio(value, 1u128)
+}
+
+/// Returns the integer part of `value`.
+pub(crate) fn floor(value: Decimal256) -> StdResult<Uint128> {
+    let floor = value.atomics() / Decimal256::one().atomics();
+    Ok(Uint128::try_from(floor)?)
+}
+
+/// Returns `value` rounded up to the nearest integer.
+pub(crate) fn ceil(value: Decimal256) -> StdResult<Uint128> {
+    let floor = floor(value)?;
+    if to_decimal256(floor) == value {
+        Ok(floor)
+    } else {
+        Ok(floor.checked_add(Uint128::one())?)
+    }
+}
+
+fn checked_div(a: Decimal256, b: Decimal256) -> StdResult<Decimal256> {
+    a.checked_div(b)
+        .map_err(|e| StdError::generic_err(e.to_string()))
+}
+
+/// Returns `|a - b|` and whether `a < b`.
+fn abs_difference_with_sign(a: Decimal256, b: Decimal256) -> (Decimal256, bool) {
+    if a >= b {
+        (a - b, false)
+    } else {
+        (b - a, true)
+    }
+}
+
+/// Computes `base^exp` by splitting the exponent into an integer and a
+/// fractional part. `base` must be in the range (0, 2).
+pub(crate) fn pow(base: Decimal256, exp: Decimal256) -> StdResult<Decimal256> {
+    if base.is_zero() || base >= Decimal256::percent(200) {
+        return Err(StdError::generic_err(
+            "osmosis math: base must be greater than zero and less than two",
+        ));
+    }
+
+    let integer = floor(exp)?;
+    let fractional = exp - to_decimal256(integer);
+
+    let integer_pow = base.checked_pow(
+        u32::try_from(integer.u128())
+            .map_err(|_| StdError::generic_err("osmosis math: exponent too large"))?,
+    )?;
+    if fractional.is_zero() {
+        return Ok(integer_pow);
+    }
+
+    let fractional_pow = pow_approx(base, fractional, POW_PRECISION)?;
+    Ok(integer_pow.checked_mul(fractional_pow)?)
+}
+
+/// Approximates `base^exp` for `exp` in the range [0, 1) using the binomial
+/// series of `(1 + x)^exp` where `x = base - 1`.
+fn pow_approx(base: Decimal256, exp: Decimal256, precision: Decimal256) -> StdResult<Decimal256> {
+    if exp.is_zero() {
+        return Ok(Decimal256::one());
+    }
+    if exp == Decimal256::percent(50) {
+        return Ok(base.sqrt());
+    }
+
+    let (x, x_neg) = abs_difference_with_sign(base, Decimal256::one());
+    let mut term = Decimal256::one();
+    let mut sum = Decimal256::one();
+    let mut negative = false;
+
+    let mut i = 1u128;
+    while term >= precision {
+        let (c, c_neg) = abs_difference_with_sign(exp, Decimal256::from_ratio(i - 1, 1u128));
+        term = checked_div(
+            term.checked_mul(c)?.checked_mul(x)?,
+            Decimal256::from_ratio(i, 1u128),
+        )?;
+        if term.is_zero() {
+            break;
+        }
+        if x_neg {
+            negative = !negative;
+        }
+        if c_neg {
+            negative = !negative;
+        }
+        sum = if negative {
+            sum.checked_sub(term)?
+        } else {
+            sum.checked_add(term)?
+        };
+        i += 1;
+    }
+
+    Ok(sum)
+}
+
+/// Solves the balancer invariant for the change in balance of the unknown
+/// asset given the change in balance of the fixed asset:
+/// `balance_unknown_before * |1 - (balance_fixed_before /
+/// balance_fixed_after)^(weight_fixed / weight_unknown)|`
+fn solve_constant_function_invariant(
+    balance_fixed_before: Decimal256,
+    balance_fixed_after: Decimal256,
+    weight_ratio: Decimal256,
+    balance_unknown_before: Decimal256,
+) -> StdResult<Decimal256> {
+    let y = checked_div(balance_fixed_before, balance_fixed_after)?;
+    let y_to_weight_ratio = pow(y, weight_ratio)?;
+    let (paranthetical, _) = abs_difference_with_sign(Decimal256::one(), y_to_weight_ratio);
+    Ok(balance_unknown_before.checked_mul(paranthetical)?)
+}
+
+/// Returns the amount of tokens out of a balancer pool for swapping
+/// `amount_in` tokens in.
+pub(crate) fn balancer_calc_out_amt_given_in(
+    (balance_in, weight_in): (Uint128, Uint128),
+    (balance_out, weight_out): (Uint128, Uint128),
+    amount_in: Uint128,
+    swap_fee: Decimal,
+) -> StdResult<Uint128> {
+    let amount_in_after_fee =
+        to_decimal256(amount_in).checked_mul(Decimal256::one() - Decimal256::from(swap_fee))?;
+    let balance_in = to_decimal256(balance_in);
+
+    let amount_out = solve_constant_function_invariant(
+        balance_in,
+        balance_in.checked_add(amount_in_after_fee)?,
+        Decimal256::from_ratio(weight_in, weight_out),
+        to_decimal256(balance_out),
+    )?;
+
+    let amount_out = floor(amount_out)?;
+    if amount_out.is_zero() {
+        return Err(StdError::generic_err(
+            "osmosis math: token amount must be positive",
+        ));
+    }
+    Ok(amount_out)
+}
+
+/// Returns the amount of tokens that must be swapped into a balancer pool to
+/// receive `amount_out` tokens out.
+pub(crate) fn balancer_calc_in_amt_given_out(
+    (balance_in, weight_in): (Uint128, Uint128),
+    (balance_out, weight_out): (Uint128, Uint128),
+    amount_out: Uint128,
+    swap_fee: Decimal,
+) -> StdResult<Uint128> {
+    let balance_out = to_decimal256(balance_out);
+
+    let amount_in = solve_constant_function_invariant(
+        balance_out,
+        balance_out.checked_sub(to_decimal256(amount_out))?,
+        Decimal256::from_ratio(weight_out, weight_in),
+        to_decimal256(balance_in),
+    )?;
+
+    // The swap fee is charged on the input asset, so divide by (1 - swap fee)
+    // to get the amount before fees.
+    let amount_in_before_fee =
+        checked_div(amount_in, Decimal256::one() - Decimal256::from(swap_fee))?;
+
+    ceil(amount_in_before_fee)
+}
+
+/// The stableswap CFMM `xy(x^2 + y^2 + w)`, where `w` is the sum of squares
+/// of the reserves of all other assets in the pool.
+fn cfmm_constant(x: Decimal256, y: Decimal256, w: Decimal256) -> StdResult<Decimal256> {
+    let x_squared = x.checked_mul(x)?;
+    let y_squared = y.checked_mul(y)?;
+    Ok(x.checked_mul(y)?
+        .checked_mul(x_squared.checked_add(y_squared)?.checked_add(w)?)?)
+}
+
+/// Binary search for the smallest `x` in `[low, high]` such that
+/// `cfmm_constant(x, y, w) >= k`.
+fn solve_cfmm_x(
+    k: Decimal256,
+    y: Decimal256,
+    w: Decimal256,
+    mut low: Decimal256,
+    mut high: Decimal256,
+) -> StdResult<Decimal256> {
+    let two = Decimal256::percent(200);
+    for _ in 0..MAX_ITERATIONS {
+        if high <= low {
+            break;
+        }
+        let mid = checked_div(low.checked_add(high)?, two)?;
+        if mid == low {
+            break;
+        }
+        if cfmm_constant(mid, y, w)? >= k {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    Ok(high)
+}
+
+/// The reserves of a stableswap pool along with their scaling factors.
+pub(crate) struct ScaledReserves {
+    /// Reserve and scaling factor of the asset swapped in
+    pub token_in: (Uint128, u64),
+    /// Reserve and scaling factor of the asset swapped out
+    pub token_out: (Uint128, u64),
+    /// Reserves and scaling factors of all other assets in the pool
+    pub remaining: Vec<(Uint128, u64)>,
+}
+
+impl ScaledReserves {
+    /// Scales all reserves down by their scaling factors and normalizes them
+    /// by the largest reserve to avoid overflows in the CFMM. Since the CFMM
+    /// is homogeneous, the normalization does not change the result.
+    fn normalized(&self) -> StdResult<(Decimal256, Decimal256, Decimal256, Decimal256)> {
+        let scale = |(amount, scaling_factor): (Uint128, u64)| {
+            Decimal256::from_ratio(amount, scaling_factor)
+        };
+        let x_in = scale(self.token_in);
+        let y_out = scale(self.token_out);
+        let remaining = self.remaining.iter().map(|r| scale(*r)).collect::<Vec<_>>();
+
+        let norm = remaining.iter().fold(x_in.max(y_out), |acc, r| acc.max(*r));
+        if norm.is_zero() {
+            return Err(StdError::generic_err("osmosis math: pool has no liquidity"));
+        }
+
+        let w = remaining
+            .into_iter()
+            .try_fold(Decimal256::zero(), |acc, r| {
+                let r = checked_div(r, norm)?;
+                Ok::<_, StdError>(acc.checked_add(r.checked_mul(r)?)?)
+            })?;
+
+        Ok((checked_div(x_in, norm)?, checked_div(y_out, norm)?, w, norm))
+    }
+}
+
+/// Returns the amount of tokens out of a stableswap pool for swapping
+/// `amount_in` tokens in.
+pub(crate) fn stableswap_calc_out_amt_given_in(
+    reserves: &ScaledReserves,
+    amount_in: Uint128,
+    swap_fee: Decimal,
+) -> StdResult<Uint128> {
+    let (reserve_in, reserve_out, w, norm) = reserves.normalized()?;
+    let (_, scaling_factor_in) = reserves.token_in;
+    let (_, scaling_factor_out) = reserves.token_out;
+
+    let amount_in = Decimal256::from_ratio(amount_in, scaling_factor_in)
+        .checked_mul(Decimal256::one() - Decimal256::from(swap_fee))?;
+    let amount_in = checked_div(amount_in, norm)?;
+
+    // Solve for the new reserve of the out asset, keeping the CFMM constant.
+    let k = cfmm_constant(reserve_out, reserve_in, w)?;
+    let reserve_in_after = reserve_in.checked_add(amount_in)?;
+    let reserve_out_after = solve_cfmm_x(k, reserve_in_after, w, Decimal256::zero(), reserve_out)?;
+
+    let amount_out = reserve_out
+        .checked_sub(reserve_out_after)?
+        .checked_mul(norm)?
+        .checked_mul(Decimal256::from_ratio(scaling_factor_out, 1u128))?;
+
+    let amount_out = floor(amount_out)?;
+    if amount_out.is_zero() {
+        return Err(StdError::generic_err(
+            "osmosis math: token amount must be positive",
+        ));
+    }
+    Ok(amount_out)
+}
+
+/// Returns the amount of tokens that must be swapped into a stableswap pool
+/// to receive `amount_out` tokens out.
+pub(crate) fn stableswap_calc_in_amt_given_out(
+    reserves: &ScaledReserves,
+    amount_out: Uint128,
+    swap_fee: Decimal,
+) -> StdResult<Uint128> {
+    let (reserve_in, reserve_out, w, norm) = reserves.normalized()?;
+    let (_, scaling_factor_in) = reserves.token_in;
+    let (_, scaling_factor_out) = reserves.token_out;
+
+    let amount_out = checked_div(Decimal256::from_ratio(amount_out, scaling_factor_out), norm)?;
+    if amount_out >= reserve_out {
+        return Err(StdError::generic_err(
+            "osmosis math: cannot swap out more than the pool reserves",
+        ));
+    }
+
+    // Solve for the new reserve of the in asset, keeping the CFMM constant.
+    let k = cfmm_constant(reserve_in, reserve_out, w)?;
+    let reserve_out_after = reserve_out.checked_sub(amount_out)?;
+    let mut high = reserve_in.checked_add(reserve_in)?;
+    while cfmm_constant(high, reserve_out_after, w)? < k {
+        high = high.checked_add(high)?;
+    }
+    let reserve_in_after = solve_cfmm_x(k, reserve_out_after, w, reserve_in, high)?;
+
+    let amount_in = reserve_in_after
+        .checked_sub(reserve_in)?
+        .checked_mul(norm)?
+        .checked_mul(Decimal256::from_ratio(scaling_factor_in, 1u128))?;
+
+    // The swap fee is charged on the input asset, so divide by (1 - swap fee)
+    // to get the amount before fees.
+    let amount_in = checked_div(amount_in, Decimal256::one() - Decimal256::from(swap_fee))?;
+
+    ceil(amount_in)
+}
+
+/// Returns the value of a balancer pool at reference prices, given the value
+/// of each reserve at its reference price and its weight.
+///
+/// Arbitrage moves the reserves to where the value of each reserve is its
+/// weight's share of the value `V` of the pool. Keeping the invariant
+/// `prod(x_i^w_i)` with normalized weights `w_i` constant gives
+/// `V = prod((p_i * x_i / w_i)^w_i)`. The factors are divided by the largest
+/// one so that they are valid bases for [`pow`].
+pub(crate) fn balancer_fair_value(assets: &[(Decimal256, Uint128)]) -> StdResult<Decimal256> {
+    let total_weight = assets
+        .iter()
+        .try_fold(Uint128::zero(), |sum, (_, weight)| sum.checked_add(*weight))?;
+
+    let factors = assets
+        .iter()
+        .map(|(value, weight)| {
+            Ok((
+                checked_div(
+                    value.checked_mul(to_decimal256(total_weight))?,
+                    to_decimal256(*weight),
+                )?,
+                Decimal256::from_ratio(*weight, total_weight),
+            ))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    if factors.iter().any(|(factor, _)| factor.is_zero()) {
+        return Ok(Decimal256::zero());
+    }
+
+    let max = factors
+        .iter()
+        .fold(Decimal256::zero(), |max, (factor, _)| max.max(*factor));
+    factors.iter().try_fold(max, |value, (factor, weight)| {
+        Ok(value.checked_mul(pow(checked_div(*factor, max)?, *weight)?)?)
+    })
+}
+
+/// Returns the value of a stableswap pool at reference prices, given the
+/// reserve, scaling factor and reference price of each asset.
+///
+/// The value is the sum `D` of the scaled reserves of the balanced pool with
+/// the same CFMM constant `prod(x_i) * sum(x_i^2)`, valued at the lowest
+/// price of a scaled unit. This is a lower bound of the value of the pool,
+/// which is tight while the assets hold their peg.
+pub(crate) fn stableswap_fair_value(reserves: &[(Uint128, u64, Decimal)]) -> StdResult<Decimal256> {
+    let scaled = reserves
+        .iter()
+        .map(|(amount, scaling_factor, _)| Decimal256::from_ratio(*amount, *scaling_factor))
+        .collect::<Vec<_>>();
+
+    // Normalize by the largest reserve to avoid overflows, which does not
+    // change the balanced point relative to the reserves.
+    let norm = scaled.iter().fold(Decimal256::zero(), |max, r| max.max(*r));
+    if norm.is_zero() {
+        return Err(StdError::generic_err("osmosis math: pool has no liquidity"));
+    }
+    let (product, sum_of_squares) = scaled.iter().try_fold(
+        (Decimal256::one(), Decimal256::zero()),
+        |(product, sum_of_squares), r| {
+            let r = checked_div(*r, norm)?;
+            Ok::<_, StdError>((
+                product.checked_mul(r)?,
+                sum_of_squares.checked_add(r.checked_mul(r)?)?,
+            ))
+        },
+    )?;
+    let k = product.checked_mul(sum_of_squares)?;
+
+    // Binary search for the balanced reserve `a` with `n * a^(n + 2) = k`,
+    // keeping the lower bound.
+    let n = reserves.len() as u32;
+    let n_decimal = Decimal256::from_ratio(n, 1u32);
+    let two = Decimal256::percent(200);
+    let mut low = Decimal256::zero();
+    let mut high = Decimal256::one();
+    for _ in 0..MAX_ITERATIONS {
+        let mid = checked_div(low.checked_add(high)?, two)?;
+        if mid == low {
+            break;
+        }
+        if n_decimal.checked_mul(mid.checked_pow(n + 2)?)? > k {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    let d = n_decimal.checked_mul(low)?.checked_mul(norm)?;
+
+    let min_price = reserves
+        .iter()
+        .map(|(_, scaling_factor, price)| {
+            Decimal256::from(*price).checked_mul(Decimal256::from_ratio(*scaling_factor, 1u128))
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .min()
+        .unwrap_or_default();
+    Ok(d.checked_mul(min_price)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balancer_fair_value() {
+        // Reserves balanced at the prices are valued at their current value
+        let value = balancer_fair_value(&[
+            (to_decimal256(Uint128::new(8_000)), Uint128::new(80)),
+            (to_decimal256(Uint128::new(2_000)), Uint128::new(20)),
+        ])
+        .unwrap();
+        assert_eq!(value, to_decimal256(Uint128::new(10_000)));
+
+        // With equal weights this is the constant product fair value
+        // 2 * sqrt(1_000 * 4_000)
+        let value = balancer_fair_value(&[
+            (to_decimal256(Uint128::new(1_000)), Uint128::new(50)),
+            (to_decimal256(Uint128::new(4_000)), Uint128::new(50)),
+        ])
+        .unwrap();
+        assert_eq!(value, to_decimal256(Uint128::new(4_000)));
+    }
+
+    #[test]
+    fn test_stableswap_fair_value() {
+        let balanced = stableswap_fair_value(&[
+            (Uint128::new(1_000_000), 1, Decimal::one()),
+            (Uint128::new(1_000_000), 1, Decimal::one()),
+        ])
+        .unwrap();
+        let diff = to_decimal256(Uint128::new(2_000_000)) - balanced;
+        assert!(diff < Decimal256::percent(1));
+
+        // Imbalanced reserves are worth less than their current value
+        let imbalanced = stableswap_fair_value(&[
+            (Uint128::new(1_500_000), 1, Decimal::one()),
+            (Uint128::new(500_000), 1, Decimal::one()),
+        ])
+        .unwrap();
+        assert!(imbalanced < to_decimal256(Uint128::new(2_000_000)));
+        assert!(imbalanced > to_decimal256(Uint128::new(1_900_000)));
+    }
+
+    #[test]
+    fn test_pow() {
+        let base = Decimal256::percent(50);
+        assert_eq!(pow(base, Decimal256::one()).unwrap(), base);
+        assert_eq!(
+            pow(base, Decimal256::percent(200)).unwrap(),
+            Decimal256::percent(25)
+        );
+        assert_eq!(pow(base, Decimal256::percent(50)).unwrap(), base.sqrt());
+
+        // 0.5^0.25 = 0.840896415...
+        let result = pow(base, Decimal256::percent(25)).unwrap();
+        let expected = Decimal256::from_ratio(840896415u128, 1_000_000_000u128);
+        let tolerance = Decimal256::from_ratio(1u128, 1_000_000u128);
+        assert!(abs_difference_with_sign(result, expected).0 < tolerance);
+
+        assert!(pow(Decimal256::zero(), Decimal256::one()).is_err());
+        assert!(pow(Decimal256::percent(200), Decimal256::one()).is_err());
+    }
+
+    #[test]
+    fn test_balancer_equal_weights_matches_constant_product() {
+        let amount_out = balancer_calc_out_amt_given_in(
+            (Uint128::new(1_000_000), Uint128::one()),
+            (Uint128::new(1_000_000), Uint128::one()),
+            Uint128::new(1_000),
+            Decimal::zero(),
+        )
+        .unwrap();
+
+        // x * y = k => 1_000_000 - 1_000_000^2 / 1_001_000 = 999.000999
+        assert_eq!(amount_out, Uint128::new(999));
+    }
+
+    #[test]
+    fn test_balancer_in_given_out_inverts_out_given_in() {
+        let pool_in = (Uint128::new(1_000_000_000), Uint128::new(2));
+        let pool_out = (Uint128::new(500_000_000), Uint128::one());
+        let swap_fee = Decimal::percent(1);
+
+        let amount_in =
+            balancer_calc_in_amt_given_out(pool_in, pool_out, Uint128::new(1_000_000), swap_fee)
+                .unwrap();
+        let amount_out =
+            balancer_calc_out_amt_given_in(pool_in, pool_out, amount_in, swap_fee).unwrap();
+
+        assert!(amount_out >= Uint128::new(1_000_000));
+        assert!(amount_out <= Uint128::new(1_000_001));
+    }
+
+    #[test]
+    fn test_stableswap_in_given_out_inverts_out_given_in() {
+        let reserves = ScaledReserves {
+            token_in: (Uint128::new(1_000_000_000), 1),
+            token_out: (Uint128::new(800_000_000), 1),
+            remaining: vec![],
+        };
+        let swap_fee = Decimal::percent(1);
+
+        let amount_out =
+            stableswap_calc_out_amt_given_in(&reserves, Uint128::new(1_000_000), swap_fee).unwrap();
+        // A stableswap pool close to balance swaps close to 1:1
+        assert!(amount_out < Uint128::new(1_000_000));
+        assert!(amount_out > Uint128::new(980_000));
+
+        let amount_in = stableswap_calc_in_amt_given_out(&reserves, amount_out, swap_fee).unwrap();
+        assert!(amount_in >= Uint128::new(999_999));
+        assert!(amount_in <= Uint128::new(1_000_001));
+    }
+}
@@ -1,8 +1,20 @@
 //! Module containing Pool and Staking implementations for Osmosis
 
+#[cfg(feature = "osmosis-cl")]
+pub(crate) mod cl_math;
+#[cfg(feature = "osmosis-cl")]
+#[cfg_attr(docsrs, doc(cfg(feature = "osmosis-cl")))]
+pub mod cl_msg;
+#[cfg(feature = "osmosis-cl")]
+mod cl_pool;
 mod helpers;
+pub(crate) mod math;
 mod pool;
+pub mod reply;
 mod staking;
 
+#[cfg(feature = "osmosis-cl")]
+#[cfg_attr(docsrs, doc(cfg(feature = "osmosis-cl")))]
+pub use cl_pool::*;
 pub use pool::*;
 pub use staking::*;
@@ -8,41 +8,72 @@ use apollo_utils::assets::{
 };
 use apollo_utils::iterators::IntoElementwise;
 use osmosis_std::types::osmosis::gamm::v1beta1::{
-    GammQuerier, MsgExitPool, MsgJoinPool, MsgJoinSwapExternAmountIn, MsgSwapExactAmountIn,
-    SwapAmountInRoute,
+    GammQuerier, MsgExitPool, MsgExitSwapShareAmountIn, MsgJoinPool, MsgJoinSwapExternAmountIn,
+    MsgSwapExactAmountIn, MsgSwapExactAmountOut, SwapAmountInRoute, SwapAmountOutRoute,
 };
 
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    Coin, CosmosMsg, Deps, Env, Event, QuerierWrapper, Response, StdError, StdResult, Uint128,
+    Coin, CosmosMsg, Decimal, Deps, Env, Event, QuerierWrapper, StdError, StdResult, Uint128,
 };
 use cw_asset::{Asset, AssetInfo, AssetList};
 
-use crate::traits::Pool;
+use crate::implementations::zap::simulate_zap_out;
+use crate::response::CwDexResponse;
+use crate::traits::{CumulativePricesSnapshot, Pool, PoolParams, PriceOracle, SwapSimulation};
 use crate::CwDexError;
 
-/// Struct for interacting with Osmosis v1beta1 balancer pools. If `pool_id`
-/// maps to another type of pool this will fail.
+use super::helpers::{
+    parse_osmosis_dec, query_arithmetic_twap, swap_simulation_from_spot_price, OsmosisPoolState,
+};
+
+/// The type of an Osmosis gamm pool
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum OsmosisPoolType {
+    /// A v1beta1 balancer pool
+    #[default]
+    Balancer,
+    /// A stableswap pool
+    StableSwap,
+}
+
+/// Struct for interacting with Osmosis v1beta1 balancer pools and stableswap
+/// pools. Swap simulations use the math of the respective pool type.
+///
+/// Both pool types are joined, exited and swapped with the gamm messages
+/// `MsgJoinPool`, `MsgExitPool` and `MsgSwapExactAmountIn` rather than the
+/// poolmanager messages, since the pinned osmosis-std (Osmosis v13) has no
+/// poolmanager module. The gamm messages route stableswap pools to the
+/// stableswap implementation on chain.
 #[cw_serde]
 #[derive(Copy)]
 pub struct OsmosisPool {
     /// The pool id of the pool to interact with
     pool_id: u64,
+    /// The type of the pool. Defaults to `Balancer` for pools serialized
+    /// before stableswap pools were supported.
+    #[serde(default)]
+    pool_type: OsmosisPoolType,
 }
 
 impl OsmosisPool {
-    /// Creates a new `OsmosisPool` instance with the given `pool_id` and
-    /// validates that the pool exists.
+    /// Creates a new `OsmosisPool` instance with the given `pool_id`,
+    /// validates that the pool exists and detects its type.
     pub fn new(pool_id: u64, deps: Deps) -> StdResult<Self> {
-        let pool = Self { pool_id };
-        // If this query succeeds then the pool exists
-        pool.get_pool_liquidity(deps)?;
-        Ok(pool)
+        // If this query succeeds then the pool exists and is of a supported type
+        let pool_type = OsmosisPoolState::query(&deps.querier, pool_id)?.pool_type();
+        Ok(Self { pool_id, pool_type })
     }
 
-    /// Creates an unchecked pool for use in testing.
+    /// Creates an unchecked balancer pool for use in testing.
     pub fn unchecked(pool_id: u64) -> Self {
-        Self { pool_id }
+        Self::unchecked_with_type(pool_id, OsmosisPoolType::Balancer)
+    }
+
+    /// Creates an unchecked pool of the given type for use in testing.
+    pub fn unchecked_with_type(pool_id: u64, pool_type: OsmosisPoolType) -> Self {
+        Self { pool_id, pool_type }
     }
 
     /// Returns the pool id of the pool
@@ -50,6 +81,20 @@ impl OsmosisPool {
         self.pool_id
     }
 
+    /// Returns the type of the pool
+    pub fn pool_type(&self) -> OsmosisPoolType {
+        self.pool_type
+    }
+
+    /// Queries the scaling factors of a stableswap pool, in the order of the
+    /// pool's assets sorted by denom. Returns `None` for balancer pools.
+    pub fn scaling_factors(&self, deps: Deps) -> StdResult<Option<Vec<u64>>> {
+        match OsmosisPoolState::query(&deps.querier, self.pool_id)? {
+            OsmosisPoolState::Balancer(_) => Ok(None),
+            OsmosisPoolState::StableSwap(pool) => Ok(Some(pool.scaling_factors)),
+        }
+    }
+
     /// Simulates a single sided join and returns `Uint128` amount of LP tokens
     /// returned. A single sided join will use all of the provided asset.
     pub fn simulate_single_sided_join(
@@ -93,6 +138,22 @@ impl OsmosisPool {
 
         Ok((lp_tokens_returned, AssetList::from(tokens_used)))
     }
+
+    /// Queries the gamm module for the spot price of `base_asset` denominated
+    /// in `quote_asset`.
+    fn query_spot_price(
+        &self,
+        querier: &QuerierWrapper,
+        base_asset: &AssetInfo,
+        quote_asset: &AssetInfo,
+    ) -> StdResult<Decimal> {
+        let response = GammQuerier::new(querier).spot_price(
+            self.pool_id,
+            assert_native_asset_info(base_asset)?,
+            assert_native_asset_info(quote_asset)?,
+        )?;
+        parse_osmosis_dec(&response.spot_price)
+    }
 }
 
 impl Pool for OsmosisPool {
@@ -102,16 +163,24 @@ impl Pool for OsmosisPool {
         env: &Env,
         assets: AssetList,
         min_out: Uint128,
-    ) -> Result<Response, CwDexError> {
+    ) -> Result<CwDexResponse, CwDexError> {
         let mut assets = assets;
 
         // Remove all zero amount Coins, merge duplicates and assert that all assets are
         // native.
         let mut assets = assert_only_native_coins(&merge_assets(assets.purge().deref())?)?;
 
-        let expected_shares = self
-            .simulate_provide_liquidity(deps, env, assets.to_owned().into())?
-            .amount;
+        // A single sided join uses all of the asset, while a join without swap
+        // may leave some of the assets unused.
+        let (expected_shares, tokens_used) = if assets.len() == 1 {
+            let shares =
+                self.simulate_single_sided_join(&deps.querier, &assets[0].clone().into())?;
+            (shares, assets.to_owned().into())
+        } else {
+            self.simulate_noswap_join(&deps.querier, &assets.to_owned().into())?
+        };
+        let mut leftover: AssetList = assets.to_owned().into();
+        leftover.deduct_many(tokens_used.clone().purge())?;
 
         // Assert slippage tolerance
         if min_out > expected_shares {
@@ -144,42 +213,111 @@ impl Pool for OsmosisPool {
 
         let event = Event::new("apollo/cw-dex/provide_liquidity")
             .add_attribute("pool_id", self.pool_id.to_string())
+            .add_attribute("pool_type", format!("{:?}", self.pool_type))
             .add_attribute("min_out", min_out)
             .add_attribute("expected_shares", expected_shares);
 
-        Ok(Response::new().add_message(join_pool).add_event(event))
+        Ok(CwDexResponse::new()
+            .add_message(join_pool)
+            .add_event(event)
+            .with_expected_out(vec![Asset::new(self.lp_token(), expected_shares)])
+            .with_leftover(leftover))
+    }
+
+    fn zap_in(
+        &self,
+        deps: Deps,
+        env: &Env,
+        asset: Asset,
+        min_out: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        // Osmosis swaps the optimal fraction of the asset itself when joining
+        // with a single asset using `MsgJoinSwapExternAmountIn`.
+        self.provide_liquidity(deps, env, vec![asset].into(), min_out)
     }
 
     fn withdraw_liquidity(
         &self,
-        _deps: Deps,
+        deps: Deps,
         env: &Env,
         lp_token: Asset,
-    ) -> Result<Response, CwDexError> {
+        min_out: AssetList,
+    ) -> Result<CwDexResponse, CwDexError> {
+        // Osmosis requires the minimum amounts to be positive and sorted by
+        // denom
+        let mut token_out_mins = min_out
+            .into_iter()
+            .filter(|asset| !asset.amount.is_zero())
+            .map(|asset| -> StdResult<Coin> { Ok(asset.clone().try_into()?) })
+            .collect::<StdResult<Vec<_>>>()?;
+        token_out_mins.sort_by(|a, b| a.denom.cmp(&b.denom));
+
         let exit_msg = MsgExitPool {
             sender: env.contract.address.to_string(),
             pool_id: self.pool_id,
             share_in_amount: lp_token.amount.to_string(),
-            token_out_mins: vec![],
+            token_out_mins: token_out_mins.into_iter().map(Into::into).collect(),
         };
 
         let event = Event::new("apollo/cw-dex/withdraw_liquidity")
             .add_attribute("pool_id", self.pool_id.to_string())
+            .add_attribute("pool_type", format!("{:?}", self.pool_type))
             .add_attribute("shares_in", lp_token.to_string());
 
-        Ok(Response::new().add_message(exit_msg).add_event(event))
+        Ok(CwDexResponse::new()
+            .add_message(exit_msg)
+            .add_event(event)
+            .with_expected_out(self.simulate_withdraw_liquidity(deps, &lp_token)?))
+    }
+
+    fn zap_out(
+        &self,
+        deps: Deps,
+        env: &Env,
+        lp_token: Asset,
+        ask_asset_info: AssetInfo,
+        min_out: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        if lp_token.info != self.lp_token() {
+            return Err(CwDexError::InvalidLpToken {});
+        }
+        let expected_out = self.simulate_zap_out(deps, &lp_token, ask_asset_info.clone())?;
+
+        // Osmosis exits the pool and swaps the other assets into the asked
+        // asset in a single message.
+        let exit_msg = MsgExitSwapShareAmountIn {
+            sender: env.contract.address.to_string(),
+            pool_id: self.pool_id,
+            token_out_denom: assert_native_asset_info(&ask_asset_info)?,
+            share_in_amount: lp_token.amount.to_string(),
+            // Min out must be greater than 0 for osmosis.
+            token_out_min_amount: min_out.max(Uint128::one()).to_string(),
+        };
+
+        let event = Event::new("apollo/cw-dex/zap_out")
+            .add_attribute("pool_id", self.pool_id.to_string())
+            .add_attribute("pool_type", format!("{:?}", self.pool_type))
+            .add_attribute("shares_in", lp_token.to_string())
+            .add_attribute("ask_asset", ask_asset_info.to_string())
+            .add_attribute("minimum_out_amount", min_out);
+
+        Ok(CwDexResponse::new()
+            .add_message(exit_msg)
+            .add_event(event)
+            .with_expected_out(vec![expected_out]))
     }
 
     fn swap(
         &self,
-        _deps: Deps,
+        deps: Deps,
         env: &Env,
         offer_asset: Asset,
         ask_asset_info: AssetInfo,
         min_out: Uint128,
-    ) -> Result<Response, CwDexError> {
+    ) -> Result<CwDexResponse, CwDexError> {
         let offer = assert_native_coin(&offer_asset)?;
         let ask_denom = assert_native_asset_info(&ask_asset_info)?;
+        let expected_out = self.simulate_swap(deps, offer_asset, ask_asset_info.clone())?;
 
         // Min out must be greater than 0 for osmosis.
         let min_out = if min_out == Uint128::zero() {
@@ -204,7 +342,43 @@ impl Pool for OsmosisPool {
             .add_attribute("ask", ask_denom)
             .add_attribute("token_out_min_amount", min_out);
 
-        Ok(Response::new().add_message(swap_msg).add_event(event))
+        Ok(CwDexResponse::new()
+            .add_message(swap_msg)
+            .add_event(event)
+            .with_expected_out(vec![Asset::new(ask_asset_info, expected_out)]))
+    }
+
+    fn swap_exact_out(
+        &self,
+        _deps: Deps,
+        env: &Env,
+        ask_asset: Asset,
+        offer_asset_info: AssetInfo,
+        max_in: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        let ask = assert_native_coin(&ask_asset)?;
+        let offer_denom = assert_native_asset_info(&offer_asset_info)?;
+
+        let swap_msg = MsgSwapExactAmountOut {
+            sender: env.contract.address.to_string(),
+            routes: vec![SwapAmountOutRoute {
+                pool_id: self.pool_id,
+                token_in_denom: offer_denom.clone(),
+            }],
+            token_in_max_amount: max_in.to_string(),
+            token_out: Some(ask.clone().into()),
+        };
+
+        let event = Event::new("apollo/cw-dex/swap_exact_out")
+            .add_attribute("pool_id", self.pool_id.to_string())
+            .add_attribute("ask", ask.to_string())
+            .add_attribute("offer", offer_denom)
+            .add_attribute("token_in_max_amount", max_in);
+
+        Ok(CwDexResponse::new()
+            .add_message(swap_msg)
+            .add_event(event)
+            .with_expected_out(vec![ask_asset]))
     }
 
     fn get_pool_liquidity(&self, deps: Deps) -> Result<AssetList, CwDexError> {
@@ -242,6 +416,13 @@ impl Pool for OsmosisPool {
         Ok(Asset::new(self.lp_token(), shares_out_amount))
     }
 
+    fn simulate_zap_in(&self, deps: Deps, _env: &Env, asset: Asset) -> Result<Asset, CwDexError> {
+        Ok(Asset::new(
+            self.lp_token(),
+            self.simulate_single_sided_join(&deps.querier, &asset)?,
+        ))
+    }
+
     fn simulate_withdraw_liquidity(
         &self,
         deps: Deps,
@@ -269,24 +450,80 @@ impl Pool for OsmosisPool {
         Ok(tokens_out.into())
     }
 
+    fn simulate_zap_out(
+        &self,
+        deps: Deps,
+        lp_token: &Asset,
+        ask_asset_info: AssetInfo,
+    ) -> Result<Asset, CwDexError> {
+        let simulation = simulate_zap_out(self, deps, lp_token, &ask_asset_info)?;
+
+        Ok(Asset::new(ask_asset_info, simulation.return_amount))
+    }
+
     fn simulate_swap(
         &self,
         deps: Deps,
         offer: Asset,
         ask_asset_info: AssetInfo,
-        sender: Option<String>,
     ) -> StdResult<Uint128> {
         let offer: Coin = offer.try_into()?;
-        let swap_response = GammQuerier::new(&deps.querier).estimate_swap_exact_amount_in(
-            sender.ok_or_else(|| StdError::generic_err("sender is required for osmosis"))?,
-            self.pool_id,
-            offer.to_string(),
-            vec![SwapAmountInRoute {
-                pool_id: self.pool_id,
-                token_out_denom: assert_native_asset_info(&ask_asset_info)?,
-            }],
-        )?;
-        Uint128::from_str(swap_response.token_out_amount.as_str())
+        OsmosisPoolState::query(&deps.querier, self.pool_id)?
+            .calc_out_amt_given_in(&offer, &assert_native_asset_info(&ask_asset_info)?)
+    }
+
+    fn simulate_swap_detailed(
+        &self,
+        deps: Deps,
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+    ) -> StdResult<SwapSimulation> {
+        let return_amount =
+            self.simulate_swap(deps, offer_asset.clone(), ask_asset_info.clone())?;
+        let spot_price =
+            self.query_spot_price(&deps.querier, &offer_asset.info, &ask_asset_info)?;
+        let swap_fee = OsmosisPoolState::query(&deps.querier, self.pool_id)?.swap_fee()?;
+
+        Ok(swap_simulation_from_spot_price(
+            offer_asset.amount,
+            return_amount,
+            spot_price,
+            swap_fee,
+        ))
+    }
+
+    fn spot_price(
+        &self,
+        deps: Deps,
+        _env: &Env,
+        base_asset: &AssetInfo,
+        quote_asset: &AssetInfo,
+    ) -> StdResult<Decimal> {
+        self.query_spot_price(&deps.querier, base_asset, quote_asset)
+    }
+
+    fn pool_params(&self, deps: Deps, _env: &Env) -> Result<PoolParams, CwDexError> {
+        Ok(OsmosisPoolState::query(&deps.querier, self.pool_id)?.pool_params()?)
+    }
+
+    fn fair_lp_token_price(
+        &self,
+        deps: Deps,
+        _env: &Env,
+        prices: &[(AssetInfo, Decimal)],
+    ) -> Result<Decimal, CwDexError> {
+        OsmosisPoolState::query(&deps.querier, self.pool_id)?.fair_lp_token_price(prices)
+    }
+
+    fn simulate_swap_exact_out(
+        &self,
+        deps: Deps,
+        ask_asset: Asset,
+        offer_asset_info: AssetInfo,
+    ) -> StdResult<Uint128> {
+        let ask: Coin = ask_asset.try_into()?;
+        OsmosisPoolState::query(&deps.querier, self.pool_id)?
+            .calc_in_amt_given_out(&ask, &assert_native_asset_info(&offer_asset_info)?)
     }
 
     fn lp_token(&self) -> AssetInfo {
@@ -294,13 +531,138 @@ impl Pool for OsmosisPool {
     }
 }
 
+/// Prices are queried from the records of the twap module, so no snapshots
+/// need to be stored.
+impl PriceOracle for OsmosisPool {
+    fn query_twap_price(
+        &self,
+        deps: Deps,
+        env: &Env,
+        base_asset_info: &AssetInfo,
+        quote_asset_info: &AssetInfo,
+        window: u64,
+        _snapshots: &[CumulativePricesSnapshot],
+    ) -> Result<Decimal, CwDexError> {
+        query_arithmetic_twap(
+            &deps.querier,
+            env,
+            self.pool_id,
+            base_asset_info,
+            quote_asset_info,
+            window,
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use cw_asset::AssetInfo;
+    use std::marker::PhantomData;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{
+        from_slice, to_binary, Addr, ContractResult, CosmosMsg, Empty, OwnedDeps, Querier,
+        QuerierResult, QueryRequest, SystemResult, Uint128,
+    };
+    use cw_asset::{Asset, AssetInfo};
+    use osmosis_std::shim::Any;
+    use osmosis_std::types::cosmos::base::v1beta1::Coin as ProtoCoin;
+    use osmosis_std::types::osmosis::gamm::poolmodels::stableswap::v1beta1::{
+        Pool as StableSwapPool, PoolParams as StableSwapPoolParams,
+    };
+    use osmosis_std::types::osmosis::gamm::v1beta1::{
+        MsgExitPool, MsgExitSwapShareAmountIn, MsgJoinPool, MsgSwapExactAmountIn,
+        QueryCalcExitPoolCoinsFromSharesResponse, QueryCalcJoinPoolNoSwapSharesResponse,
+        QueryPoolResponse, QueryTotalPoolLiquidityResponse,
+    };
+    use prost::Message;
 
     use crate::traits::Pool;
+    use crate::CwDexError;
 
-    use super::OsmosisPool;
+    use super::{OsmosisPool, OsmosisPoolType};
+
+    fn coin(amount: u128, denom: &str) -> ProtoCoin {
+        ProtoCoin {
+            denom: denom.to_string(),
+            amount: amount.to_string(),
+        }
+    }
+
+    /// Answers the gamm queries for pool 1, a uatom/uosmo stableswap pool with
+    /// reserves of 1_000_000 of each asset and 1_000_000 shares. Joining the
+    /// pool with 1_000 of each asset returns 1_000 shares and exiting the pool
+    /// returns 1_000 of each asset.
+    struct MockGammQuerier(MockQuerier);
+
+    impl Querier for MockGammQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            let path = match from_slice::<QueryRequest<Empty>>(bin_request).unwrap() {
+                QueryRequest::Stargate { path, .. } => path,
+                _ => return self.0.raw_query(bin_request),
+            };
+            let reserves = vec![coin(1_000_000, "uatom"), coin(1_000_000, "uosmo")];
+            let res = match path.as_str() {
+                "/osmosis.gamm.v1beta1.Query/Pool" => to_binary(&QueryPoolResponse {
+                    pool: Some(Any {
+                        type_url: "/osmosis.gamm.poolmodels.stableswap.v1beta1.Pool".to_string(),
+                        value: StableSwapPool {
+                            id: 1,
+                            pool_params: Some(StableSwapPoolParams {
+                                swap_fee: "0.003000000000000000".to_string(),
+                                exit_fee: "0.000000000000000000".to_string(),
+                            }),
+                            total_shares: Some(coin(1_000_000, "gamm/pool/1")),
+                            pool_liquidity: reserves,
+                            scaling_factors: vec![1, 1],
+                            ..Default::default()
+                        }
+                        .encode_to_vec(),
+                    }),
+                }),
+                "/osmosis.gamm.v1beta1.Query/TotalPoolLiquidity" => {
+                    to_binary(&QueryTotalPoolLiquidityResponse {
+                        liquidity: reserves,
+                    })
+                }
+                "/osmosis.gamm.v1beta1.Query/CalcJoinPoolNoSwapShares" => {
+                    to_binary(&QueryCalcJoinPoolNoSwapSharesResponse {
+                        tokens_out: vec![coin(1_000, "uatom"), coin(1_000, "uosmo")],
+                        shares_out: "1000".to_string(),
+                    })
+                }
+                "/osmosis.gamm.v1beta1.Query/CalcExitPoolCoinsFromShares" => {
+                    to_binary(&QueryCalcExitPoolCoinsFromSharesResponse {
+                        tokens_out: vec![coin(1_000, "uatom"), coin(1_000, "uosmo")],
+                    })
+                }
+                _ => return SystemResult::Ok(ContractResult::Err("unexpected query".to_string())),
+            };
+            SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+        }
+    }
+
+    fn mock_gamm_deps() -> OwnedDeps<MockStorage, MockApi, MockGammQuerier> {
+        OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: MockGammQuerier(MockQuerier::default()),
+            custom_query_type: PhantomData,
+        }
+    }
+
+    /// Decodes the stargate message of type `T` in `msg`
+    fn decode_msg<T: Message + Default>(msg: &CosmosMsg, type_url: &str) -> T {
+        match msg {
+            CosmosMsg::Stargate {
+                type_url: msg_type_url,
+                value,
+            } => {
+                assert_eq!(msg_type_url, type_url);
+                T::decode(value.as_slice()).unwrap()
+            }
+            _ => panic!("unexpected msg"),
+        }
+    }
 
     #[test]
     fn test_lp_token() {
@@ -313,4 +675,105 @@ mod tests {
             AssetInfo::Cw20(_) => panic!("Unexpected cw20 token"),
         }
     }
+
+    #[test]
+    fn test_pool_type_defaults_to_balancer() {
+        let pool: OsmosisPool = cosmwasm_std::from_slice(br#"{"pool_id":1337}"#).unwrap();
+
+        assert_eq!(pool, OsmosisPool::unchecked(1337u64));
+        assert_eq!(pool.pool_type(), OsmosisPoolType::Balancer);
+    }
+
+    #[test]
+    fn test_zap_out_invalid_lp_token() {
+        let deps = mock_dependencies();
+        let pool = OsmosisPool::unchecked(1337u64);
+
+        let err = pool
+            .zap_out(
+                deps.as_ref(),
+                &mock_env(),
+                Asset::cw20(Addr::unchecked("lp_token"), 1000u128),
+                AssetInfo::native("uosmo"),
+                Uint128::new(900),
+            )
+            .unwrap_err();
+        assert_eq!(err, CwDexError::InvalidLpToken {});
+    }
+
+    #[test]
+    fn test_zap_out_without_min_out() {
+        let deps = mock_gamm_deps();
+        let pool = OsmosisPool::unchecked_with_type(1, OsmosisPoolType::StableSwap);
+
+        let response = pool
+            .zap_out(
+                deps.as_ref(),
+                &mock_env(),
+                Asset::native("gamm/pool/1", 1_000u128),
+                AssetInfo::native("uosmo"),
+                Uint128::zero(),
+            )
+            .unwrap();
+
+        // Osmosis rejects a min out of zero
+        let msg: MsgExitSwapShareAmountIn = decode_msg(
+            &response.messages[0].msg,
+            MsgExitSwapShareAmountIn::TYPE_URL,
+        );
+        assert_eq!(msg.token_out_denom, "uosmo");
+        assert_eq!(msg.share_in_amount, "1000");
+        assert_eq!(msg.token_out_min_amount, "1");
+    }
+
+    #[test]
+    fn test_stableswap_pool_uses_gamm_messages() {
+        let deps = mock_gamm_deps();
+        let env = mock_env();
+        let pool = OsmosisPool::unchecked_with_type(1, OsmosisPoolType::StableSwap);
+
+        let response = pool
+            .provide_liquidity(
+                deps.as_ref(),
+                &env,
+                vec![
+                    Asset::native("uatom", 1_000u128),
+                    Asset::native("uosmo", 1_000u128),
+                ]
+                .into(),
+                Uint128::new(1_000),
+            )
+            .unwrap();
+        let msg: MsgJoinPool = decode_msg(&response.messages[0].msg, MsgJoinPool::TYPE_URL);
+        assert_eq!(msg.pool_id, 1);
+        assert_eq!(msg.share_out_amount, "1000");
+
+        let response = pool
+            .withdraw_liquidity(
+                deps.as_ref(),
+                &env,
+                Asset::native("gamm/pool/1", 1_000u128),
+                vec![Asset::native("uatom", 900u128)].into(),
+            )
+            .unwrap();
+        let msg: MsgExitPool = decode_msg(&response.messages[0].msg, MsgExitPool::TYPE_URL);
+        assert_eq!(msg.pool_id, 1);
+        assert_eq!(msg.share_in_amount, "1000");
+        assert_eq!(msg.token_out_mins, vec![coin(900, "uatom")]);
+
+        let response = pool
+            .swap(
+                deps.as_ref(),
+                &env,
+                Asset::native("uatom", 1_000u128),
+                AssetInfo::native("uosmo"),
+                Uint128::new(900),
+            )
+            .unwrap();
+        let msg: MsgSwapExactAmountIn =
+            decode_msg(&response.messages[0].msg, MsgSwapExactAmountIn::TYPE_URL);
+        assert_eq!(msg.routes[0].pool_id, 1);
+        assert_eq!(msg.routes[0].token_out_denom, "uosmo");
+        assert_eq!(msg.token_out_min_amount, "900");
+    }
 }
@@ -0,0 +1,199 @@
+//! Helpers for parsing the replies of the submessages sent by the Osmosis
+//! pool and staking implementations.
+
+use cosmwasm_std::{Binary, Event, Reply, SubMsgResponse};
+use osmosis_std::types::osmosis::lockup::MsgLockTokensResponse;
+use osmosis_std::types::osmosis::superfluid::MsgLockAndSuperfluidDelegateResponse;
+use prost::Message;
+
+#[cfg(feature = "osmosis-cl")]
+use super::cl_msg::MsgCreatePositionResponse;
+use crate::CwDexError;
+
+/// The type of the event emitted by the lockup module when a lock starts
+/// unlocking
+const BEGIN_UNLOCK_EVENT: &str = "begin_unlock";
+
+/// Information about a lock that started unlocking, parsed from the events of
+/// a `MsgBeginUnlocking` or `MsgSuperfluidUnbondLock` submessage.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnlockingInfo {
+    /// The ID of the unlocking lock
+    pub lock_id: u64,
+    /// The owner of the lock
+    pub owner: String,
+    /// The lockup duration, as formatted by the lockup module
+    pub duration: String,
+    /// The time at which the lock is released, as formatted by the lockup
+    /// module
+    pub unlock_time: String,
+}
+
+/// Returns the response of a successful submessage.
+fn into_sub_msg_response(reply: Reply) -> Result<SubMsgResponse, CwDexError> {
+    reply
+        .result
+        .into_result()
+        .map_err(|reason| CwDexError::SubMsgFailed { reason })
+}
+
+/// Decodes the protobuf encoded response data of a successful submessage.
+fn decode_reply_data<T: Message + Default>(reply: Reply, msg_type: &str) -> Result<T, CwDexError> {
+    let data: Binary = into_sub_msg_response(reply)?
+        .data
+        .ok_or(CwDexError::NoReplyData {})?;
+
+    T::decode(data.as_slice()).map_err(|e| CwDexError::ReplyDecode {
+        msg_type: msg_type.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+/// Returns the value of the attribute `key` of the first event of type `ty`.
+fn event_attribute(events: &[Event], ty: &str, key: &str) -> Result<String, CwDexError> {
+    events
+        .iter()
+        .find(|event| event.ty == ty)
+        .and_then(|event| event.attributes.iter().find(|attr| attr.key == key))
+        .map(|attr| attr.value.clone())
+        .ok_or_else(|| CwDexError::MissingReplyAttribute {
+            event: ty.to_string(),
+            attribute: key.to_string(),
+        })
+}
+
+/// Parses the reply of the `MsgLockTokens` submessage sent by
+/// [`OsmosisStaking::stake`](super::OsmosisStaking), returning the ID of the
+/// created lock.
+pub fn parse_lock_tokens_reply(reply: Reply) -> Result<u64, CwDexError> {
+    Ok(decode_reply_data::<MsgLockTokensResponse>(reply, "MsgLockTokensResponse")?.id)
+}
+
+/// Parses the reply of the `MsgLockAndSuperfluidDelegate` submessage sent by
+/// [`OsmosisSuperfluidStaking::stake`](super::OsmosisSuperfluidStaking),
+/// returning the ID of the created lock.
+pub fn parse_lock_and_superfluid_delegate_reply(reply: Reply) -> Result<u64, CwDexError> {
+    Ok(decode_reply_data::<MsgLockAndSuperfluidDelegateResponse>(
+        reply,
+        "MsgLockAndSuperfluidDelegateResponse",
+    )?
+    .id)
+}
+
+/// Parses the reply of the `MsgCreatePosition` submessage sent by
+/// [`OsmosisClPool::provide_liquidity`](super::OsmosisClPool), returning the
+/// response which contains the id of the created position.
+#[cfg(feature = "osmosis-cl")]
+#[cfg_attr(docsrs, doc(cfg(feature = "osmosis-cl")))]
+pub fn parse_create_position_reply(reply: Reply) -> Result<MsgCreatePositionResponse, CwDexError> {
+    decode_reply_data(reply, "MsgCreatePositionResponse")
+}
+
+/// Parses the reply of the `MsgBeginUnlocking` submessage sent by
+/// [`OsmosisStaking::unlock`](super::OsmosisStaking), returning information
+/// about the unlocking lock from the `begin_unlock` event.
+pub fn parse_begin_unlocking_reply(reply: Reply) -> Result<UnlockingInfo, CwDexError> {
+    let events = into_sub_msg_response(reply)?.events;
+
+    let lock_id = event_attribute(&events, BEGIN_UNLOCK_EVENT, "period_lock_id")?;
+    let lock_id = lock_id.parse().map_err(|_| CwDexError::ReplyDecode {
+        msg_type: BEGIN_UNLOCK_EVENT.to_string(),
+        reason: format!("invalid period_lock_id: {}", lock_id),
+    })?;
+
+    Ok(UnlockingInfo {
+        lock_id,
+        owner: event_attribute(&events, BEGIN_UNLOCK_EVENT, "owner")?,
+        duration: event_attribute(&events, BEGIN_UNLOCK_EVENT, "duration")?,
+        unlock_time: event_attribute(&events, BEGIN_UNLOCK_EVENT, "unlock_time")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::SubMsgResult;
+
+    use super::*;
+
+    fn reply(data: Option<Binary>, events: Vec<Event>) -> Reply {
+        Reply {
+            id: 123,
+            result: SubMsgResult::Ok(SubMsgResponse { events, data }),
+        }
+    }
+
+    #[test]
+    fn test_parse_lock_tokens_reply() {
+        let data = MsgLockTokensResponse { id: 42 }.encode_to_vec();
+        assert_eq!(
+            parse_lock_tokens_reply(reply(Some(data.into()), vec![])).unwrap(),
+            42
+        );
+
+        assert_eq!(
+            parse_lock_tokens_reply(reply(None, vec![])).unwrap_err(),
+            CwDexError::NoReplyData {}
+        );
+
+        let failed = Reply {
+            id: 123,
+            result: SubMsgResult::Err("out of gas".to_string()),
+        };
+        assert_eq!(
+            parse_lock_tokens_reply(failed).unwrap_err(),
+            CwDexError::SubMsgFailed {
+                reason: "out of gas".to_string()
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "osmosis-cl")]
+    fn test_parse_create_position_reply() {
+        let response = MsgCreatePositionResponse {
+            position_id: 42,
+            amount0: "1000".to_string(),
+            amount1: "2000".to_string(),
+            liquidity_created: "1414213562373095048801".to_string(),
+            lower_tick: -100,
+            upper_tick: 100,
+        };
+        let data = response.encode_to_vec();
+        assert_eq!(
+            parse_create_position_reply(reply(Some(data.into()), vec![])).unwrap(),
+            response
+        );
+
+        assert!(matches!(
+            parse_create_position_reply(reply(Some(Binary::from(b"invalid")), vec![])),
+            Err(CwDexError::ReplyDecode { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_begin_unlocking_reply() {
+        let event = Event::new(BEGIN_UNLOCK_EVENT)
+            .add_attribute("period_lock_id", "7")
+            .add_attribute("owner", "osmo1owner")
+            .add_attribute("duration", "336h0m0s")
+            .add_attribute("unlock_time", "2023-01-15 00:00:00 +0000 UTC");
+
+        assert_eq!(
+            parse_begin_unlocking_reply(reply(None, vec![event])).unwrap(),
+            UnlockingInfo {
+                lock_id: 7,
+                owner: "osmo1owner".to_string(),
+                duration: "336h0m0s".to_string(),
+                unlock_time: "2023-01-15 00:00:00 +0000 UTC".to_string(),
+            }
+        );
+
+        assert_eq!(
+            parse_begin_unlocking_reply(reply(None, vec![])).unwrap_err(),
+            CwDexError::MissingReplyAttribute {
+                event: BEGIN_UNLOCK_EVENT.to_string(),
+                attribute: "period_lock_id".to_string(),
+            }
+        );
+    }
+}
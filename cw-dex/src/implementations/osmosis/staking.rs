@@ -13,10 +13,15 @@ use osmosis_std::types::osmosis::superfluid::{
 };
 use std::time::Duration;
 
-use crate::traits::{ForceUnlock, LockedStaking, Rewards, Stake, Unlock};
+use crate::implementations::reply_ids::ReplyIds;
+use crate::traits::{ForceUnlock, LockedStaking, Rewards, Stake, UnbondingPosition, Unlock};
 use crate::CwDexError;
 
-use super::helpers::ToProtobufDuration;
+use super::helpers::{LockupState, ToProtobufDuration};
+
+pub use crate::implementations::reply_ids::{
+    OSMOSIS_LOCK_TOKENS_REPLY_ID, OSMOSIS_UNLOCK_TOKENS_REPLY_ID,
+};
 
 /// Implementation of locked staking on osmosis. Using the Staking trait.
 /// `lockup_duration` is the duration of the lockup period in nano seconds.
@@ -29,6 +34,9 @@ pub struct OsmosisStaking {
     pub lock_id: Option<u64>,
     /// Denomination of the associated LP token
     pub lp_token_denom: String,
+    /// Reply IDs of the submessages sent by this implementation
+    #[serde(default)]
+    pub reply_ids: ReplyIds,
 }
 
 impl OsmosisStaking {
@@ -55,14 +63,16 @@ impl OsmosisStaking {
             lockup_duration: Duration::from_secs(lockup_duration),
             lock_id,
             lp_token_denom,
+            reply_ids: ReplyIds::default(),
         })
     }
-}
 
-/// Reply ID for locking tokens
-pub const OSMOSIS_LOCK_TOKENS_REPLY_ID: u64 = 123;
-/// Reply ID for unlocking tokens
-pub const OSMOSIS_UNLOCK_TOKENS_REPLY_ID: u64 = 124;
+    /// Returns a copy of the instance that sends submessages with the given
+    /// reply IDs instead of the default ones.
+    pub fn with_reply_ids(self, reply_ids: ReplyIds) -> Self {
+        Self { reply_ids, ..self }
+    }
+}
 
 impl Rewards for OsmosisStaking {
     fn claim_rewards(&self, _deps: Deps, _env: &Env) -> Result<Response, CwDexError> {
@@ -104,13 +114,21 @@ impl Stake for OsmosisStaking {
 
         Ok(Response::new()
             .add_submessage(SubMsg {
-                id: OSMOSIS_LOCK_TOKENS_REPLY_ID,
+                id: self.reply_ids.lock_tokens,
                 msg: stake_msg.into(),
                 gas_limit: None,
                 reply_on: ReplyOn::Success,
             })
             .add_event(event))
     }
+
+    fn query_staked_balance(
+        &self,
+        querier: &QuerierWrapper,
+        user: &Addr,
+    ) -> Result<Uint128, CwDexError> {
+        Ok(LockupState::query(querier, user, &self.lp_token_denom, self.lockup_duration)?.locked)
+    }
 }
 
 impl Unlock for OsmosisStaking {
@@ -138,7 +156,7 @@ impl Unlock for OsmosisStaking {
 
         Ok(Response::new()
             .add_submessage(SubMsg {
-                id: OSMOSIS_UNLOCK_TOKENS_REPLY_ID,
+                id: self.reply_ids.unlock_tokens,
                 msg: unstake_msg.into(),
                 gas_limit: None,
                 reply_on: ReplyOn::Success,
@@ -155,6 +173,17 @@ impl Unlock for OsmosisStaking {
         // Osmosis automatically sends the unlocked tokens after the lockup duration
         Ok(Response::new())
     }
+
+    fn query_unbonding_positions(
+        &self,
+        querier: &QuerierWrapper,
+        user: &Addr,
+    ) -> Result<Vec<UnbondingPosition>, CwDexError> {
+        Ok(
+            LockupState::query(querier, user, &self.lp_token_denom, self.lockup_duration)?
+                .unlocking,
+        )
+    }
 }
 
 impl LockedStaking for OsmosisStaking {
@@ -196,6 +225,8 @@ pub struct OsmosisSuperfluidStaking {
     validator_address: Addr,
     lock_id: Option<u64>,
     lp_token_denom: String,
+    #[serde(default)]
+    reply_ids: ReplyIds,
 }
 
 const TWO_WEEKS_IN_SECS: u64 = 14 * 24 * 60 * 60;
@@ -216,8 +247,15 @@ impl OsmosisSuperfluidStaking {
             validator_address,
             lock_id,
             lp_token_denom,
+            reply_ids: ReplyIds::default(),
         })
     }
+
+    /// Returns a copy of the instance that sends submessages with the given
+    /// reply IDs instead of the default ones.
+    pub fn with_reply_ids(self, reply_ids: ReplyIds) -> Self {
+        Self { reply_ids, ..self }
+    }
 }
 
 impl Rewards for OsmosisSuperfluidStaking {
@@ -257,13 +295,27 @@ impl Stake for OsmosisSuperfluidStaking {
 
         Ok(Response::new()
             .add_submessage(SubMsg {
-                id: OSMOSIS_LOCK_TOKENS_REPLY_ID,
+                id: self.reply_ids.lock_tokens,
                 msg: stake_msg.into(),
                 gas_limit: None,
                 reply_on: ReplyOn::Success,
             })
             .add_event(event))
     }
+
+    fn query_staked_balance(
+        &self,
+        querier: &QuerierWrapper,
+        user: &Addr,
+    ) -> Result<Uint128, CwDexError> {
+        Ok(LockupState::query(
+            querier,
+            user,
+            &self.lp_token_denom,
+            Duration::from_secs(TWO_WEEKS_IN_SECS),
+        )?
+        .locked)
+    }
 }
 
 impl Unlock for OsmosisSuperfluidStaking {
@@ -294,6 +346,20 @@ impl Unlock for OsmosisSuperfluidStaking {
         // Osmosis automatically sends the unlocked tokens after the lockup duration
         Ok(Response::new())
     }
+
+    fn query_unbonding_positions(
+        &self,
+        querier: &QuerierWrapper,
+        user: &Addr,
+    ) -> Result<Vec<UnbondingPosition>, CwDexError> {
+        Ok(LockupState::query(
+            querier,
+            user,
+            &self.lp_token_denom,
+            Duration::from_secs(TWO_WEEKS_IN_SECS),
+        )?
+        .unlocking)
+    }
 }
 
 impl LockedStaking for OsmosisSuperfluidStaking {
@@ -302,3 +368,43 @@ impl LockedStaking for OsmosisSuperfluidStaking {
         Ok(CwDuration::Time(TWO_WEEKS_IN_SECS))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::from_slice;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+
+    use super::*;
+
+    #[test]
+    fn test_deserialize_without_reply_ids() {
+        let staking: OsmosisStaking = from_slice(
+            br#"{"lockup_duration":{"secs":86400,"nanos":0},"lock_id":null,"lp_token_denom":"gamm/pool/1"}"#,
+        )
+        .unwrap();
+        assert_eq!(staking.reply_ids, ReplyIds::default());
+    }
+
+    #[test]
+    fn test_stake_with_reply_ids() {
+        let reply_ids = ReplyIds {
+            lock_tokens: 1,
+            unlock_tokens: 2,
+            ..ReplyIds::default()
+        };
+        let staking = OsmosisStaking::new(86400, Some(3), "gamm/pool/1".to_string())
+            .unwrap()
+            .with_reply_ids(reply_ids);
+        let deps = mock_dependencies();
+
+        let res = staking
+            .stake(deps.as_ref(), &mock_env(), Uint128::one())
+            .unwrap();
+        assert_eq!(res.messages[0].id, 1);
+
+        let res = staking
+            .unlock(deps.as_ref(), &mock_env(), Uint128::one())
+            .unwrap();
+        assert_eq!(res.messages[0].id, 2);
+    }
+}
@@ -2,16 +2,28 @@
 //! For use in serialization.
 
 use crate::error::CwDexError;
-use crate::traits::pool::Pool as PoolTrait;
+use crate::response::CwDexResponse;
+use crate::traits::pool::{Pool as PoolTrait, PoolParams, SwapSimulation};
+use crate::traits::price_oracle::{CumulativePricesSnapshot, PriceOracle};
+use crate::traits::staking::Rewards;
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Deps, Env, Response, StdResult, Uint128};
+use cosmwasm_std::{Decimal, Deps, Env, StdResult, Uint128};
 use cw_asset::{Asset, AssetInfo, AssetList};
 
 #[cfg(feature = "astroport")]
 use crate::astroport::AstroportPool;
 
 #[cfg(feature = "osmosis")]
-use {crate::implementations::osmosis::OsmosisPool, cw_asset::AssetInfoBase, std::str::FromStr};
+use {crate::implementations::osmosis::OsmosisPool, std::str::FromStr};
+
+#[cfg(feature = "osmosis-cl")]
+use crate::implementations::osmosis::OsmosisClPool;
+
+#[cfg(any(feature = "astroport", feature = "junoswap", feature = "osmosis"))]
+use cw_asset::AssetInfoBase;
+
+#[cfg(any(feature = "astroport", feature = "junoswap"))]
+use cw20::{Cw20QueryMsg, MinterResponse};
 
 #[cfg(feature = "junoswap")]
 use crate::junoswap::JunoswapPool;
@@ -27,6 +39,9 @@ pub enum Pool {
     /// Contains an Osmosis pool implementation
     #[cfg(feature = "osmosis")]
     Osmosis(OsmosisPool),
+    /// Contains an Osmosis concentrated liquidity pool implementation
+    #[cfg(feature = "osmosis-cl")]
+    OsmosisCl(OsmosisClPool),
     /// Contains an Junoswap pool implementation
     #[cfg(feature = "junoswap")]
     Junoswap(JunoswapPool),
@@ -43,6 +58,28 @@ impl Pool {
         match self {
             #[cfg(feature = "osmosis")]
             Pool::Osmosis(x) => Box::new(*x),
+            #[cfg(feature = "osmosis-cl")]
+            Pool::OsmosisCl(x) => Box::new(*x),
+            #[cfg(feature = "junoswap")]
+            Pool::Junoswap(x) => Box::new(x.clone()),
+            #[cfg(feature = "astroport")]
+            Pool::Astroport(x) => Box::new(x.clone()),
+            _ => {
+                panic!("Pool variant not supported");
+            }
+        }
+    }
+
+    /// Returns a specific `Pool` instance as a boxed generic `PriceOracle`
+    /// trait
+    pub fn as_price_oracle(&self) -> Box<dyn PriceOracle> {
+        // This is needed to avoid a warning when compiling with all features
+        #[allow(unreachable_patterns)]
+        match self {
+            #[cfg(feature = "osmosis")]
+            Pool::Osmosis(x) => Box::new(*x),
+            #[cfg(feature = "osmosis-cl")]
+            Pool::OsmosisCl(x) => Box::new(*x),
             #[cfg(feature = "junoswap")]
             Pool::Junoswap(x) => Box::new(x.clone()),
             #[cfg(feature = "astroport")]
@@ -53,8 +90,27 @@ impl Pool {
         }
     }
 
+    /// Returns a specific `Pool` instance as a generic `Rewards` trait object,
+    /// or an error if the pool accrues no rewards to its liquidity providers
+    /// that must be claimed.
+    pub fn as_rewards(&self) -> Result<&dyn Rewards, CwDexError> {
+        // This is needed to avoid a warning when compiling with all features
+        #[allow(unreachable_patterns)]
+        match self {
+            #[cfg(feature = "osmosis-cl")]
+            Pool::OsmosisCl(x) => Ok(x),
+            _ => Err(CwDexError::UnsupportedPoolOperation {
+                operation: "claim_rewards".to_string(),
+            }),
+        }
+    }
+
     /// Returns the matching pool given a LP token.
     ///
+    /// Native LP tokens are resolved from their `gamm/pool/N` denom. For cw20
+    /// LP tokens the minter of the token is queried and verified to be an
+    /// Astroport pair or Junoswap pool with the token as its LP token.
+    ///
     /// Arguments:
     /// - `lp_token`: Said LP token
     pub fn get_pool_for_lp_token(deps: Deps, lp_token: &AssetInfo) -> Result<Self, CwDexError> {
@@ -74,6 +130,38 @@ impl Pool {
 
                 Ok(Pool::Osmosis(OsmosisPool::new(pool_id, deps)?))
             }
+            #[cfg(any(feature = "astroport", feature = "junoswap"))]
+            AssetInfoBase::Cw20(lp_token_addr) => {
+                // Both Astroport pairs and Junoswap pools are the minter of
+                // their LP token
+                let minter = deps
+                    .querier
+                    .query_wasm_smart::<Option<MinterResponse>>(
+                        lp_token_addr,
+                        &Cw20QueryMsg::Minter {},
+                    )?
+                    .ok_or(CwDexError::NotLpToken {})?;
+                let pool_addr = deps.api.addr_validate(&minter.minter)?;
+
+                #[cfg(feature = "astroport")]
+                if let Ok(pool) = AstroportPool::new(deps, pool_addr.clone()) {
+                    if &pool.lp_token_addr == lp_token_addr {
+                        return Ok(Pool::Astroport(pool));
+                    }
+                }
+
+                #[cfg(feature = "junoswap")]
+                {
+                    let pool = JunoswapPool::new(pool_addr, lp_token_addr.clone());
+                    if let Ok(info) = pool.query_info(&deps.querier) {
+                        if info.lp_token_address == lp_token_addr.as_str() {
+                            return Ok(Pool::Junoswap(pool));
+                        }
+                    }
+                }
+
+                Err(CwDexError::NotLpToken {})
+            }
             _ => Err(CwDexError::NotLpToken {}),
         }
     }
@@ -88,18 +176,42 @@ impl PoolTrait for Pool {
         env: &Env,
         assets: AssetList,
         min_out: Uint128,
-    ) -> Result<Response, CwDexError> {
+    ) -> Result<CwDexResponse, CwDexError> {
         self.as_trait()
             .provide_liquidity(deps, env, assets, min_out)
     }
 
+    fn zap_in(
+        &self,
+        deps: Deps,
+        env: &Env,
+        asset: Asset,
+        min_out: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        self.as_trait().zap_in(deps, env, asset, min_out)
+    }
+
     fn withdraw_liquidity(
         &self,
         deps: Deps,
         env: &Env,
         asset: Asset,
-    ) -> Result<Response, CwDexError> {
-        self.as_trait().withdraw_liquidity(deps, env, asset)
+        min_out: AssetList,
+    ) -> Result<CwDexResponse, CwDexError> {
+        self.as_trait()
+            .withdraw_liquidity(deps, env, asset, min_out)
+    }
+
+    fn zap_out(
+        &self,
+        deps: Deps,
+        env: &Env,
+        lp_token: Asset,
+        ask_asset_info: AssetInfo,
+        min_out: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        self.as_trait()
+            .zap_out(deps, env, lp_token, ask_asset_info, min_out)
     }
 
     fn swap(
@@ -109,11 +221,23 @@ impl PoolTrait for Pool {
         offer_asset: Asset,
         ask_asset_info: AssetInfo,
         min_out: Uint128,
-    ) -> Result<Response, CwDexError> {
+    ) -> Result<CwDexResponse, CwDexError> {
         self.as_trait()
             .swap(deps, env, offer_asset, ask_asset_info, min_out)
     }
 
+    fn swap_exact_out(
+        &self,
+        deps: Deps,
+        env: &Env,
+        ask_asset: Asset,
+        offer_asset_info: AssetInfo,
+        max_in: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        self.as_trait()
+            .swap_exact_out(deps, env, ask_asset, offer_asset_info, max_in)
+    }
+
     fn get_pool_liquidity(&self, deps: Deps) -> Result<AssetList, CwDexError> {
         self.as_trait().get_pool_liquidity(deps)
     }
@@ -127,6 +251,10 @@ impl PoolTrait for Pool {
         self.as_trait().simulate_provide_liquidity(deps, env, asset)
     }
 
+    fn simulate_zap_in(&self, deps: Deps, env: &Env, asset: Asset) -> Result<Asset, CwDexError> {
+        self.as_trait().simulate_zap_in(deps, env, asset)
+    }
+
     fn simulate_withdraw_liquidity(
         &self,
         deps: Deps,
@@ -135,15 +263,68 @@ impl PoolTrait for Pool {
         self.as_trait().simulate_withdraw_liquidity(deps, asset)
     }
 
+    fn simulate_zap_out(
+        &self,
+        deps: Deps,
+        lp_token: &Asset,
+        ask_asset_info: AssetInfo,
+    ) -> Result<Asset, CwDexError> {
+        self.as_trait()
+            .simulate_zap_out(deps, lp_token, ask_asset_info)
+    }
+
     fn simulate_swap(
         &self,
         deps: Deps,
         offer_asset: Asset,
         ask_asset_info: AssetInfo,
-        sender: Option<String>,
     ) -> StdResult<Uint128> {
         self.as_trait()
-            .simulate_swap(deps, offer_asset, ask_asset_info, sender)
+            .simulate_swap(deps, offer_asset, ask_asset_info)
+    }
+
+    fn simulate_swap_detailed(
+        &self,
+        deps: Deps,
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+    ) -> StdResult<SwapSimulation> {
+        self.as_trait()
+            .simulate_swap_detailed(deps, offer_asset, ask_asset_info)
+    }
+
+    fn spot_price(
+        &self,
+        deps: Deps,
+        env: &Env,
+        base_asset: &AssetInfo,
+        quote_asset: &AssetInfo,
+    ) -> StdResult<Decimal> {
+        self.as_trait()
+            .spot_price(deps, env, base_asset, quote_asset)
+    }
+
+    fn pool_params(&self, deps: Deps, env: &Env) -> Result<PoolParams, CwDexError> {
+        self.as_trait().pool_params(deps, env)
+    }
+
+    fn fair_lp_token_price(
+        &self,
+        deps: Deps,
+        env: &Env,
+        prices: &[(AssetInfo, Decimal)],
+    ) -> Result<Decimal, CwDexError> {
+        self.as_trait().fair_lp_token_price(deps, env, prices)
+    }
+
+    fn simulate_swap_exact_out(
+        &self,
+        deps: Deps,
+        ask_asset: Asset,
+        offer_asset_info: AssetInfo,
+    ) -> StdResult<Uint128> {
+        self.as_trait()
+            .simulate_swap_exact_out(deps, ask_asset, offer_asset_info)
     }
 
     fn lp_token(&self) -> AssetInfo {
@@ -154,3 +335,217 @@ impl PoolTrait for Pool {
         self.as_trait().pool_assets(deps)
     }
 }
+
+impl PriceOracle for Pool {
+    fn query_cumulative_prices(
+        &self,
+        deps: Deps,
+        env: &Env,
+    ) -> Result<CumulativePricesSnapshot, CwDexError> {
+        self.as_price_oracle().query_cumulative_prices(deps, env)
+    }
+
+    fn query_twap_price(
+        &self,
+        deps: Deps,
+        env: &Env,
+        base_asset_info: &AssetInfo,
+        quote_asset_info: &AssetInfo,
+        window: u64,
+        snapshots: &[CumulativePricesSnapshot],
+    ) -> Result<Decimal, CwDexError> {
+        self.as_price_oracle().query_twap_price(
+            deps,
+            env,
+            base_asset_info,
+            quote_asset_info,
+            window,
+            snapshots,
+        )
+    }
+}
+
+#[cfg(all(test, feature = "junoswap"))]
+mod tests {
+    use cosmwasm_std::testing::mock_dependencies;
+    use cosmwasm_std::{
+        from_binary, to_binary, Addr, Binary, ContractResult, SystemResult, WasmQuery,
+    };
+    use cw20_0_10_3::Denom;
+    use wasmswap::msg::{InfoResponse, QueryMsg};
+
+    use super::*;
+
+    const PAIR: &str = "pair";
+    const LP_TOKEN: &str = "lp_token";
+    const OTHER_TOKEN: &str = "other_token";
+
+    fn mock_querier(query: &WasmQuery) -> SystemResult<ContractResult<Binary>> {
+        match query {
+            WasmQuery::Smart { contract_addr, msg } => {
+                let res = match contract_addr.as_str() {
+                    LP_TOKEN | OTHER_TOKEN => match from_binary(msg).unwrap() {
+                        Cw20QueryMsg::Minter {} => to_binary(&Some(MinterResponse {
+                            minter: PAIR.to_string(),
+                            cap: None,
+                        })),
+                        _ => panic!("unexpected query"),
+                    },
+                    PAIR => match from_binary(msg) {
+                        Ok(QueryMsg::Info {}) => to_binary(&InfoResponse {
+                            token1_reserve: Uint128::new(1_000_000),
+                            token1_denom: Denom::Native("uatom".to_string()),
+                            token2_reserve: Uint128::new(1_000_000),
+                            token2_denom: Denom::Native("ujuno".to_string()),
+                            lp_token_supply: Uint128::new(1_000_000),
+                            lp_token_address: LP_TOKEN.to_string(),
+                            owner: None,
+                            lp_fee_percent: Default::default(),
+                            protocol_fee_percent: Default::default(),
+                            protocol_fee_recipient: "protocol".to_string(),
+                        }),
+                        _ => {
+                            return SystemResult::Ok(ContractResult::Err(
+                                "unknown query".to_string(),
+                            ))
+                        }
+                    },
+                    _ => panic!("unexpected contract"),
+                };
+                SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+            }
+            _ => panic!("unexpected query"),
+        }
+    }
+
+    #[test]
+    fn test_get_pool_for_cw20_lp_token() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(mock_querier);
+
+        let pool =
+            Pool::get_pool_for_lp_token(deps.as_ref(), &AssetInfo::cw20(Addr::unchecked(LP_TOKEN)))
+                .unwrap();
+        assert_eq!(
+            pool,
+            Pool::Junoswap(JunoswapPool::new(
+                Addr::unchecked(PAIR),
+                Addr::unchecked(LP_TOKEN)
+            ))
+        );
+
+        // A token minted by a pool that does not use it as its LP token
+        let err = Pool::get_pool_for_lp_token(
+            deps.as_ref(),
+            &AssetInfo::cw20(Addr::unchecked(OTHER_TOKEN)),
+        )
+        .unwrap_err();
+        assert_eq!(err, CwDexError::NotLpToken {});
+    }
+}
+
+#[cfg(all(test, feature = "astroport"))]
+mod astroport_tests {
+    use cosmwasm_std::testing::mock_dependencies;
+    use cosmwasm_std::{
+        from_binary, to_binary, Addr, Binary, ContractResult, StdError, SystemResult, WasmQuery,
+    };
+    use cw_asset::astroport::AstroAssetInfo;
+
+    use super::*;
+    use crate::implementations::astroport::msg::{PairInfo, PairQueryMsg, PairType};
+
+    /// Answers the cw20 `Minter` query of "lp_token", minted by "pair", and of
+    /// "fake_lp_token", minted by "evil_pair". Both pairs claim to be "pair"
+    /// with their token as LP token.
+    fn mock_querier(query: &WasmQuery) -> SystemResult<ContractResult<Binary>> {
+        let (contract_addr, msg) = match query {
+            WasmQuery::Smart { contract_addr, msg } => (contract_addr.as_str(), msg),
+            _ => panic!("unexpected query"),
+        };
+        let res = match contract_addr {
+            "lp_token" | "fake_lp_token" => match from_binary(msg).unwrap() {
+                Cw20QueryMsg::Minter {} => to_binary(&Some(MinterResponse {
+                    minter: if contract_addr == "lp_token" {
+                        "pair".to_string()
+                    } else {
+                        "evil_pair".to_string()
+                    },
+                    cap: None,
+                })),
+                _ => panic!("unexpected query"),
+            },
+            "pair" | "evil_pair" => match from_binary(msg) {
+                Ok(PairQueryMsg::Pair {}) => to_binary(&PairInfo {
+                    asset_infos: vec![
+                        AstroAssetInfo::NativeToken {
+                            denom: "uatom".to_string(),
+                        },
+                        AstroAssetInfo::NativeToken {
+                            denom: "uluna".to_string(),
+                        },
+                    ],
+                    contract_addr: Addr::unchecked("pair"),
+                    liquidity_token: Addr::unchecked(if contract_addr == "pair" {
+                        "lp_token"
+                    } else {
+                        "fake_lp_token"
+                    }),
+                    pair_type: PairType::Xyk {},
+                }),
+                _ => return SystemResult::Ok(ContractResult::Err("unknown query".to_string())),
+            },
+            _ => return SystemResult::Ok(ContractResult::Err("not a token".to_string())),
+        };
+        SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+    }
+
+    #[test]
+    fn test_get_pool_for_cw20_lp_token() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(mock_querier);
+
+        let pool = Pool::get_pool_for_lp_token(
+            deps.as_ref(),
+            &AssetInfo::cw20(Addr::unchecked("lp_token")),
+        )
+        .unwrap();
+        assert_eq!(
+            pool,
+            Pool::Astroport(AstroportPool {
+                pair_addr: Addr::unchecked("pair"),
+                lp_token_addr: Addr::unchecked("lp_token"),
+                pool_assets: vec![AssetInfo::native("uatom"), AssetInfo::native("uluna")],
+                pair_type: PairType::Xyk {},
+            })
+        );
+    }
+
+    #[test]
+    fn test_get_pool_for_lp_token_malicious_minter() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(mock_querier);
+
+        // The minter claims to be another pair that uses the token as its LP
+        // token
+        let err = Pool::get_pool_for_lp_token(
+            deps.as_ref(),
+            &AssetInfo::cw20(Addr::unchecked("fake_lp_token")),
+        )
+        .unwrap_err();
+        assert_eq!(err, CwDexError::NotLpToken {});
+    }
+
+    #[test]
+    fn test_get_pool_for_lp_token_minter_query_error() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(mock_querier);
+
+        let err = Pool::get_pool_for_lp_token(
+            deps.as_ref(),
+            &AssetInfo::cw20(Addr::unchecked("not_a_token")),
+        )
+        .unwrap_err();
+        assert!(matches!(err, CwDexError::Std(StdError::GenericErr { .. })));
+    }
+}
@@ -0,0 +1,65 @@
+//! Contains the reply IDs of the submessages sent by the pool and staking
+//! implementations.
+
+use cosmwasm_schema::cw_serde;
+
+/// Reply ID for locking tokens
+pub const OSMOSIS_LOCK_TOKENS_REPLY_ID: u64 = 123;
+/// Reply ID for unlocking tokens
+pub const OSMOSIS_UNLOCK_TOKENS_REPLY_ID: u64 = 124;
+/// Reply ID for creating a concentrated liquidity position
+pub const OSMOSIS_CREATE_POSITION_REPLY_ID: u64 = 125;
+
+/// Reply IDs of the submessages sent by the pool and staking implementations,
+/// configurable so that they don't clash with the reply IDs of the calling
+/// contract. Each implementation only uses the IDs of the submessages it
+/// sends.
+///
+/// Missing fields are set to their defaults when deserializing, so
+/// implementations serialized before reply IDs were configurable keep using
+/// the default IDs.
+#[cw_serde]
+#[derive(Copy)]
+#[serde(default)]
+pub struct ReplyIds {
+    /// Reply ID of the submessage locking tokens, sent by `stake` of
+    /// `OsmosisStaking` and `OsmosisSuperfluidStaking`. Defaults to
+    /// [`OSMOSIS_LOCK_TOKENS_REPLY_ID`].
+    pub lock_tokens: u64,
+    /// Reply ID of the submessage unlocking tokens, sent by `unlock` of
+    /// `OsmosisStaking`. Defaults to [`OSMOSIS_UNLOCK_TOKENS_REPLY_ID`].
+    pub unlock_tokens: u64,
+    /// Reply ID of the submessage creating a position, sent by
+    /// `provide_liquidity` of `OsmosisClPool`. Defaults to
+    /// [`OSMOSIS_CREATE_POSITION_REPLY_ID`].
+    pub create_position: u64,
+}
+
+impl Default for ReplyIds {
+    fn default() -> Self {
+        Self {
+            lock_tokens: OSMOSIS_LOCK_TOKENS_REPLY_ID,
+            unlock_tokens: OSMOSIS_UNLOCK_TOKENS_REPLY_ID,
+            create_position: OSMOSIS_CREATE_POSITION_REPLY_ID,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::from_slice;
+
+    use super::*;
+
+    #[test]
+    fn test_deserialize_partial_reply_ids() {
+        let reply_ids: ReplyIds = from_slice(br#"{"create_position":1}"#).unwrap();
+        assert_eq!(
+            reply_ids,
+            ReplyIds {
+                create_position: 1,
+                ..ReplyIds::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,777 @@
+//! Contains types for multi-hop swaps through the pools of the `Pool` enum.
+//! For use in serialization.
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Deps, Env, Event, StdResult, Uint128};
+use cw_asset::{Asset, AssetInfo};
+
+use crate::error::CwDexError;
+use crate::implementations::pool::Pool;
+use crate::response::CwDexResponse;
+use crate::traits::pool::Pool as PoolTrait;
+use crate::traits::router::Router;
+
+#[cfg(feature = "osmosis")]
+use {
+    apollo_utils::assets::{assert_native_asset_info, assert_native_coin},
+    osmosis_std::types::osmosis::gamm::v1beta1::{MsgSwapExactAmountIn, SwapAmountInRoute},
+};
+
+/// A single hop of a [`SwapRoute`], swapping `offer_asset_info` for
+/// `ask_asset_info` in `pool`.
+#[cw_serde]
+pub struct SwapOperation {
+    /// The pool to swap in
+    pub pool: Pool,
+    /// The asset offered in this hop
+    pub offer_asset_info: AssetInfo,
+    /// The asset received from this hop
+    pub ask_asset_info: AssetInfo,
+}
+
+impl SwapOperation {
+    /// Returns the pool id if the operation swaps in an Osmosis gamm pool.
+    fn osmosis_pool_id(&self) -> Option<u64> {
+        // This is needed to avoid a warning when compiling with all features
+        #[allow(unreachable_patterns)]
+        match &self.pool {
+            #[cfg(feature = "osmosis")]
+            Pool::Osmosis(pool) => Some(pool.pool_id()),
+            _ => None,
+        }
+    }
+}
+
+/// A route of one or more [`SwapOperation`]s, where the ask asset of each
+/// operation is the offer asset of the next.
+///
+/// Consecutive operations on Osmosis gamm pools are executed as a single
+/// `MsgSwapExactAmountIn` with chained `SwapAmountInRoute`s. All other
+/// operations are executed as sequential swap messages, where the amount
+/// offered in each hop is the simulated output of the previous hop. Each
+/// intermediate hop must return at least that simulated output, so that a hop
+/// returning less reverts the route instead of the next hop offering funds the
+/// contract did not receive. The caller's minimum output is enforced on the
+/// last hop.
+#[cw_serde]
+pub struct SwapRoute(pub Vec<SwapOperation>);
+
+impl SwapRoute {
+    /// Creates a new `SwapRoute` and validates that the operations are
+    /// connected.
+    pub fn new(operations: Vec<SwapOperation>) -> Result<Self, CwDexError> {
+        let route = Self(operations);
+        route.validate()?;
+        Ok(route)
+    }
+
+    /// Returns the operations of the route
+    pub fn operations(&self) -> &[SwapOperation] {
+        &self.0
+    }
+
+    /// Validates that the route is not empty and that the ask asset of each
+    /// operation is the offer asset of the next.
+    pub fn validate(&self) -> Result<(), CwDexError> {
+        if self.0.is_empty() {
+            return Err(CwDexError::InvalidSwapRoute {
+                reason: "route must contain at least one operation".to_string(),
+            });
+        }
+
+        for (prev, next) in self.0.iter().zip(self.0.iter().skip(1)) {
+            if prev.ask_asset_info != next.offer_asset_info {
+                return Err(CwDexError::InvalidSwapRoute {
+                    reason: format!(
+                        "ask asset {} does not match offer asset {} of the next operation",
+                        prev.ask_asset_info, next.offer_asset_info
+                    ),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Groups the operations into hops that are executed with a single
+    /// message. Consecutive Osmosis gamm operations are grouped together, all
+    /// other operations are executed one by one.
+    fn hops(&self) -> Vec<Vec<&SwapOperation>> {
+        let mut hops: Vec<Vec<&SwapOperation>> = vec![];
+        for op in &self.0 {
+            match hops.last_mut() {
+                Some(hop)
+                    if op.osmosis_pool_id().is_some() && hop[0].osmosis_pool_id().is_some() =>
+                {
+                    hop.push(op)
+                }
+                _ => hops.push(vec![op]),
+            }
+        }
+        hops
+    }
+
+    /// Validates the route and that `offer_asset` is the offer asset of the
+    /// first operation.
+    fn assert_offer_asset(&self, offer_asset: &Asset) -> Result<(), CwDexError> {
+        self.validate()?;
+        if offer_asset.info != self.0[0].offer_asset_info {
+            return Err(CwDexError::InvalidInAsset {
+                a: offer_asset.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Simulates all operations of a hop and returns the output of the last one.
+fn simulate_hop(deps: Deps, hop: &[&SwapOperation], offer_amount: Uint128) -> StdResult<Uint128> {
+    hop.iter().try_fold(offer_amount, |amount, op| {
+        op.pool.simulate_swap(
+            deps,
+            Asset::new(op.offer_asset_info.clone(), amount),
+            op.ask_asset_info.clone(),
+        )
+    })
+}
+
+/// Returns a `CwDexResponse` with the messages to execute all operations of a hop.
+fn swap_hop(
+    deps: Deps,
+    env: &Env,
+    hop: &[&SwapOperation],
+    offer_asset: Asset,
+    min_out: Uint128,
+) -> Result<CwDexResponse, CwDexError> {
+    #[cfg(feature = "osmosis")]
+    {
+        if hop[0].osmosis_pool_id().is_some() {
+            let routes = hop
+                .iter()
+                .filter_map(|op| op.osmosis_pool_id().map(|pool_id| (pool_id, op)))
+                .map(|(pool_id, op)| {
+                    Ok(SwapAmountInRoute {
+                        pool_id,
+                        token_out_denom: assert_native_asset_info(&op.ask_asset_info)?,
+                    })
+                })
+                .collect::<StdResult<Vec<_>>>()?;
+
+            // Min out must be greater than 0 for osmosis.
+            let swap_msg = MsgSwapExactAmountIn {
+                sender: env.contract.address.to_string(),
+                routes,
+                token_in: Some(assert_native_coin(&offer_asset)?.into()),
+                token_out_min_amount: min_out.max(Uint128::one()).to_string(),
+            };
+
+            return Ok(CwDexResponse::new().add_message(swap_msg));
+        }
+    }
+
+    let op = hop[0];
+    op.pool
+        .swap(deps, env, offer_asset, op.ask_asset_info.clone(), min_out)
+}
+
+impl Router for SwapRoute {
+    fn swap(
+        &self,
+        deps: Deps,
+        env: &Env,
+        offer_asset: Asset,
+        min_out: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        self.assert_offer_asset(&offer_asset)?;
+
+        let hops = self.hops();
+        let last_hop = hops.len() - 1;
+
+        let mut response = CwDexResponse::new();
+        let mut offer = offer_asset.clone();
+        for (i, hop) in hops.into_iter().enumerate() {
+            let expected_out = simulate_hop(deps, &hop, offer.amount)?;
+
+            // The output of the whole route is checked against the caller's
+            // `min_out` on the last hop. The next hop offers the simulated
+            // output of an intermediate hop, so the intermediate hop must
+            // return at least that amount.
+            let hop_min_out = if i == last_hop {
+                if expected_out < min_out {
+                    return Err(CwDexError::MinOutNotReceived {
+                        min_out,
+                        received: expected_out,
+                    });
+                }
+                min_out
+            } else {
+                expected_out
+            };
+
+            let ask_asset_info = hop[hop.len() - 1].ask_asset_info.clone();
+            let hop_response = swap_hop(deps, env, &hop, offer, hop_min_out)?;
+            response = response
+                .add_submessages(hop_response.messages)
+                .add_events(hop_response.events);
+
+            offer = Asset::new(ask_asset_info, expected_out);
+        }
+
+        let event = Event::new("apollo/cw-dex/swap_route")
+            .add_attribute("offer_asset", offer_asset.to_string())
+            .add_attribute("ask_asset_info", offer.info.to_string())
+            .add_attribute("expected_out", offer.amount)
+            .add_attribute("minimum_out_amount", min_out)
+            .add_attribute("operations", self.0.len().to_string());
+
+        Ok(response.add_event(event).with_expected_out(vec![offer]))
+    }
+
+    fn simulate_swap(&self, deps: Deps, offer_asset: Asset) -> StdResult<Uint128> {
+        self.assert_offer_asset(&offer_asset)?;
+
+        self.hops()
+            .iter()
+            .try_fold(offer_asset.amount, |amount, hop| {
+                simulate_hop(deps, hop, amount)
+            })
+    }
+
+    fn offer_asset_info(&self) -> Result<AssetInfo, CwDexError> {
+        self.validate()?;
+        Ok(self.0[0].offer_asset_info.clone())
+    }
+
+    fn ask_asset_info(&self) -> Result<AssetInfo, CwDexError> {
+        self.validate()?;
+        Ok(self.0[self.0.len() - 1].ask_asset_info.clone())
+    }
+}
+
+#[cfg(all(test, feature = "junoswap"))]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{
+        from_binary, to_binary, Addr, Binary, ContractResult, CosmosMsg, Decimal, SystemResult,
+        WasmMsg, WasmQuery,
+    };
+    use cw20_0_10_3::Denom;
+    use wasmswap::msg::{ExecuteMsg, InfoResponse, QueryMsg, Token1ForToken2PriceResponse};
+
+    use super::*;
+    use crate::implementations::junoswap::JunoswapPool;
+
+    /// Pools of uatom/ujuno and ujuno/uosmo
+    const POOLS: [(&str, &str, &str); 2] =
+        [("pool_a", "uatom", "ujuno"), ("pool_b", "ujuno", "uosmo")];
+
+    /// Swap on a constant product curve with reserves of 1_000_000 and a 0.3%
+    /// fee
+    fn get_price(input_amount: Uint128) -> Uint128 {
+        let input_with_fee = input_amount * Uint128::new(997);
+        input_with_fee * Uint128::new(1_000_000) / (Uint128::new(1_000_000_000) + input_with_fee)
+    }
+
+    fn mock_pools_querier(query: &WasmQuery) -> SystemResult<ContractResult<Binary>> {
+        let (contract_addr, msg) = match query {
+            WasmQuery::Smart { contract_addr, msg } => (contract_addr, msg),
+            _ => return SystemResult::Ok(ContractResult::Err("unexpected query".to_string())),
+        };
+        let (_, token1, token2) = match POOLS.iter().find(|(addr, ..)| addr == contract_addr) {
+            Some(pool) => pool,
+            None => return SystemResult::Ok(ContractResult::Err("unknown contract".to_string())),
+        };
+        let res = match from_binary(msg).unwrap() {
+            QueryMsg::Info {} => to_binary(&InfoResponse {
+                token1_reserve: Uint128::new(1_000_000),
+                token1_denom: Denom::Native(token1.to_string()),
+                token2_reserve: Uint128::new(1_000_000),
+                token2_denom: Denom::Native(token2.to_string()),
+                lp_token_supply: Uint128::new(1_000_000),
+                lp_token_address: format!("{}_lp_token", contract_addr),
+                owner: None,
+                // A total fee of 0.3%
+                lp_fee_percent: Decimal::permille(300),
+                protocol_fee_percent: Default::default(),
+                protocol_fee_recipient: "protocol".to_string(),
+            }),
+            QueryMsg::Token1ForToken2Price { token1_amount } => {
+                to_binary(&Token1ForToken2PriceResponse {
+                    token2_amount: get_price(token1_amount),
+                })
+            }
+            _ => return SystemResult::Ok(ContractResult::Err("unexpected query".to_string())),
+        };
+        SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+    }
+
+    fn junoswap_op(pool: &str, offer: &str, ask: &str) -> SwapOperation {
+        SwapOperation {
+            pool: Pool::Junoswap(JunoswapPool::new(
+                Addr::unchecked(pool),
+                Addr::unchecked(format!("{}_lp_token", pool)),
+            )),
+            offer_asset_info: AssetInfo::native(offer),
+            ask_asset_info: AssetInfo::native(ask),
+        }
+    }
+
+    fn route() -> SwapRoute {
+        SwapRoute::new(
+            POOLS
+                .iter()
+                .map(|(pool, offer, ask)| junoswap_op(pool, offer, ask))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    /// Returns the `input_amount` and `min_output` of each wasmswap swap
+    /// message in `response`
+    fn swap_amounts(response: &CwDexResponse) -> Vec<(Uint128, Uint128)> {
+        response
+            .messages
+            .iter()
+            .map(|sub_msg| match &sub_msg.msg {
+                CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => match from_binary(msg).unwrap() {
+                    ExecuteMsg::Swap {
+                        input_amount,
+                        min_output,
+                        ..
+                    } => (input_amount, min_output),
+                    _ => panic!("unexpected execute msg"),
+                },
+                _ => panic!("unexpected msg"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_new_validates_route() {
+        let err = SwapRoute::new(vec![]).unwrap_err();
+        assert!(matches!(err, CwDexError::InvalidSwapRoute { .. }));
+
+        let err = SwapRoute::new(vec![
+            junoswap_op("pool_a", "uatom", "ujuno"),
+            junoswap_op("pool_b", "uosmo", "ujuno"),
+        ])
+        .unwrap_err();
+        assert!(matches!(err, CwDexError::InvalidSwapRoute { .. }));
+
+        let route = route();
+        assert_eq!(
+            route.offer_asset_info().unwrap(),
+            AssetInfo::native("uatom")
+        );
+        assert_eq!(route.ask_asset_info().unwrap(), AssetInfo::native("uosmo"));
+    }
+
+    #[test]
+    #[cfg(feature = "osmosis")]
+    fn test_hops_groups_consecutive_osmosis_operations() {
+        use crate::implementations::osmosis::OsmosisPool;
+
+        let osmosis_op = |pool_id: u64, offer: &str, ask: &str| SwapOperation {
+            pool: Pool::Osmosis(OsmosisPool::unchecked(pool_id)),
+            offer_asset_info: AssetInfo::native(offer),
+            ask_asset_info: AssetInfo::native(ask),
+        };
+        let route = SwapRoute::new(vec![
+            osmosis_op(1, "uatom", "uosmo"),
+            osmosis_op(2, "uosmo", "ujuno"),
+            junoswap_op("pool_a", "ujuno", "uatom"),
+            junoswap_op("pool_b", "uatom", "uion"),
+            osmosis_op(3, "uion", "uosmo"),
+        ])
+        .unwrap();
+
+        let hop_lengths: Vec<usize> = route.hops().iter().map(|hop| hop.len()).collect();
+        assert_eq!(hop_lengths, vec![2, 1, 1, 1]);
+    }
+
+    #[test]
+    fn test_simulate_swap() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(mock_pools_querier);
+
+        let return_amount = route()
+            .simulate_swap(deps.as_ref(), Asset::native("uatom", 10_000u128))
+            .unwrap();
+        assert_eq!(return_amount, Uint128::new(9_745));
+
+        let err = route()
+            .simulate_swap(deps.as_ref(), Asset::native("ujuno", 10_000u128))
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid input asset"));
+    }
+
+    #[test]
+    fn test_swap_min_out() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(mock_pools_querier);
+        let env = mock_env();
+        let offer_asset = Asset::native("uatom", 10_000u128);
+
+        let err = route()
+            .swap(
+                deps.as_ref(),
+                &env,
+                offer_asset.clone(),
+                Uint128::new(9_746),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err,
+            CwDexError::MinOutNotReceived {
+                min_out: Uint128::new(9_746),
+                received: Uint128::new(9_745),
+            }
+        );
+
+        let response = route()
+            .swap(deps.as_ref(), &env, offer_asset, Uint128::new(9_700))
+            .unwrap();
+        assert_eq!(
+            response.expected_out,
+            vec![Asset::native("uosmo", 9_745u128)].into()
+        );
+        // The intermediate hop must return the 9_871 ujuno offered to the last
+        // hop, which is checked against the caller's min_out
+        assert_eq!(
+            swap_amounts(&response),
+            vec![
+                (Uint128::new(10_000), Uint128::new(9_871)),
+                (Uint128::new(9_871), Uint128::new(9_700)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_swap_hop_returning_less_than_simulated() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(mock_pools_querier);
+        let response = route()
+            .swap(
+                deps.as_ref(),
+                &mock_env(),
+                Asset::native("uatom", 10_000u128),
+                Uint128::zero(),
+            )
+            .unwrap();
+        let swaps = swap_amounts(&response);
+
+        // An earlier message in the same transaction swaps 1% of the ujuno
+        // reserve out of pool_a, so the first hop returns less than simulated.
+        let input_with_fee = swaps[0].0 * Uint128::new(997);
+        let received =
+            input_with_fee * Uint128::new(990_000) / (Uint128::new(1_010_000_000) + input_with_fee);
+        assert!(received < get_price(swaps[0].0));
+
+        // The first hop reverts instead of the second hop offering the
+        // shortfall out of the contract's ujuno balance.
+        assert!(received < swaps[0].1);
+        assert_eq!(swaps[0].1, swaps[1].0);
+    }
+
+    #[test]
+    fn test_zap_in_with_route() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(mock_pools_querier);
+        let env = mock_env();
+        let route = SwapRoute::new(vec![junoswap_op("pool_a", "uatom", "ujuno")]).unwrap();
+        let pool = junoswap_op("pool_b", "ujuno", "uosmo").pool;
+        let asset = Asset::native("uatom", 10_000u128);
+
+        // Zapping in with the uatom offered to the route is the same as zapping
+        // in with the ujuno returned from it
+        let lp_out = pool
+            .simulate_zap_in_with_route(deps.as_ref(), &env, asset.clone(), &route)
+            .unwrap();
+        let expected_lp_out = pool
+            .simulate_zap_in(deps.as_ref(), &env, Asset::native("ujuno", 9_871u128))
+            .unwrap();
+        assert_eq!(lp_out, expected_lp_out);
+
+        let response = pool
+            .zap_in_with_route(deps.as_ref(), &env, asset.clone(), &route, lp_out.amount)
+            .unwrap();
+        assert_eq!(response.expected_out, vec![lp_out.clone()].into());
+        // The route must return the amount zapped in with
+        let route_swap = CwDexResponse::new().add_submessage(response.messages[0].clone());
+        assert_eq!(
+            swap_amounts(&route_swap),
+            vec![(Uint128::new(10_000), Uint128::new(9_871))]
+        );
+
+        let err = pool
+            .zap_in_with_route(
+                deps.as_ref(),
+                &env,
+                asset.clone(),
+                &route,
+                lp_out.amount + Uint128::one(),
+            )
+            .unwrap_err();
+        assert!(matches!(err, CwDexError::MinOutNotReceived { .. }));
+
+        let err = pool
+            .zap_in_with_route(
+                deps.as_ref(),
+                &env,
+                Asset::native("ujuno", 10_000u128),
+                &route,
+                Uint128::zero(),
+            )
+            .unwrap_err();
+        assert!(matches!(err, CwDexError::InvalidInAsset { .. }));
+
+        let pool_a = junoswap_op("pool_a", "uatom", "ujuno").pool;
+        let err = pool_a
+            .zap_in_with_route(deps.as_ref(), &env, asset, &route(), Uint128::zero())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            CwDexError::AssetNotInPool {
+                asset: AssetInfo::native("uosmo")
+            }
+        );
+    }
+}
+
+#[cfg(all(test, feature = "astroport"))]
+mod astroport_tests {
+    use std::str::FromStr;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{
+        from_binary, to_binary, Addr, Binary, ContractResult, CosmosMsg, Decimal, SystemResult,
+        WasmMsg, WasmQuery,
+    };
+
+    use super::*;
+    use crate::implementations::astroport::helpers::MAX_ALLOWED_SLIPPAGE;
+    use crate::implementations::astroport::msg::{
+        PairExecuteMsg, PairQueryMsg, PairType, SimulationResponse,
+    };
+    use crate::implementations::astroport::AstroportPool;
+
+    /// Returns 99% of the offered amount from any pair
+    fn mock_pairs_querier(query: &WasmQuery) -> SystemResult<ContractResult<Binary>> {
+        let res = match query {
+            WasmQuery::Smart { msg, .. } => match from_binary(msg).unwrap() {
+                PairQueryMsg::Simulation { offer_asset } => to_binary(&SimulationResponse {
+                    return_amount: offer_asset.amount * Decimal::percent(99),
+                    spread_amount: Uint128::zero(),
+                    commission_amount: offer_asset.amount * Decimal::percent(1),
+                }),
+                _ => return SystemResult::Ok(ContractResult::Err("unexpected query".to_string())),
+            },
+            _ => return SystemResult::Ok(ContractResult::Err("unexpected query".to_string())),
+        };
+        SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+    }
+
+    fn astroport_op(pair: &str, offer: &str, ask: &str) -> SwapOperation {
+        SwapOperation {
+            pool: Pool::Astroport(AstroportPool {
+                pair_addr: Addr::unchecked(pair),
+                lp_token_addr: Addr::unchecked(format!("{}_lp_token", pair)),
+                pool_assets: vec![AssetInfo::native(offer), AssetInfo::native(ask)],
+                pair_type: PairType::Xyk {},
+            }),
+            offer_asset_info: AssetInfo::native(offer),
+            ask_asset_info: AssetInfo::native(ask),
+        }
+    }
+
+    /// Returns the `belief_price` and `max_spread` of each Astroport swap
+    /// message in `response`
+    fn swap_limits(response: &CwDexResponse) -> Vec<(Option<Decimal>, Option<Decimal>)> {
+        response
+            .messages
+            .iter()
+            .map(|sub_msg| match &sub_msg.msg {
+                CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => match from_binary(msg).unwrap() {
+                    PairExecuteMsg::Swap {
+                        belief_price,
+                        max_spread,
+                        ..
+                    } => (belief_price, max_spread),
+                    _ => panic!("unexpected execute msg"),
+                },
+                _ => panic!("unexpected msg"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_swap_without_min_out() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(mock_pairs_querier);
+        let route = SwapRoute::new(vec![
+            astroport_op("pair_a", "uatom", "ujuno"),
+            astroport_op("pair_b", "ujuno", "uosmo"),
+        ])
+        .unwrap();
+
+        let response = route
+            .swap(
+                deps.as_ref(),
+                &mock_env(),
+                Asset::native("uatom", 10_000u128),
+                Uint128::zero(),
+            )
+            .unwrap();
+        assert_eq!(
+            response.expected_out,
+            vec![Asset::native("uosmo", 9_801u128)].into()
+        );
+
+        // Without a minimum the last hop swaps without a belief price, limited
+        // only by the maximum spread of the pair
+        let limits = swap_limits(&response);
+        assert_eq!(
+            limits[1],
+            (None, Some(Decimal::from_str(MAX_ALLOWED_SLIPPAGE).unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_swap_min_out_on_last_hop() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(mock_pairs_querier);
+        let route = SwapRoute::new(vec![
+            astroport_op("pair_a", "uatom", "ujuno"),
+            astroport_op("pair_b", "ujuno", "uosmo"),
+        ])
+        .unwrap();
+
+        let response = route
+            .swap(
+                deps.as_ref(),
+                &mock_env(),
+                Asset::native("uatom", 10_000u128),
+                Uint128::new(9_000),
+            )
+            .unwrap();
+
+        // The intermediate hop must return the simulated 9_900 ujuno, which the
+        // last hop offers for at least 9_000 uosmo
+        assert_eq!(
+            swap_limits(&response),
+            vec![
+                (
+                    Some(Decimal::from_ratio(10_000u128, 9_900u128)),
+                    Some(Decimal::zero())
+                ),
+                (
+                    Some(Decimal::from_ratio(9_900u128, 9_000u128)),
+                    Some(Decimal::zero())
+                ),
+            ]
+        );
+    }
+}
+
+#[cfg(all(test, feature = "osmosis"))]
+mod osmosis_tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::CosmosMsg;
+    use prost::Message;
+
+    use super::*;
+    use crate::implementations::osmosis::OsmosisPool;
+
+    fn osmosis_op(pool_id: u64, offer: &str, ask: &str) -> SwapOperation {
+        SwapOperation {
+            pool: Pool::Osmosis(OsmosisPool::unchecked(pool_id)),
+            offer_asset_info: AssetInfo::native(offer),
+            ask_asset_info: AssetInfo::native(ask),
+        }
+    }
+
+    fn route() -> SwapRoute {
+        SwapRoute::new(vec![
+            osmosis_op(1, "uatom", "uosmo"),
+            osmosis_op(2, "uosmo", "ujuno"),
+        ])
+        .unwrap()
+    }
+
+    /// Returns the `MsgSwapExactAmountIn` of each message in `response`
+    fn swap_msgs(response: &CwDexResponse) -> Vec<MsgSwapExactAmountIn> {
+        response
+            .messages
+            .iter()
+            .map(|sub_msg| match &sub_msg.msg {
+                CosmosMsg::Stargate { type_url, value } => {
+                    assert_eq!(type_url, MsgSwapExactAmountIn::TYPE_URL);
+                    MsgSwapExactAmountIn::decode(value.as_slice()).unwrap()
+                }
+                _ => panic!("unexpected msg"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_new_validates_route() {
+        let err = SwapRoute::new(vec![
+            osmosis_op(1, "uatom", "uosmo"),
+            osmosis_op(2, "ujuno", "uosmo"),
+        ])
+        .unwrap_err();
+        assert!(matches!(err, CwDexError::InvalidSwapRoute { .. }));
+
+        let route = route();
+        assert_eq!(
+            route.offer_asset_info().unwrap(),
+            AssetInfo::native("uatom")
+        );
+        assert_eq!(route.ask_asset_info().unwrap(), AssetInfo::native("ujuno"));
+    }
+
+    #[test]
+    fn test_swap_hop_chains_osmosis_operations() {
+        let deps = mock_dependencies();
+        let env = mock_env();
+        let route = route();
+        let hops = route.hops();
+        assert_eq!(hops.len(), 1);
+
+        let response = swap_hop(
+            deps.as_ref(),
+            &env,
+            &hops[0],
+            Asset::native("uatom", 10_000u128),
+            Uint128::new(9_000),
+        )
+        .unwrap();
+        let msgs = swap_msgs(&response);
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(
+            msgs[0].routes,
+            vec![
+                SwapAmountInRoute {
+                    pool_id: 1,
+                    token_out_denom: "uosmo".to_string(),
+                },
+                SwapAmountInRoute {
+                    pool_id: 2,
+                    token_out_denom: "ujuno".to_string(),
+                },
+            ]
+        );
+        assert_eq!(msgs[0].token_out_min_amount, "9000");
+
+        // Osmosis rejects a min out of zero
+        let response = swap_hop(
+            deps.as_ref(),
+            &env,
+            &hops[0],
+            Asset::native("uatom", 10_000u128),
+            Uint128::zero(),
+        )
+        .unwrap();
+        assert_eq!(swap_msgs(&response)[0].token_out_min_amount, "1");
+    }
+}
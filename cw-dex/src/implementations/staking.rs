@@ -0,0 +1,322 @@
+//! Contains an enum with variants for Staking implementations.
+//! For use in serialization.
+
+use crate::error::CwDexError;
+use crate::traits::{
+    LockedStaking, Rewards, Stake, Staking as StakingTrait, UnbondingPosition, Unlock, Unstake,
+};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Deps, Env, QuerierWrapper, Response, Uint128};
+use cw_asset::AssetList;
+use cw_utils::Duration;
+
+#[cfg(feature = "astroport")]
+use crate::astroport::AstroportStaking;
+
+#[cfg(feature = "osmosis")]
+use crate::osmosis::{OsmosisStaking, OsmosisSuperfluidStaking};
+
+#[cfg(feature = "junoswap")]
+use crate::junoswap::JunoswapStaking;
+
+/// An enum with all known variants that implement the staking traits.
+/// See [`Pool`](crate::implementations::Pool) for why this is an enum and not
+/// a trait object.
+///
+/// All variants implement [`Stake`] and [`Rewards`]. Calling a method of
+/// [`Unstake`], [`Unlock`] or [`LockedStaking`] on a variant that does not
+/// support it returns [`CwDexError::UnsupportedStakingOperation`].
+#[cw_serde]
+#[non_exhaustive]
+pub enum Staking {
+    /// Contains an Osmosis locked staking implementation
+    #[cfg(feature = "osmosis")]
+    Osmosis(OsmosisStaking),
+    /// Contains an Osmosis superfluid staking implementation
+    #[cfg(feature = "osmosis")]
+    OsmosisSuperfluid(OsmosisSuperfluidStaking),
+    /// Contains an Astroport staking implementation
+    #[cfg(feature = "astroport")]
+    Astroport(AstroportStaking),
+    /// Contains a Junoswap staking implementation
+    #[cfg(feature = "junoswap")]
+    Junoswap(JunoswapStaking),
+}
+
+impl Staking {
+    /// Returns a specific `Staking` instance as a generic `Stake` trait object
+    pub fn as_stake(&self) -> &dyn Stake {
+        // This is needed to avoid a warning when compiling with all features
+        #[allow(unreachable_patterns)]
+        match self {
+            #[cfg(feature = "osmosis")]
+            Staking::Osmosis(x) => x,
+            #[cfg(feature = "osmosis")]
+            Staking::OsmosisSuperfluid(x) => x,
+            #[cfg(feature = "astroport")]
+            Staking::Astroport(x) => x,
+            #[cfg(feature = "junoswap")]
+            Staking::Junoswap(x) => x,
+            _ => {
+                panic!("Staking variant not supported");
+            }
+        }
+    }
+
+    /// Returns a specific `Staking` instance as a generic `Unstake` trait
+    /// object, or an error if the variant does not support unstaking.
+    pub fn as_unstake(&self) -> Result<&dyn Unstake, CwDexError> {
+        #[allow(unreachable_patterns)]
+        match self {
+            #[cfg(feature = "astroport")]
+            Staking::Astroport(x) => Ok(x),
+            #[cfg(feature = "junoswap")]
+            Staking::Junoswap(x) => Ok(x),
+            _ => Err(self.unsupported("unstake")),
+        }
+    }
+
+    /// Returns a specific `Staking` instance as a generic `LockedStaking` trait
+    /// object, or an error if the variant does not support locked staking.
+    pub fn as_locked_staking(&self) -> Result<&dyn LockedStaking, CwDexError> {
+        #[allow(unreachable_patterns)]
+        match self {
+            #[cfg(feature = "osmosis")]
+            Staking::Osmosis(x) => Ok(x),
+            #[cfg(feature = "osmosis")]
+            Staking::OsmosisSuperfluid(x) => Ok(x),
+            #[cfg(feature = "junoswap")]
+            Staking::Junoswap(x) => Ok(x),
+            _ => Err(self.unsupported("locked staking")),
+        }
+    }
+
+    /// Returns the name of the variant, used in error messages
+    fn name(&self) -> &'static str {
+        #[allow(unreachable_patterns)]
+        match self {
+            #[cfg(feature = "osmosis")]
+            Staking::Osmosis(_) => "osmosis",
+            #[cfg(feature = "osmosis")]
+            Staking::OsmosisSuperfluid(_) => "osmosis_superfluid",
+            #[cfg(feature = "astroport")]
+            Staking::Astroport(_) => "astroport",
+            #[cfg(feature = "junoswap")]
+            Staking::Junoswap(_) => "junoswap",
+            _ => "unknown",
+        }
+    }
+
+    fn unsupported(&self, operation: &str) -> CwDexError {
+        CwDexError::UnsupportedStakingOperation {
+            operation: operation.to_string(),
+            implementation: self.name().to_string(),
+        }
+    }
+}
+
+// Implement the staking traits for the Staking enum so we can use all the
+// trait methods directly on the enum type.
+impl Rewards for Staking {
+    fn claim_rewards(&self, deps: Deps, env: &Env) -> Result<Response, CwDexError> {
+        self.as_stake().claim_rewards(deps, env)
+    }
+
+    fn query_pending_rewards(
+        &self,
+        querier: &QuerierWrapper,
+        user: &Addr,
+    ) -> Result<AssetList, CwDexError> {
+        self.as_stake().query_pending_rewards(querier, user)
+    }
+}
+
+impl Stake for Staking {
+    fn stake(&self, deps: Deps, env: &Env, amount: Uint128) -> Result<Response, CwDexError> {
+        self.as_stake().stake(deps, env, amount)
+    }
+
+    fn query_staked_balance(
+        &self,
+        querier: &QuerierWrapper,
+        user: &Addr,
+    ) -> Result<Uint128, CwDexError> {
+        self.as_stake().query_staked_balance(querier, user)
+    }
+}
+
+impl Unstake for Staking {
+    fn unstake(&self, deps: Deps, env: &Env, amount: Uint128) -> Result<Response, CwDexError> {
+        self.as_unstake()?.unstake(deps, env, amount)
+    }
+}
+
+impl StakingTrait for Staking {}
+
+impl Unlock for Staking {
+    fn unlock(&self, deps: Deps, env: &Env, amount: Uint128) -> Result<Response, CwDexError> {
+        self.as_locked_staking()?.unlock(deps, env, amount)
+    }
+
+    fn withdraw_unlocked(
+        &self,
+        deps: Deps,
+        env: &Env,
+        amount: Uint128,
+    ) -> Result<Response, CwDexError> {
+        self.as_locked_staking()?
+            .withdraw_unlocked(deps, env, amount)
+    }
+
+    fn query_unbonding_positions(
+        &self,
+        querier: &QuerierWrapper,
+        user: &Addr,
+    ) -> Result<Vec<UnbondingPosition>, CwDexError> {
+        self.as_locked_staking()?
+            .query_unbonding_positions(querier, user)
+    }
+}
+
+impl LockedStaking for Staking {
+    fn get_lockup_duration(&self, deps: Deps) -> Result<Duration, CwDexError> {
+        self.as_locked_staking()?.get_lockup_duration(deps)
+    }
+}
+
+#[cfg(all(test, feature = "astroport"))]
+mod astroport_tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+
+    use super::*;
+
+    fn astroport_staking() -> Staking {
+        Staking::Astroport(AstroportStaking {
+            lp_token_addr: Addr::unchecked("lp_token"),
+            generator_addr: Addr::unchecked("generator"),
+            astro_addr: Addr::unchecked("astro"),
+        })
+    }
+
+    #[test]
+    fn test_supported_operations() {
+        let staking = astroport_staking();
+
+        assert!(staking.as_unstake().is_ok());
+    }
+
+    #[test]
+    fn test_unsupported_operations() {
+        let deps = mock_dependencies();
+        let env = mock_env();
+        let staking = astroport_staking();
+        let unsupported = CwDexError::UnsupportedStakingOperation {
+            operation: "locked staking".to_string(),
+            implementation: "astroport".to_string(),
+        };
+
+        assert_eq!(
+            staking
+                .unlock(deps.as_ref(), &env, Uint128::one())
+                .unwrap_err(),
+            unsupported
+        );
+        assert_eq!(
+            staking
+                .withdraw_unlocked(deps.as_ref(), &env, Uint128::one())
+                .unwrap_err(),
+            unsupported
+        );
+        assert_eq!(
+            staking
+                .query_unbonding_positions(&deps.as_ref().querier, &Addr::unchecked("user"))
+                .unwrap_err(),
+            unsupported
+        );
+        assert_eq!(
+            staking.get_lockup_duration(deps.as_ref()).unwrap_err(),
+            unsupported
+        );
+    }
+}
+
+#[cfg(all(test, feature = "osmosis"))]
+mod osmosis_tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+
+    use super::*;
+
+    fn osmosis_staking() -> Staking {
+        Staking::Osmosis(OsmosisStaking::new(86400, None, "gamm/pool/1".to_string()).unwrap())
+    }
+
+    fn osmosis_superfluid_staking() -> Staking {
+        Staking::OsmosisSuperfluid(
+            OsmosisSuperfluidStaking::new(
+                Addr::unchecked("validator"),
+                None,
+                "gamm/pool/1".to_string(),
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_supported_operations() {
+        let deps = mock_dependencies();
+
+        assert_eq!(
+            osmosis_staking()
+                .get_lockup_duration(deps.as_ref())
+                .unwrap(),
+            Duration::Time(86400)
+        );
+        assert_eq!(
+            osmosis_superfluid_staking()
+                .get_lockup_duration(deps.as_ref())
+                .unwrap(),
+            Duration::Time(14 * 24 * 60 * 60)
+        );
+    }
+
+    #[test]
+    fn test_unsupported_operations() {
+        let deps = mock_dependencies();
+
+        assert_eq!(
+            osmosis_staking()
+                .unstake(deps.as_ref(), &mock_env(), Uint128::one())
+                .unwrap_err(),
+            CwDexError::UnsupportedStakingOperation {
+                operation: "unstake".to_string(),
+                implementation: "osmosis".to_string(),
+            }
+        );
+        assert_eq!(
+            osmosis_superfluid_staking()
+                .unstake(deps.as_ref(), &mock_env(), Uint128::one())
+                .unwrap_err(),
+            CwDexError::UnsupportedStakingOperation {
+                operation: "unstake".to_string(),
+                implementation: "osmosis_superfluid".to_string(),
+            }
+        );
+    }
+}
+
+#[cfg(all(test, feature = "junoswap"))]
+mod junoswap_tests {
+    use super::*;
+
+    #[test]
+    fn test_supported_operations() {
+        // Junoswap staking supports all staking operations.
+        let staking = Staking::Junoswap(JunoswapStaking::new(
+            Addr::unchecked("staking"),
+            Addr::unchecked("lp_token"),
+        ));
+
+        assert!(staking.as_unstake().is_ok());
+        assert!(staking.as_locked_staking().is_ok());
+    }
+}
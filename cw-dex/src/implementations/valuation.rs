@@ -0,0 +1,90 @@
+//! Helpers for valuing LP tokens from the invariant of a pool and reference
+//! prices of its assets.
+//!
+//! The value of the current reserves at the reference prices can be inflated
+//! by swapping the pool out of balance within a block. Arbitrage at the
+//! reference prices moves the reserves along the curve of the pool, so the
+//! value of the reserves the pool would hold at the reference prices cannot be
+//! manipulated without donating to the pool.
+
+use cosmwasm_std::{Decimal, Decimal256, StdError, StdResult, Uint128};
+use cw_asset::AssetInfo;
+
+use crate::CwDexError;
+
+/// Returns the reference price of `asset_info` from `prices`.
+pub(crate) fn reference_price(
+    prices: &[(AssetInfo, Decimal)],
+    asset_info: &AssetInfo,
+) -> Result<Decimal, CwDexError> {
+    prices
+        .iter()
+        .find(|(info, _)| info == asset_info)
+        .map(|(_, price)| *price)
+        .ok_or_else(|| CwDexError::MissingPrice {
+            asset: asset_info.clone(),
+        })
+}
+
+/// Returns the value of a constant product pool with reserves `x` and `y` at
+/// the reference prices `p_x` and `p_y`, given as `[(x, p_x), (y, p_y)]`.
+///
+/// Arbitrage moves the reserves to where `x * p_x = y * p_y`, at which the
+/// pool is worth `2 * sqrt(x * y * p_x * p_y)`.
+#[cfg(any(feature = "astroport", feature = "junoswap"))]
+pub(crate) fn xyk_fair_value(reserves: [(Uint128, Decimal); 2]) -> StdResult<Decimal256> {
+    let [(x, p_x), (y, p_y)] = reserves;
+    let value_x = Decimal256::from_ratio(x, 1u128).checked_mul(p_x.into())?;
+    let value_y = Decimal256::from_ratio(y, 1u128).checked_mul(p_y.into())?;
+
+    Ok(Decimal256::from_ratio(2u128, 1u128)
+        .checked_mul(value_x.sqrt())?
+        .checked_mul(value_y.sqrt())?)
+}
+
+/// Returns the price of one unit of an LP token with supply `total_supply`
+/// of a pool worth `value`.
+pub(crate) fn lp_price_from_value(value: Decimal256, total_supply: Uint128) -> StdResult<Decimal> {
+    if total_supply.is_zero() {
+        return Err(StdError::generic_err("pool has no liquidity"));
+    }
+
+    let price = value
+        .checked_div(Decimal256::from_ratio(total_supply, 1u128))
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    Ok(Decimal::new(Uint128::try_from(price.atomics())?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(any(feature = "astroport", feature = "junoswap"))]
+    fn test_xyk_fair_value() {
+        let balanced = xyk_fair_value([
+            (Uint128::new(1_000_000), Decimal::percent(200)),
+            (Uint128::new(2_000_000), Decimal::one()),
+        ])
+        .unwrap();
+        assert_eq!(balanced, Decimal256::from_ratio(4_000_000u128, 1u128));
+
+        // Swapping the pool out of balance does not increase its value
+        let imbalanced = xyk_fair_value([
+            (Uint128::new(2_000_000), Decimal::percent(200)),
+            (Uint128::new(1_000_000), Decimal::one()),
+        ])
+        .unwrap();
+        assert_eq!(imbalanced, balanced);
+    }
+
+    #[test]
+    fn test_lp_price_from_value() {
+        let value = Decimal256::from_ratio(4_000_000u128, 1u128);
+        assert_eq!(
+            lp_price_from_value(value, Uint128::new(1_000_000)).unwrap(),
+            Decimal::from_ratio(4u128, 1u128)
+        );
+        assert!(lp_price_from_value(value, Uint128::zero()).is_err());
+    }
+}
@@ -0,0 +1,249 @@
+//! Helpers for zapping out of pools, i.e. withdrawing liquidity into a single
+//! asset of the pool.
+
+#[cfg(any(feature = "astroport", feature = "junoswap"))]
+use cosmwasm_std::Env;
+use cosmwasm_std::{Deps, StdError, Uint128};
+#[cfg(any(feature = "astroport", feature = "junoswap"))]
+use cw_asset::AssetList;
+use cw_asset::{Asset, AssetInfo};
+
+#[cfg(any(feature = "astroport", feature = "junoswap"))]
+use crate::response::CwDexResponse;
+use crate::traits::Pool;
+use crate::CwDexError;
+
+/// The estimated outcome of withdrawing liquidity and swapping the withdrawn
+/// assets into a single asset of the pool.
+pub(crate) struct ZapOutSimulation {
+    /// The amount of the asked asset received from withdrawing liquidity
+    #[cfg(any(feature = "astroport", feature = "junoswap"))]
+    pub withdrawn_amount: Uint128,
+    /// The withdrawn assets to swap into the asked asset along with the
+    /// expected return of each swap
+    #[cfg(any(feature = "astroport", feature = "junoswap"))]
+    pub swaps: Vec<(Asset, Uint128)>,
+    /// The total amount of the asked asset expected
+    pub return_amount: Uint128,
+}
+
+/// Simulates withdrawing `lp_token` from `pool` and swapping all withdrawn
+/// assets other than `ask_asset_info` into it in the pool.
+///
+/// The swaps are made against the reserves left after withdrawing. All
+/// supported pool curves scale linearly with the reserves, so swapping an
+/// amount in the pool after the withdrawal returns as much as swapping the
+/// amount scaled up by the ratio of reserves before the withdrawal, scaled
+/// back down by the same ratio. For pools with more than two assets, the
+/// effect of the swaps on each other is not accounted for.
+pub(crate) fn simulate_zap_out<P: Pool + ?Sized>(
+    pool: &P,
+    deps: Deps,
+    lp_token: &Asset,
+    ask_asset_info: &AssetInfo,
+) -> Result<ZapOutSimulation, CwDexError> {
+    let reserves = pool.get_pool_liquidity(deps)?;
+    if reserves.find(ask_asset_info).is_none() {
+        return Err(CwDexError::AssetNotInPool {
+            asset: ask_asset_info.clone(),
+        });
+    }
+
+    let withdrawn = pool.simulate_withdraw_liquidity(deps, lp_token)?;
+
+    let mut withdrawn_amount = Uint128::zero();
+    let mut return_amount = Uint128::zero();
+    #[cfg(any(feature = "astroport", feature = "junoswap"))]
+    let mut swaps = vec![];
+    for asset in withdrawn.into_iter() {
+        if asset.info == *ask_asset_info {
+            withdrawn_amount = withdrawn_amount.checked_add(asset.amount)?;
+            continue;
+        }
+        if asset.amount.is_zero() {
+            continue;
+        }
+
+        let reserve = reserves
+            .find(&asset.info)
+            .map(|a| a.amount)
+            .unwrap_or_default();
+        let remaining = reserve.checked_sub(asset.amount)?;
+        if remaining.is_zero() {
+            return Err(StdError::generic_err("No liquidity left in the pool to swap with").into());
+        }
+
+        let scaled_offer = Asset::new(
+            asset.info.clone(),
+            asset.amount.multiply_ratio(reserve, remaining),
+        );
+        let expected = pool
+            .simulate_swap(deps, scaled_offer, ask_asset_info.clone())?
+            .multiply_ratio(remaining, reserve);
+        if expected.is_zero() {
+            continue;
+        }
+
+        return_amount = return_amount.checked_add(expected)?;
+        #[cfg(any(feature = "astroport", feature = "junoswap"))]
+        swaps.push((asset.clone(), expected));
+    }
+
+    Ok(ZapOutSimulation {
+        #[cfg(any(feature = "astroport", feature = "junoswap"))]
+        withdrawn_amount,
+        #[cfg(any(feature = "astroport", feature = "junoswap"))]
+        swaps,
+        return_amount: return_amount.checked_add(withdrawn_amount)?,
+    })
+}
+
+/// Returns a `CwDexResponse` withdrawing `lp_token` from `pool` and swapping all
+/// withdrawn assets other than `ask_asset_info` into it, for pools that do not
+/// support withdrawing into a single asset natively.
+///
+/// The minimum output of each swap is its share of `min_out`, in proportion
+/// to its expected return, so that the swaps fail if less than `min_out` of
+/// `ask_asset_info` would be received in total.
+#[cfg(any(feature = "astroport", feature = "junoswap"))]
+pub(crate) fn withdraw_and_swap<P: Pool + ?Sized>(
+    pool: &P,
+    deps: Deps,
+    env: &Env,
+    lp_token: Asset,
+    ask_asset_info: &AssetInfo,
+    min_out: Uint128,
+) -> Result<CwDexResponse, CwDexError> {
+    let simulation = simulate_zap_out(pool, deps, &lp_token, ask_asset_info)?;
+    if simulation.return_amount < min_out {
+        return Err(CwDexError::MinOutNotReceived {
+            min_out,
+            received: simulation.return_amount,
+        });
+    }
+
+    // The minimum output is enforced by the swaps
+    let withdraw = pool.withdraw_liquidity(deps, env, lp_token, AssetList::new())?;
+    let mut response = CwDexResponse::new()
+        .add_submessages(withdraw.messages)
+        .add_events(withdraw.events);
+
+    let min_out_from_swaps = min_out.saturating_sub(simulation.withdrawn_amount);
+    let expected_from_swaps = simulation.return_amount - simulation.withdrawn_amount;
+    for (offer_asset, expected) in simulation.swaps {
+        let swap_min_out = expected.multiply_ratio(min_out_from_swaps, expected_from_swaps);
+        let swap = pool.swap(deps, env, offer_asset, ask_asset_info.clone(), swap_min_out)?;
+        response = response
+            .add_submessages(swap.messages)
+            .add_events(swap.events);
+    }
+
+    Ok(response.with_expected_out(vec![Asset::new(
+        ask_asset_info.clone(),
+        simulation.return_amount,
+    )]))
+}
+
+#[cfg(all(test, feature = "junoswap"))]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{
+        from_binary, to_binary, Addr, Binary, ContractResult, Decimal, SystemResult, WasmQuery,
+    };
+    use cw20_0_10_3::Denom;
+    use wasmswap::msg::{InfoResponse, QueryMsg, Token1ForToken2PriceResponse};
+
+    use super::*;
+    use crate::implementations::junoswap::JunoswapPool;
+
+    const PAIR: &str = "pair";
+    const LP_TOKEN: &str = "lp_token";
+
+    fn mock_pair_querier(query: &WasmQuery) -> SystemResult<ContractResult<Binary>> {
+        match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == PAIR => {
+                let res = match from_binary(msg).unwrap() {
+                    QueryMsg::Info {} => to_binary(&InfoResponse {
+                        token1_reserve: Uint128::new(1_000_000),
+                        token1_denom: Denom::Native("uatom".to_string()),
+                        token2_reserve: Uint128::new(1_000_000),
+                        token2_denom: Denom::Native("ujuno".to_string()),
+                        lp_token_supply: Uint128::new(1_000_000),
+                        lp_token_address: LP_TOKEN.to_string(),
+                        owner: None,
+                        // A total fee of 0.3%
+                        lp_fee_percent: Decimal::permille(300),
+                        protocol_fee_percent: Default::default(),
+                        protocol_fee_recipient: "protocol".to_string(),
+                    }),
+                    QueryMsg::Token1ForToken2Price { token1_amount } => {
+                        // Swap on the constant product curve with a 0.3% fee
+                        let input_with_fee = token1_amount * Uint128::new(997);
+                        to_binary(&Token1ForToken2PriceResponse {
+                            token2_amount: input_with_fee * Uint128::new(1_000_000)
+                                / (Uint128::new(1_000_000_000) + input_with_fee),
+                        })
+                    }
+                    _ => panic!("unexpected query"),
+                };
+                SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+            }
+            _ => panic!("unexpected query"),
+        }
+    }
+
+    #[test]
+    fn test_withdraw_and_swap() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(mock_pair_querier);
+        let env = mock_env();
+
+        let pool = JunoswapPool::new(Addr::unchecked(PAIR), Addr::unchecked(LP_TOKEN));
+        let lp_token = Asset::cw20(Addr::unchecked(LP_TOKEN), 100_000u128);
+        let ask_asset_info = AssetInfo::native("ujuno");
+
+        let simulation =
+            simulate_zap_out(&pool, deps.as_ref(), &lp_token, &ask_asset_info).unwrap();
+        assert_eq!(simulation.withdrawn_amount, Uint128::new(100_000));
+        assert_eq!(
+            simulation.swaps,
+            vec![(Asset::native("uatom", 100_000u128), Uint128::new(89_756))]
+        );
+        assert_eq!(simulation.return_amount, Uint128::new(189_756));
+
+        let res = withdraw_and_swap(
+            &pool,
+            deps.as_ref(),
+            &env,
+            lp_token.clone(),
+            &ask_asset_info,
+            Uint128::new(180_000),
+        )
+        .unwrap();
+        assert!(res.events.iter().any(|e| e.ty == "apollo/cw-dex/swap"
+            && e.attributes
+                .iter()
+                .any(|a| a.key == "minimum_out_amount" && a.value == "80000")));
+        assert_eq!(
+            res.expected_out,
+            vec![Asset::native("ujuno", 189_756u128)].into()
+        );
+
+        let err = withdraw_and_swap(
+            &pool,
+            deps.as_ref(),
+            &env,
+            lp_token,
+            &ask_asset_info,
+            Uint128::new(200_000),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            CwDexError::MinOutNotReceived {
+                min_out: Uint128::new(200_000),
+                received: Uint128::new(189_756)
+            }
+        );
+    }
+}
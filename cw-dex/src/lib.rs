@@ -14,12 +14,16 @@
 //! - [Astroport](crate::implementations::astroport)
 //! - [Junoswap](crate::implementations::junoswap)
 
+pub mod callback;
 pub mod error;
 pub mod implementations;
+pub mod response;
 pub mod traits;
 
+pub use callback::*;
 pub use error::*;
 pub use implementations::*;
+pub use response::*;
 
 // #[cfg(test)]
 // pub mod tests;
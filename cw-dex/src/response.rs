@@ -0,0 +1,170 @@
+//! Contains the `CwDexResponse` type returned by the execute functions of the
+//! `Pool` and `Router` traits.
+
+use cosmwasm_std::{CosmosMsg, Event, Response, StdResult, SubMsg};
+use cw_asset::AssetList;
+
+/// The result of executing an action on a pool or a route, containing the
+/// messages to perform the action along with its expected outcome.
+///
+/// Convert it into a [`Response`] with [`CwDexResponse::into_response`], or
+/// append it to an existing one with [`CwDexResponse::append_to`]. Several
+/// results can be combined with [`CwDexResponse::merge`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CwDexResponse {
+    /// The messages to execute the action
+    pub messages: Vec<SubMsg>,
+    /// The events describing the action
+    pub events: Vec<Event>,
+    /// The estimated assets received from the action
+    pub expected_out: AssetList,
+    /// The assets offered to the action that are not used by it and stay in
+    /// the contract's balance
+    pub leftover: AssetList,
+}
+
+impl CwDexResponse {
+    /// Creates an empty `CwDexResponse`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a message that is executed without a reply
+    pub fn add_message(mut self, msg: impl Into<CosmosMsg>) -> Self {
+        self.messages.push(SubMsg::new(msg));
+        self
+    }
+
+    /// Adds messages that are executed without a reply
+    pub fn add_messages<M: Into<CosmosMsg>>(self, msgs: impl IntoIterator<Item = M>) -> Self {
+        self.add_submessages(msgs.into_iter().map(SubMsg::new))
+    }
+
+    /// Adds a submessage
+    pub fn add_submessage(mut self, msg: SubMsg) -> Self {
+        self.messages.push(msg);
+        self
+    }
+
+    /// Adds submessages
+    pub fn add_submessages(mut self, msgs: impl IntoIterator<Item = SubMsg>) -> Self {
+        self.messages.extend(msgs);
+        self
+    }
+
+    /// Adds an event
+    pub fn add_event(mut self, event: Event) -> Self {
+        self.events.push(event);
+        self
+    }
+
+    /// Adds events
+    pub fn add_events(mut self, events: impl IntoIterator<Item = Event>) -> Self {
+        self.events.extend(events);
+        self
+    }
+
+    /// Sets the estimated assets received from the action
+    pub fn with_expected_out(mut self, expected_out: impl Into<AssetList>) -> Self {
+        self.expected_out = expected_out.into();
+        self
+    }
+
+    /// Sets the assets not used by the action
+    pub fn with_leftover(mut self, leftover: impl Into<AssetList>) -> Self {
+        self.leftover = leftover.into();
+        self
+    }
+
+    /// Appends the messages and events of `other` to this response and adds
+    /// up the expected outputs and leftovers of both.
+    pub fn merge(mut self, other: CwDexResponse) -> StdResult<Self> {
+        self.messages.extend(other.messages);
+        self.events.extend(other.events);
+        self.expected_out.add_many(&other.expected_out)?;
+        self.leftover.add_many(&other.leftover)?;
+        Ok(self)
+    }
+
+    /// Appends the messages and events to `response`
+    pub fn append_to(self, response: Response) -> Response {
+        response
+            .add_submessages(self.messages)
+            .add_events(self.events)
+    }
+
+    /// Converts this into a [`Response`] with the messages and events
+    pub fn into_response(self) -> Response {
+        self.append_to(Response::new())
+    }
+}
+
+impl From<CwDexResponse> for Response {
+    fn from(response: CwDexResponse) -> Self {
+        response.into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{BankMsg, Coin, ReplyOn};
+    use cw_asset::Asset;
+
+    use super::*;
+
+    fn send(amount: u128) -> CosmosMsg {
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr".to_string(),
+            amount: vec![Coin::new(amount, "uatom")],
+        })
+    }
+
+    #[test]
+    fn test_merge() {
+        let a = CwDexResponse::new()
+            .add_message(send(1))
+            .add_event(Event::new("a"))
+            .with_expected_out(vec![Asset::native("uatom", 10u128)]);
+        let b = CwDexResponse::new()
+            .add_submessage(SubMsg::reply_on_success(send(2), 1))
+            .add_event(Event::new("b"))
+            .with_expected_out(vec![
+                Asset::native("uatom", 5u128),
+                Asset::native("uosmo", 1u128),
+            ])
+            .with_leftover(vec![Asset::native("ujuno", 3u128)]);
+
+        let merged = a.merge(b).unwrap();
+        assert_eq!(
+            merged.messages,
+            vec![SubMsg::new(send(1)), SubMsg::reply_on_success(send(2), 1)]
+        );
+        assert_eq!(merged.events, vec![Event::new("a"), Event::new("b")]);
+        assert_eq!(
+            merged.expected_out,
+            vec![
+                Asset::native("uatom", 15u128),
+                Asset::native("uosmo", 1u128)
+            ]
+            .into()
+        );
+        assert_eq!(merged.leftover, vec![Asset::native("ujuno", 3u128)].into());
+    }
+
+    #[test]
+    fn test_append_to() {
+        let response = Response::new()
+            .add_attribute("action", "test")
+            .add_message(send(1));
+        let res = CwDexResponse::new()
+            .add_submessage(SubMsg::reply_on_error(send(2), 7))
+            .add_event(Event::new("cw-dex"))
+            .append_to(response);
+
+        assert_eq!(res.attributes.len(), 1);
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(res.messages[1].id, 7);
+        assert_eq!(res.messages[1].reply_on, ReplyOn::Error);
+        assert_eq!(res.events, vec![Event::new("cw-dex")]);
+    }
+}
@@ -2,7 +2,11 @@
 //! exchanges
 
 pub mod pool;
+pub mod price_oracle;
+pub mod router;
 pub mod staking;
 
 pub use pool::*;
+pub use price_oracle::*;
+pub use router::*;
 pub use staking::*;
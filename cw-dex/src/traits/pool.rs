@@ -1,21 +1,74 @@
 //! Contains the `Pool` trait for abstracting the behavior of a dex pool.
 
-use cosmwasm_std::{Deps, Env, Response, StdResult, Uint128};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Decimal, Deps, Env, StdResult, Uint128};
 use cw_asset::{Asset, AssetInfo, AssetList};
 
+#[cfg(doc)]
+use crate::callback::CwDexCallbackMsg;
 use crate::error::CwDexError;
+use crate::response::CwDexResponse;
+use crate::traits::router::Router;
+
+/// The estimated outcome of a swap, as returned by
+/// [`Pool::simulate_swap_detailed`]. All amounts are denominated in the asked
+/// asset.
+#[cw_serde]
+pub struct SwapSimulation {
+    /// The amount of the asked asset returned by the swap
+    pub return_amount: Uint128,
+    /// The difference between the amount that would be returned at the current
+    /// spot price and `return_amount`, excluding fees. I.e. the price impact
+    /// of the swap.
+    pub spread_amount: Uint128,
+    /// The fee charged by the pool
+    pub commission_amount: Uint128,
+}
+
+/// The curve of a pool, as returned in [`PoolParams`].
+#[cw_serde]
+pub enum PoolType {
+    /// A constant product pool with the invariant `x * y = k`
+    ConstantProduct,
+    /// A weighted pool with the invariant `prod(x_i^w_i) = k`
+    Weighted,
+    /// A stableswap pool for assets that trade close to a peg
+    StableSwap,
+    /// A pool where liquidity is concentrated around a price
+    ConcentratedLiquidity,
+}
+
+/// The type, fees and curve parameters of a pool, as returned by
+/// [`Pool::pool_params`].
+#[cw_serde]
+pub struct PoolParams {
+    /// The curve of the pool
+    pub pool_type: PoolType,
+    /// The fee charged on the amount swapped. For pools with dynamic fees this
+    /// is the fee at the current state of the pool.
+    pub swap_fee: Decimal,
+    /// The fee charged on the assets withdrawn when withdrawing liquidity
+    pub exit_fee: Decimal,
+    /// The normalized weight of each asset for weighted pools
+    pub weights: Option<Vec<(AssetInfo, Decimal)>>,
+    /// The amplification coefficient for stableswap and concentrated
+    /// liquidity pools that have one
+    pub amp: Option<Decimal>,
+}
 
 /// Trait to represent an AMM pool.
 pub trait Pool {
     /// Provide liquidity to the pool.
     ///
-    /// Returns a Response with the necessary messages to provide liquidity to
-    /// the pool. `assets` must only contain the assets in the pool, but the
-    /// ratio of amounts does not need to be the same as the pool's ratio.
+    /// Returns a [`CwDexResponse`] with the necessary messages to provide
+    /// liquidity to the pool and the expected amount of LP tokens. `assets`
+    /// must only contain the assets in the pool, but the ratio of amounts does
+    /// not need to be the same as the pool's ratio.
     ///
     /// All implementations of this trait should try to use as much of the
     /// provided assets as possible, but it may leave some in the contracts
-    /// balance if they are not exactly in the same ratio as the pool. All
+    /// balance if they are not exactly in the same ratio as the pool. The
+    /// amounts that are known not to be used are returned as `leftover`. All
     /// implementations should return an error if the returned amount of LP
     /// tokens is less than `min_out`.
     ///
@@ -28,7 +81,69 @@ pub trait Pool {
         env: &Env,
         assets: AssetList,
         min_out: Uint128,
-    ) -> Result<Response, CwDexError>;
+    ) -> Result<CwDexResponse, CwDexError>;
+
+    /// Provide liquidity to the pool with a single asset (zap in).
+    ///
+    /// `asset` must be one of the assets of the pool. Implementations either
+    /// provide `asset` single sided, if the pool supports it, or swap the
+    /// optimal fraction of it into the other pool asset, accounting for the
+    /// fee and curve of the pool, and provide liquidity with the remainder
+    /// and the returned asset. All implementations should return an error if
+    /// the returned amount of LP tokens is less than `min_out`. To zap in with
+    /// an asset that is not in the pool, use [`Pool::zap_in_with_route`].
+    ///
+    /// The default implementation returns an error for pools that do not
+    /// support zapping in.
+    ///
+    /// Arguments:
+    /// - `asset`: the asset to provide liquidity with
+    /// - `min_out`: the minimum amount of LP tokens to receive
+    fn zap_in(
+        &self,
+        _deps: Deps,
+        _env: &Env,
+        _asset: Asset,
+        _min_out: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        Err(CwDexError::UnsupportedPoolOperation {
+            operation: "zap_in".to_string(),
+        })
+    }
+
+    /// Provide liquidity to the pool with any single asset, by first swapping
+    /// it through `route` into one of the assets of the pool and then zapping
+    /// in with the returned asset. See [`Pool::zap_in`].
+    ///
+    /// The swap through `route` must return at least its simulated amount,
+    /// which is the amount zapped in with, so `min_out` applies end-to-end to
+    /// the LP tokens received. The zap in is simulated against the pool before
+    /// the swap, so `route` should not swap through this pool.
+    ///
+    /// Arguments:
+    /// - `asset`: the asset to provide liquidity with. Must be the offer asset
+    ///   of `route`.
+    /// - `route`: the route swapping `asset` into one of the pool assets
+    /// - `min_out`: the minimum amount of LP tokens to receive
+    fn zap_in_with_route(
+        &self,
+        deps: Deps,
+        env: &Env,
+        asset: Asset,
+        route: &dyn Router,
+        min_out: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        let pool_asset = simulate_route_into_pool(self, deps, &asset, route)?;
+
+        let swap = route.swap(deps, env, asset, pool_asset.amount)?;
+        let zap = self.zap_in(deps, env, pool_asset, min_out)?;
+
+        // The expected output of the swap is used up by the zap in
+        Ok(CwDexResponse::new()
+            .add_submessages(swap.messages)
+            .add_events(swap.events)
+            .merge(zap)?)
+    }
 
     /// Get the LP token for this pool
     fn lp_token(&self) -> AssetInfo;
@@ -39,15 +154,52 @@ pub trait Pool {
     /// - `lp_token`: the LP tokens to withdraw as an [`Asset`]. The `info`
     ///   field must correspond to the LP token of the pool. Else, an error is
     ///   returned.
+    /// - `min_out`: the minimum amount of each of the pool assets to receive.
+    ///   Assets of the pool that are not in the list have no minimum. All
+    ///   implementations should return an error, or make the withdrawal fail,
+    ///   if less than the minimum of any asset would be received. Pools without
+    ///   native minimums send a [`CwDexCallbackMsg`] asserting them, which the
+    ///   calling contract must handle.
     ///
-    /// Returns a Response containing the messages to withdraw liquidity from
-    /// the pool.
+    /// Returns a [`CwDexResponse`] containing the messages to withdraw
+    /// liquidity from the pool and the expected assets to be returned.
     fn withdraw_liquidity(
         &self,
         deps: Deps,
         env: &Env,
         lp_token: Asset,
-    ) -> Result<Response, CwDexError>;
+        min_out: AssetList,
+    ) -> Result<CwDexResponse, CwDexError>;
+
+    /// Withdraw liquidity from the pool into a single asset (zap out).
+    ///
+    /// Implementations either withdraw directly into `ask_asset_info`, if the
+    /// pool supports it, or withdraw all assets and swap the ones other than
+    /// `ask_asset_info` into it in the pool. All implementations should return
+    /// an error if the returned amount of `ask_asset_info` is less than
+    /// `min_out`.
+    ///
+    /// The default implementation returns an error for pools that do not
+    /// support zapping out.
+    ///
+    /// Arguments:
+    /// - `lp_token`: the LP tokens to withdraw as an [`Asset`]. The `info`
+    ///   field must correspond to the LP token of the pool. Else, an error is
+    ///   returned.
+    /// - `ask_asset_info`: the asset of the pool to receive
+    /// - `min_out`: the minimum amount of `ask_asset_info` to receive
+    fn zap_out(
+        &self,
+        _deps: Deps,
+        _env: &Env,
+        _lp_token: Asset,
+        _ask_asset_info: AssetInfo,
+        _min_out: Uint128,
+    ) -> Result<CwDexResponse, CwDexError> {
+        Err(CwDexError::UnsupportedPoolOperation {
+            operation: "zap_out".to_string(),
+        })
+    }
 
     /// Swap assets in the pool.
     ///
@@ -56,7 +208,8 @@ pub trait Pool {
     /// - `ask_asset`: The asset we want to receive from the swap.
     /// - `min_out`: The minimum amount of `ask_asset` to receive.
     ///
-    /// Returns a Response containing the messages to swap assets in the pool.
+    /// Returns a [`CwDexResponse`] containing the messages to swap assets in
+    /// the pool and the expected amount of the ask asset.
     fn swap(
         &self,
         deps: Deps,
@@ -64,7 +217,26 @@ pub trait Pool {
         offer_asset: Asset,
         ask_asset_info: AssetInfo,
         min_out: Uint128,
-    ) -> Result<Response, CwDexError>;
+    ) -> Result<CwDexResponse, CwDexError>;
+
+    /// Swap assets in the pool, receiving an exact amount of the ask asset.
+    ///
+    /// Arguments:
+    /// - `ask_asset`: The asset and the exact amount of it we want to receive.
+    /// - `offer_asset_info`: The asset we want to swap.
+    /// - `max_in`: The maximum amount of `offer_asset_info` to spend. All
+    ///   implementations should return an error if more than this is needed.
+    ///
+    /// Returns a [`CwDexResponse`] containing the messages to swap assets in
+    /// the pool and the expected amount of the ask asset.
+    fn swap_exact_out(
+        &self,
+        deps: Deps,
+        env: &Env,
+        ask_asset: Asset,
+        offer_asset_info: AssetInfo,
+        max_in: Uint128,
+    ) -> Result<CwDexResponse, CwDexError>;
 
     // === Query functions ===
 
@@ -83,6 +255,36 @@ pub trait Pool {
         assets: AssetList,
     ) -> Result<Asset, CwDexError>;
 
+    /// Returns an estimated number of LP tokens that would be minted for
+    /// zapping in with the given asset. See [`Pool::zap_in`].
+    ///
+    /// Arguments:
+    /// - `asset`: the asset to provide liquidity with.
+    fn simulate_zap_in(&self, _deps: Deps, _env: &Env, _asset: Asset) -> Result<Asset, CwDexError> {
+        Err(CwDexError::UnsupportedPoolOperation {
+            operation: "simulate_zap_in".to_string(),
+        })
+    }
+
+    /// Returns an estimated number of LP tokens that would be minted for
+    /// zapping in with the given asset through `route`. See
+    /// [`Pool::zap_in_with_route`].
+    ///
+    /// Arguments:
+    /// - `asset`: the asset to provide liquidity with. Must be the offer asset
+    ///   of `route`.
+    /// - `route`: the route swapping `asset` into one of the pool assets
+    fn simulate_zap_in_with_route(
+        &self,
+        deps: Deps,
+        env: &Env,
+        asset: Asset,
+        route: &dyn Router,
+    ) -> Result<Asset, CwDexError> {
+        let pool_asset = simulate_route_into_pool(self, deps, &asset, route)?;
+        self.simulate_zap_in(deps, env, pool_asset)
+    }
+
     /// Returns an estimated number of assets to be returned for withdrawing the
     /// given LP tokens.
     ///
@@ -96,22 +298,102 @@ pub trait Pool {
         lp_token: &Asset,
     ) -> Result<AssetList, CwDexError>;
 
+    /// Returns an estimated amount of `ask_asset_info` to be returned for
+    /// zapping out of the pool with the given LP tokens. See
+    /// [`Pool::zap_out`].
+    ///
+    /// Arguments:
+    /// - `lp_token`: the LP tokens to withdraw as an [`Asset`].
+    /// - `ask_asset_info`: the asset of the pool to receive
+    fn simulate_zap_out(
+        &self,
+        _deps: Deps,
+        _lp_token: &Asset,
+        _ask_asset_info: AssetInfo,
+    ) -> Result<Asset, CwDexError> {
+        Err(CwDexError::UnsupportedPoolOperation {
+            operation: "simulate_zap_out".to_string(),
+        })
+    }
+
     /// Simulates a swap and returns the estimated amount of the asset asked
     /// for, given the offered asset
     ///
     /// Arguments:
     /// - `offer_asset`: The asset offered in the swap
     /// - `ask_asset_info`: The asset asked for in the swap
-    /// - `sender`: Sender address (required for Osmosis)
     fn simulate_swap(
         &self,
         deps: Deps,
         offer_asset: Asset,
         ask_asset_info: AssetInfo,
-        //For some reason Osmosis requires us to send a sender address for simulation.
-        //This obviously makes no sense and I guess we'll have to make a PR to
-        //Osmosis to fix this, or perhaps copy their math and perform the calculation here...
-        sender: Option<String>,
+    ) -> StdResult<Uint128>;
+
+    /// Simulates a swap and returns the estimated amount of the asset asked
+    /// for along with the spread (price impact) and commission of the swap.
+    ///
+    /// Arguments:
+    /// - `offer_asset`: The asset offered in the swap
+    /// - `ask_asset_info`: The asset asked for in the swap
+    fn simulate_swap_detailed(
+        &self,
+        deps: Deps,
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+    ) -> StdResult<SwapSimulation>;
+
+    /// Returns the current marginal price of `base_asset` denominated in
+    /// `quote_asset`, i.e. the amount of `quote_asset` one unit of
+    /// `base_asset` is worth, excluding fees.
+    fn spot_price(
+        &self,
+        deps: Deps,
+        env: &Env,
+        base_asset: &AssetInfo,
+        quote_asset: &AssetInfo,
+    ) -> StdResult<Decimal>;
+
+    /// Returns the type, fees and curve parameters of the pool.
+    fn pool_params(&self, _deps: Deps, _env: &Env) -> Result<PoolParams, CwDexError> {
+        Err(CwDexError::UnsupportedPoolOperation {
+            operation: "pool_params".to_string(),
+        })
+    }
+
+    /// Returns the fair price of one unit of the pool's LP token, given the
+    /// `prices` of all pool assets denominated in a common quote asset.
+    ///
+    /// The price is derived from the invariant of the pool, i.e. the value of
+    /// the reserves the pool would hold if it was balanced at `prices`, so it
+    /// cannot be manipulated by swapping in the pool. `prices` must therefore
+    /// come from a manipulation resistant source, such as an oracle or a
+    /// [`PriceOracle`](crate::traits::PriceOracle) TWAP.
+    ///
+    /// Returns [`CwDexError::UnsupportedPoolOperation`] for pools without
+    /// a supported invariant.
+    fn fair_lp_token_price(
+        &self,
+        _deps: Deps,
+        _env: &Env,
+        _prices: &[(AssetInfo, Decimal)],
+    ) -> Result<Decimal, CwDexError> {
+        Err(CwDexError::UnsupportedPoolOperation {
+            operation: "fair_lp_token_price".to_string(),
+        })
+    }
+
+    /// Simulates a swap and returns the estimated amount of the offered asset
+    /// needed to receive exactly the asked asset
+    ///
+    /// Arguments:
+    /// - `ask_asset`: The asset and the exact amount of it asked for in the
+    ///   swap
+    /// - `offer_asset_info`: The asset offered in the swap
+    fn simulate_swap_exact_out(
+        &self,
+        deps: Deps,
+        ask_asset: Asset,
+        offer_asset_info: AssetInfo,
     ) -> StdResult<Uint128>;
 
     /// Returns the assets in the pool as a [`Vec<AssetInfo>`]
@@ -123,3 +405,26 @@ pub trait Pool {
             .collect())
     }
 }
+
+/// Validates that `route` swaps `asset` into one of the pool assets and
+/// returns the pool asset with the estimated amount received from the
+/// swap.
+fn simulate_route_into_pool<P: Pool + ?Sized>(
+    pool: &P,
+    deps: Deps,
+    asset: &Asset,
+    route: &dyn Router,
+) -> Result<Asset, CwDexError> {
+    if route.offer_asset_info()? != asset.info {
+        return Err(CwDexError::InvalidInAsset { a: asset.clone() });
+    }
+    let ask_asset_info = route.ask_asset_info()?;
+    if !pool.pool_assets(deps)?.contains(&ask_asset_info) {
+        return Err(CwDexError::AssetNotInPool {
+            asset: ask_asset_info,
+        });
+    }
+
+    let return_amount = route.simulate_swap(deps, asset.clone())?;
+    Ok(Asset::new(ask_asset_info, return_amount))
+}
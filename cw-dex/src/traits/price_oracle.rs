@@ -0,0 +1,81 @@
+//! Contains the `PriceOracle` trait for querying manipulation resistant prices
+//! from a dex pool.
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Decimal, Deps, Env, Uint128};
+use cw_asset::AssetInfo;
+
+use crate::error::CwDexError;
+
+/// The cumulative prices of a pool at a point in time, as returned by
+/// [`PriceOracle::query_cumulative_prices`].
+#[cw_serde]
+pub struct CumulativePricesSnapshot {
+    /// The block time in seconds at which the snapshot was taken
+    pub timestamp: u64,
+    /// The cumulative price of each asset of the pool in terms of each other
+    /// asset, as `(base_asset_info, quote_asset_info, cumulative_price)`
+    pub cumulative_prices: Vec<(AssetInfo, AssetInfo, Uint128)>,
+}
+
+impl CumulativePricesSnapshot {
+    /// Returns the cumulative price of `base_asset_info` in terms of
+    /// `quote_asset_info`, if it is in the snapshot.
+    pub fn cumulative_price(
+        &self,
+        base_asset_info: &AssetInfo,
+        quote_asset_info: &AssetInfo,
+    ) -> Option<Uint128> {
+        self.cumulative_prices
+            .iter()
+            .find(|(base, quote, _)| base == base_asset_info && quote == quote_asset_info)
+            .map(|(_, _, price)| *price)
+    }
+}
+
+/// Trait to query time weighted average prices (TWAP) from an AMM pool.
+///
+/// Some dexes keep a record of prices that the TWAP over any recent window can
+/// be queried from. Others only expose the current cumulative prices of the
+/// pool, in which case the caller must store [`CumulativePricesSnapshot`]s
+/// over time and pass them to [`PriceOracle::query_twap_price`].
+pub trait PriceOracle {
+    /// Returns the current cumulative prices of the pool, to be stored by the
+    /// caller for computing TWAPs later.
+    ///
+    /// Returns [`CwDexError::UnsupportedPoolOperation`] if the dex does not
+    /// expose cumulative prices.
+    fn query_cumulative_prices(
+        &self,
+        _deps: Deps,
+        _env: &Env,
+    ) -> Result<CumulativePricesSnapshot, CwDexError> {
+        Err(CwDexError::UnsupportedPoolOperation {
+            operation: "query_cumulative_prices".to_string(),
+        })
+    }
+
+    /// Returns the time weighted average price of `base_asset_info` in terms
+    /// of `quote_asset_info` over the last `window` seconds.
+    ///
+    /// Dexes that only expose cumulative prices compute the TWAP since the
+    /// most recent of `snapshots` taken at least `window` seconds ago, and
+    /// return [`CwDexError::TwapUnavailable`] if there is no such snapshot.
+    /// Other dexes ignore `snapshots`.
+    ///
+    /// Returns [`CwDexError::UnsupportedPoolOperation`] if the dex has no way
+    /// to compute a TWAP.
+    fn query_twap_price(
+        &self,
+        _deps: Deps,
+        _env: &Env,
+        _base_asset_info: &AssetInfo,
+        _quote_asset_info: &AssetInfo,
+        _window: u64,
+        _snapshots: &[CumulativePricesSnapshot],
+    ) -> Result<Decimal, CwDexError> {
+        Err(CwDexError::UnsupportedPoolOperation {
+            operation: "query_twap_price".to_string(),
+        })
+    }
+}
@@ -0,0 +1,42 @@
+//! Contains the `Router` trait for abstracting multi-hop swaps across pools.
+
+use cosmwasm_std::{Deps, Env, StdResult, Uint128};
+use cw_asset::{Asset, AssetInfo};
+
+use crate::error::CwDexError;
+use crate::response::CwDexResponse;
+
+/// Trait to represent a route of swaps through one or more pools.
+pub trait Router {
+    /// Swap `offer_asset` through every hop of the route.
+    ///
+    /// Returns a [`CwDexResponse`] containing the messages to execute the
+    /// whole route and the expected amount of the route's ask asset.
+    /// Implementations must enforce `min_out` on the final output of the
+    /// route rather than on each individual hop.
+    ///
+    /// Arguments:
+    /// - `offer_asset`: The asset to swap. Must match the offer asset of the
+    ///   first hop of the route.
+    /// - `min_out`: The minimum amount of the route's ask asset to receive.
+    fn swap(
+        &self,
+        deps: Deps,
+        env: &Env,
+        offer_asset: Asset,
+        min_out: Uint128,
+    ) -> Result<CwDexResponse, CwDexError>;
+
+    /// Simulates swapping `offer_asset` through every hop of the route and
+    /// returns the estimated amount of the route's ask asset.
+    ///
+    /// Arguments:
+    /// - `offer_asset`: The asset offered in the first hop of the route
+    fn simulate_swap(&self, deps: Deps, offer_asset: Asset) -> StdResult<Uint128>;
+
+    /// Returns the asset offered in the first hop of the route.
+    fn offer_asset_info(&self) -> Result<AssetInfo, CwDexError>;
+
+    /// Returns the asset received from the last hop of the route.
+    fn ask_asset_info(&self) -> Result<AssetInfo, CwDexError>;
+}
@@ -1,8 +1,9 @@
 //! Contains traits related to various forms of staking
 
+use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{Addr, Deps, Env, QuerierWrapper, Response, Uint128};
 use cw_asset::AssetList;
-use cw_utils::Duration as CwDuration;
+use cw_utils::{Duration as CwDuration, Expiration};
 
 use crate::error::CwDexError;
 
@@ -40,6 +41,14 @@ pub trait Stake: Rewards {
     /// implementation requires a CW20 allowance, the `Response` should
     /// contain messages to increase the allowance.
     fn stake(&self, deps: Deps, env: &Env, amount: Uint128) -> Result<Response, CwDexError>;
+
+    /// Query the amount of the asset currently staked by `user`. Assets that
+    /// are unbonding are not included.
+    fn query_staked_balance(
+        &self,
+        querier: &QuerierWrapper,
+        user: &Addr,
+    ) -> Result<Uint128, CwDexError>;
 }
 
 /// Defines an interface for unstaking
@@ -56,6 +65,17 @@ pub trait Unstake {
 /// A compound trait containing `Stake`, `Unstake` and `Rewards`
 pub trait Staking: Stake + Unstake + Rewards {}
 
+/// An amount of staked assets that is unbonding
+#[cw_serde]
+pub struct UnbondingPosition {
+    /// The ID of the position, if the staking implementation has one
+    pub id: Option<u64>,
+    /// The amount of the staked asset that is unbonding
+    pub amount: Uint128,
+    /// When the assets are released
+    pub release_at: Expiration,
+}
+
 /// Defines an interface for unlocking assets
 pub trait Unlock {
     /// Start unlocking `amount` of the locked asset. Depending on the
@@ -71,6 +91,16 @@ pub trait Unlock {
         env: &Env,
         amount: Uint128,
     ) -> Result<Response, CwDexError>;
+
+    /// Query the positions of `user` that are currently unlocking.
+    ///
+    /// Returns the unlocking positions, which may be empty. Positions that
+    /// have been released but not yet withdrawn are included.
+    fn query_unbonding_positions(
+        &self,
+        querier: &QuerierWrapper,
+        user: &Addr,
+    ) -> Result<Vec<UnbondingPosition>, CwDexError>;
 }
 
 /// Defines an interface for interacting with a staking module with a lockup
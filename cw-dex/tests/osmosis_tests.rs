@@ -1,8 +1,10 @@
 mod tests {
     use apollo_utils::coins::coin_from_str;
     use apollo_utils::submessages::{find_event, parse_attribute_value};
-    use cosmwasm_std::{Coin, SubMsgResponse, Uint128};
-    use cw_asset::{Asset, AssetInfo};
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{Addr, Coin, SubMsgResponse, Uint128};
+    use cw_asset::{Asset, AssetInfo, AssetList};
+    use cw_dex::CwDexCallbackMsg;
     use cw_dex_test_contract::msg::{ExecuteMsg, QueryMsg};
     use cw_dex_test_helpers::osmosis::{setup_pool_and_test_contract, OsmosisPoolType};
     use cw_dex_test_helpers::provide_liquidity;
@@ -135,6 +137,7 @@ mod tests {
         // Withdraw liquidity. We are not allowed to withdraw all liquidity on osmosis.
         let withdraw_msg = ExecuteMsg::WithdrawLiquidity {
             amount: admin_lp_token_balance.checked_sub(Uint128::one()).unwrap(),
+            min_out: AssetList::new(),
         };
         runner
             .execute_cosmos_msgs::<MsgExecuteContractResponse>(
@@ -341,7 +344,6 @@ mod tests {
         let simulate_query = QueryMsg::SimulateSwap {
             offer: offer.clone(),
             ask: ask.clone(),
-            sender: Some(contract_addr.clone()),
         };
         let expected_out = wasm.query(&contract_addr, &simulate_query).unwrap();
         let min_out = if min_out {
@@ -375,4 +377,52 @@ mod tests {
         assert_eq!(ask_balance, expected_out);
         assert_eq!(offer_balance, Uint128::zero());
     }
+
+    #[test]
+    fn test_cw_dex_callback_assert_min_out() {
+        let (runner, accs, _, contract_addr) = setup_pool_and_contract(
+            OsmosisPoolType::Basic,
+            INITIAL_TWO_POOL_LIQUIDITY.to_vec(),
+            None,
+        )
+        .unwrap();
+        let admin = &accs[0];
+
+        // Build the callback the way the pool implementations do, as a message
+        // to the contract itself
+        let mut env = mock_env();
+        env.contract.address = Addr::unchecked(&contract_addr);
+        let balance_before =
+            bank_balance_query(&runner, contract_addr.clone(), DENOM0.to_string()).unwrap();
+        let callback = CwDexCallbackMsg::AssertMinOut {
+            balances_before: vec![Asset::native(DENOM0, balance_before)].into(),
+            min_out: vec![Asset::native(DENOM0, 1_000u128)].into(),
+        }
+        .into_cosmos_msg(&env)
+        .unwrap();
+
+        // Nothing has been received yet
+        runner
+            .execute_cosmos_msgs::<MsgExecuteContractResponse>(&[callback.clone()], admin)
+            .unwrap_err();
+
+        // Tokens received by the contract before the callback is executed count
+        // towards the min out
+        bank_send(
+            &runner,
+            admin,
+            &contract_addr,
+            vec![Coin::new(1_000, DENOM0)],
+        )
+        .unwrap();
+        let events = runner
+            .execute_cosmos_msgs::<MsgExecuteContractResponse>(&[callback], admin)
+            .unwrap()
+            .events;
+        let response = SubMsgResponse { events, data: None };
+        let event = find_event(&response, "wasm-apollo/cw-dex/assert_min_out").unwrap();
+        let received: Uint128 =
+            parse_attribute_value(event, &format!("native:{}", DENOM0)).unwrap();
+        assert_eq!(received, Uint128::new(1_000));
+    }
 }
@@ -4,6 +4,7 @@ use cosmwasm_std::{
     to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult, Uint128,
 };
 use cw_asset::{Asset, AssetInfo, AssetList};
+use cw_dex::osmosis::reply::parse_lock_tokens_reply;
 use cw_dex::osmosis::{OsmosisPool, OsmosisStaking};
 use cw_dex::traits::{ForceUnlock, Pool, Stake, Unlock};
 // use cw2::set_contract_version;
@@ -49,8 +50,8 @@ pub fn execute(
         ExecuteMsg::ProvideLiquidity { assets, min_out } => {
             execute_provide_liquidity(deps, env, info, assets, min_out)
         }
-        ExecuteMsg::WithdrawLiquidity { amount } => {
-            execute_withdraw_liquidity(deps, env, info, amount)
+        ExecuteMsg::WithdrawLiquidity { amount, min_out } => {
+            execute_withdraw_liquidity(deps, env, info, amount, min_out)
         }
         ExecuteMsg::Stake { amount } => execute_stake(deps, env, info, amount),
         ExecuteMsg::Unlock { amount } => execute_unlock(deps, env, info, amount),
@@ -65,6 +66,7 @@ pub fn execute(
             ask,
             min_out,
         } => execute_swap(deps, env, offer, ask, min_out),
+        ExecuteMsg::CwDexCallback(msg) => Ok(msg.execute(deps.as_ref(), &env)?.into_response()),
     }
 }
 
@@ -77,7 +79,9 @@ pub fn execute_provide_liquidity(
 ) -> Result<Response, ContractError> {
     let pool = POOL.load(deps.storage)?;
 
-    Ok(pool.provide_liquidity(deps.as_ref(), &env, assets, min_out)?)
+    Ok(pool
+        .provide_liquidity(deps.as_ref(), &env, assets, min_out)?
+        .into_response())
 }
 
 pub fn execute_withdraw_liquidity(
@@ -85,6 +89,7 @@ pub fn execute_withdraw_liquidity(
     env: Env,
     _info: MessageInfo,
     amount: Uint128,
+    min_out: AssetList,
 ) -> Result<Response, ContractError> {
     let pool = POOL.load(deps.storage)?;
     let lp_token = Asset {
@@ -92,7 +97,9 @@ pub fn execute_withdraw_liquidity(
         amount,
     };
 
-    Ok(pool.withdraw_liquidity(deps.as_ref(), &env, lp_token)?)
+    Ok(pool
+        .withdraw_liquidity(deps.as_ref(), &env, lp_token, min_out)?
+        .into_response())
 }
 
 pub fn execute_stake(
@@ -149,7 +156,9 @@ pub fn execute_swap(
 ) -> Result<Response, ContractError> {
     let pool = POOL.load(deps.storage)?;
 
-    Ok(pool.swap(deps.as_ref(), &env, offer, ask, min_out)?)
+    Ok(pool
+        .swap(deps.as_ref(), &env, offer, ask, min_out)?
+        .into_response())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -160,25 +169,27 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::SimulateProvideLiquidity { assets } => {
             to_binary(&pool.simulate_provide_liquidity(deps, &env, assets)?.amount)
         }
-        QueryMsg::SimulateSwap { offer, ask, sender } => {
-            query_simulate_swap(deps, offer, ask, sender)
-        }
+        QueryMsg::SimulateSwap { offer, ask } => query_simulate_swap(deps, offer, ask),
     }
 }
 
-pub fn query_simulate_swap(
-    deps: Deps,
-    offer: Asset,
-    ask: AssetInfo,
-    to: Option<String>,
-) -> StdResult<Binary> {
+pub fn query_simulate_swap(deps: Deps, offer: Asset, ask: AssetInfo) -> StdResult<Binary> {
     let pool = POOL.load(deps.storage)?;
-    to_binary(&pool.simulate_swap(deps, offer, ask, to)?)
+    to_binary(&pool.simulate_swap(deps, offer, ask)?)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(_deps: DepsMut, _env: Env, _msg: Reply) -> Result<Response, ContractError> {
-    Ok(Response::default())
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let mut staking = STAKING.load(deps.storage)?;
+    match msg.id {
+        id if id == staking.reply_ids.lock_tokens => {
+            let lock_id = parse_lock_tokens_reply(msg)?;
+            staking.lock_id = Some(lock_id);
+            STAKING.save(deps.storage, &staking)?;
+            Ok(Response::new().add_attribute("lock_id", lock_id.to_string()))
+        }
+        _ => Ok(Response::default()),
+    }
 }
 
 #[cfg(test)]
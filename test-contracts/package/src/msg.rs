@@ -1,6 +1,7 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{to_binary, Coin, CosmosMsg, Uint128, WasmMsg};
 use cw_asset::{Asset, AssetInfo, AssetList};
+use cw_dex::CwDexCallbackMsg;
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -17,6 +18,7 @@ pub enum ExecuteMsg {
     },
     WithdrawLiquidity {
         amount: Uint128,
+        min_out: AssetList,
     },
     Stake {
         amount: Uint128,
@@ -36,6 +38,7 @@ pub enum ExecuteMsg {
         ask: AssetInfo,
         min_out: Uint128,
     },
+    CwDexCallback(CwDexCallbackMsg),
 }
 
 impl ExecuteMsg {
@@ -56,9 +59,5 @@ pub enum QueryMsg {
     #[returns(Uint128)]
     SimulateProvideLiquidity { assets: AssetList },
     #[returns(Uint128)]
-    SimulateSwap {
-        offer: Asset,
-        ask: AssetInfo,
-        sender: Option<String>,
-    },
+    SimulateSwap { offer: Asset, ask: AssetInfo },
 }